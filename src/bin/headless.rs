@@ -0,0 +1,48 @@
+// Runs the robot from the command line, without the browser
+// cargo run --release --no-default-features --features headless --bin headless -- --ticks 5000 --world src/worlds/world3.bin
+use frontend_wasm::headless::{run_headless, HeadlessConfig};
+use frontend_wasm::logging;
+use frontend_wasm::procedural::ProceduralConfig;
+
+fn main() {
+    let mut config = HeadlessConfig::default();
+    let mut log_level = log::LevelFilter::Warn;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ticks" => config.ticks = parse_next(&mut args),
+            "--seed" => config.seed = parse_next(&mut args),
+            // --size generates a procedural world, with the same seed as the robot
+            "--size" => config.procedural.get_or_insert_with(ProceduralConfig::default).size = parse_next(&mut args),
+            "--record" => config.record = Some(args.next().unwrap_or_else(|| usage()).into()),
+            "--telemetry" => config.telemetry = Some(args.next().unwrap_or_else(|| usage()).into()),
+            "--world" => config.world = Some(args.next().unwrap_or_else(|| usage()).into()),
+            // default level of the robot logs, like warn or debug
+            "--log" => log_level = parse_next(&mut args),
+            _ => usage(),
+        }
+    }
+    logging::init(log_level);
+    if let Some(procedural) = config.procedural.as_mut() {
+        procedural.seed = config.seed;
+    }
+
+    match run_headless(&config) {
+        Ok(report) => println!("Ran {} ticks, {} failed", report.ticks, report.failed_ticks),
+        Err(error) => {
+            eprintln!("Could not start the game: {:?}", error);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn parse_next<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>) -> T {
+    args.next()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| usage())
+}
+
+fn usage() -> ! {
+    eprintln!("usage: headless [--ticks N] [--seed N] [--world PATH | --size N] [--record PATH] [--telemetry PATH] [--log LEVEL]");
+    std::process::exit(2);
+}
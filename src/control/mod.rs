@@ -0,0 +1,144 @@
+use std::cell::Cell;
+
+/*
+    Controls of a running game, shared between the Menu and the game loop.
+    The loop asks what to do before every tick.
+ */
+#[derive(Debug, Default)]
+pub(crate) struct RunControl {
+    paused: Cell<bool>,
+    pending_steps: Cell<usize>,
+    tick_time: Cell<u32>,
+    tick_limit: Cell<Option<usize>>,
+    stopped: Cell<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RunAction {
+    Tick,
+    Wait,
+    Stop,
+}
+
+impl RunControl {
+    pub(crate) fn new(tick_time: u32, tick_limit: Option<usize>) -> Self {
+        Self {
+            tick_time: Cell::new(tick_time),
+            tick_limit: Cell::new(tick_limit),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn pause(&self) {
+        self.paused.set(true);
+    }
+    pub(crate) fn resume(&self) {
+        self.paused.set(false);
+        self.pending_steps.set(0);
+    }
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.get()
+    }
+    //runs one more tick while paused
+    pub(crate) fn step(&self) {
+        if self.paused.get() {
+            self.pending_steps.set(self.pending_steps.get() + 1);
+        }
+    }
+
+    pub(crate) fn tick_time(&self) -> u32 {
+        self.tick_time.get()
+    }
+    pub(crate) fn set_tick_time(&self, tick_time: u32) {
+        self.tick_time.set(tick_time);
+    }
+    pub(crate) fn tick_limit(&self) -> Option<usize> {
+        self.tick_limit.get()
+    }
+    pub(crate) fn set_tick_limit(&self, tick_limit: Option<usize>) {
+        self.tick_limit.set(tick_limit);
+    }
+
+    //control of the game started after this one, with the same delay and limit but not paused or stopped
+    pub(crate) fn next_game(&self) -> Self {
+        Self::new(self.tick_time.get(), self.tick_limit.get())
+    }
+
+    pub(crate) fn stop(&self) {
+        self.stopped.set(true);
+    }
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.get()
+    }
+
+    pub(crate) fn next(&self, ticks_done: usize) -> RunAction {
+        if self.stopped.get() || self.tick_limit.get().is_some_and(|limit| ticks_done >= limit) {
+            self.stopped.set(true);
+            return RunAction::Stop;
+        }
+        if !self.paused.get() {
+            return RunAction::Tick;
+        }
+        match self.pending_steps.get() {
+            0 => RunAction::Wait,
+            steps => {
+                self.pending_steps.set(steps - 1);
+                RunAction::Tick
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pause_and_step(){
+        let control = RunControl::new(0, None);
+        assert_eq!(control.next(0), RunAction::Tick);
+        control.pause();
+        assert_eq!(control.next(1), RunAction::Wait);
+        control.step();
+        control.step();
+        assert_eq!(control.next(1), RunAction::Tick);
+        assert_eq!(control.next(2), RunAction::Tick);
+        assert_eq!(control.next(3), RunAction::Wait);
+        control.resume();
+        assert_eq!(control.next(3), RunAction::Tick);
+    }
+
+    #[test]
+    fn test_step_only_while_paused(){
+        let control = RunControl::new(0, None);
+        control.step();
+        control.pause();
+        assert_eq!(control.next(0), RunAction::Wait);
+    }
+
+    #[test]
+    fn test_tick_limit_and_stop(){
+        let control = RunControl::new(0, Some(2));
+        assert_eq!(control.next(1), RunAction::Tick);
+        assert_eq!(control.next(2), RunAction::Stop);
+        assert!(control.is_stopped());
+
+        let control = RunControl::new(0, None);
+        control.stop();
+        assert_eq!(control.next(0), RunAction::Stop);
+    }
+
+    #[test]
+    fn test_next_game_starts_running(){
+        let control = RunControl::new(300, Some(2));
+        control.pause();
+        assert_eq!(control.next(2), RunAction::Stop);
+
+        let next = control.next_game();
+        assert!(!next.is_stopped());
+        assert!(!next.is_paused());
+        assert_eq!(next.tick_time(), 300);
+        assert_eq!(next.next(0), RunAction::Tick);
+        assert_eq!(next.next(2), RunAction::Stop);
+    }
+}
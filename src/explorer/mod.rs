@@ -2,8 +2,6 @@ use std::collections::{HashSet};
 use std::rc::Rc;
 use std::cell::RefCell;
 use charting_tools::charted_coordinate::ChartedCoordinate;
-use charting_tools::charted_paths::ChartedPaths;
-use charting_tools::ChartingTools;
 use robotics_lib::interface::{go, look_at_sky, robot_map, where_am_i, Direction};
 use robotics_lib::runner::{Runnable};
 
@@ -13,26 +11,53 @@ use robotics_lib::world::{World};
 use rust_eze_tomtom::TomTom;
 use crate::biomes::{detect_biome, is_weather_gonna_be_nice_n, is_weather_nice};
 use crate::explorer::ExplorerError::{FailedToGo, FrontierNotAccessible};
-use crate::fast_paths::{self, go_to_coordinates};
-use crate::interface::Jerry;
-use crate::road_builder::generate_road_builders;
-use crate::sector_analyzer::{analyzer_execute, new_sector_analyzer};
-use crate::utils::{calculate_spatial_index, robot_map_slice, ActiveRegion, JerryStatus, Mission};
+use crate::fast_paths::{self, Path, PathCost};
+use crate::jerry::Jerry;
+use crate::logging;
+use crate::sector_analyzer::{analyze_and_plan, new_sector_analyzer};
+use crate::utils::{calculate_spatial_index, robot_map_slice, JerryStatus, Mission, MissionData, MissionKind, MissionOutcome};
 
 use crate::utils::MissionStatus::{Active, Completed, New};
 use rust_and_furious_dynamo::dynamo::Dynamo;
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::snapshot::charted;
 
+// the explorer walks the same ground again and again, its paths should stay cheap when the weather turns
+const FORECAST_TICKS: usize = 10;
+// and they go around the content, which is left for the road builders
+const CONTENT_PENALTY: usize = 2;
+
+fn explorer_cost() -> PathCost {
+    PathCost::AvoidContent(Box::new(PathCost::Forecast(FORECAST_TICKS)), CONTENT_PENALTY)
+}
+
+pub(crate) struct Explorer;
+
+impl MissionKind for Explorer {
+    fn name(&self) -> &'static str {
+        "Explore"
+    }
+    fn recharges(&self) -> bool {
+        true
+    }
+    fn execute(&self, jerry: &mut Jerry, world: &mut World, mission_index: usize) -> Result<MissionOutcome, JerryStatus> {
+        explorer_execute(jerry, world, mission_index)
+    }
+}
 
 pub fn new_explorer(jerry: &mut Jerry, world: &mut World, spatial_index: usize) -> Mission{
     let (frontier, frontier_hs) = initialize_frontier(jerry, world);
-    Mission{
-        name: "Explore".to_string(),
-        status: New,
-        additional_data: Some(Box::new(ExplorerData{frontier, frontier_hs, spatial_index, robot_moved: false})),
-    }
+    Mission::new(New, MissionData::Explorer(ExplorerData{frontier, frontier_hs, spatial_index, robot_moved: false}))
+}
+//the explorer state of the mission at the given index
+fn explorer_data(jerry: &Jerry, mission_index: usize) -> Result<&ExplorerData, JerryStatus>{
+    jerry.missions.get(mission_index).and_then(Mission::explorer).ok_or(JerryStatus::MissionExecutionError)
 }
-pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usize) -> Result<(), JerryStatus>{
+fn explorer_data_mut(jerry: &mut Jerry, mission_index: usize) -> Result<&mut ExplorerData, JerryStatus>{
+    jerry.missions.get_mut(mission_index).and_then(Mission::explorer_mut).ok_or(JerryStatus::MissionExecutionError)
+}
+pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usize) -> Result<MissionOutcome, JerryStatus>{
     //initialize the frontier in the beginning of the simulation
     let mut counter = 0;
     let mut new_tick = true;
@@ -40,8 +65,7 @@ pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usi
         //println!("");
         //if the robot has less than 100 energy on the new tick, use the dynamo tool with the probability of 0.8
         if new_tick && jerry.get_energy().get_energy_level() < 100{
-            let mut rng = rand::thread_rng();
-            let probability = rng.gen_range(0.0..1.0);
+            let probability = jerry.rng.gen_range(0.0..1.0);
             if probability < 0.8{
                 *jerry.get_energy_mut() = Dynamo::update_energy();
             }
@@ -53,7 +77,7 @@ pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usi
         //let time_initial = std::time::Instant::now();
         
 
-        let map = robot_map(world).unwrap();
+        let map = robot_map(world).ok_or(JerryStatus::MissionExecutionError)?;
         let (robot_view, position) = where_am_i(jerry, world);
 
         //if the current weather is not nice for a current biome
@@ -87,10 +111,7 @@ pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usi
             //println!("took {:?} to update web page ", time_web.elapsed());
         }
 
-        let mut mission = jerry.missions.get_mut(mission_index);
-        let data: &mut ExplorerData = mission.as_mut().unwrap()
-        .additional_data.as_mut()
-        .unwrap().downcast_mut().unwrap();
+        let data = explorer_data_mut(jerry, mission_index)?;
         let robot_moved = data.robot_moved;
         data.robot_moved = false;
         //update the frontier if the robot has moved
@@ -100,70 +121,28 @@ pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usi
 
         counter += 1;
         new_tick = false;
-        let mut charted_paths  = ChartingTools::tool::<ChartedPaths>()
-            .expect("too many tools used!");
-        charted_paths.init(&map, world);
 
         //Doing the mission
-        let mut mission = jerry.missions.get_mut(mission_index);
-        let data: &ExplorerData = mission.as_ref().unwrap()
-        .additional_data.as_ref()
-        .unwrap().downcast_ref().unwrap();
-        //update the frontier if the robot has moved
-        let spatial_index = data.spatial_index.clone();
+        let data = explorer_data(jerry, mission_index)?;
+        let spatial_index = data.spatial_index;
 
         //if the frontier is empty, the robot should stop executing the mission
         //and execute the analyzer
         if data.frontier.is_empty(){
-            mission.as_mut().unwrap().status = Completed;
-            let new_analyzer = new_sector_analyzer(spatial_index, jerry.world_dim);
-            println!("Analyzing sector {}", spatial_index);
-            let data = new_analyzer.additional_data.as_ref().unwrap().downcast_ref::<ActiveRegion>().unwrap();
-            let (tl, br) = (data.top_left, data.bottom_right);
-            let sector_data = analyzer_execute(world, tl, br);
-            println!("Sector data: {:?}", sector_data);
-            //here we check if the sector is fully explored
-            if let None = sector_data.nodes.iter().find(|node|
-                 !jerry.road_tiles.contains(&ChartedCoordinate(node.0, node.1))){
-                    println!("Sector {} is fully explored", spatial_index);
-                return Ok(());
-            }
-            generate_road_builders(jerry, world, sector_data);
-            jerry.active_region.top_left = tl;
-            jerry.active_region.bottom_right = br;
-            return Ok(());
+            complete_and_analyze(jerry, world, mission_index, spatial_index)?;
+            return Ok(MissionOutcome::Completed);
         }
 
         //Debugging
         //let time_choose = std::time::Instant::now();
         //print!("Choosing tile ");
 
-        let selected_tile = choose_frontier_tile(jerry, charted_paths, mission_index);
+        let selected_tile = choose_frontier_tile(jerry, world, &map, mission_index);
         //if the frontier is not accessible, the robot should stop executing the mission
         if selected_tile.is_err(){
-            println!("FRONTIER NOT ACCESSIBLE");
-            let mut mission = jerry.missions.get_mut(mission_index);
-            let data: &ExplorerData = mission.as_ref().unwrap().additional_data.as_ref().unwrap().downcast_ref().unwrap();
-            mission.as_mut().unwrap().status = Completed;
-
-            let new_analyzer = new_sector_analyzer(spatial_index, jerry.world_dim);
-            println!("Analyzing sector {}", spatial_index);
-            let data = new_analyzer.additional_data.as_ref().unwrap().downcast_ref::<ActiveRegion>().unwrap();
-            let (tl, br) = (data.top_left, data.bottom_right);
-            let sector_data = analyzer_execute(world, tl, br);
-            println!("Sector data: {:?}", sector_data);
-
-            //here we check if the sector is fully explored
-            if let None = sector_data.nodes.iter().find(|node|
-                !jerry.road_tiles.contains(&ChartedCoordinate(node.0, node.1))){
-                   println!("Sector {} is fully explored", spatial_index);
-               return Ok(());
-           }
-            generate_road_builders(jerry, world, sector_data);
-            jerry.active_region.top_left = tl;
-            jerry.active_region.bottom_right = br;
-
-            return Ok(());
+            log::warn!(target: logging::EXPLORER, "Frontier of sector {} not accessible", spatial_index);
+            complete_and_analyze(jerry, world, mission_index, spatial_index)?;
+            return Ok(MissionOutcome::Completed);
         }
 
         //Debugging
@@ -173,10 +152,10 @@ pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usi
 
 
         //try to reach the selected tile or throw an error if the cost to get there is > 1000 or > than the robot has
-        let selected_tile = selected_tile.unwrap().0;
+        let (selected_tile, selected_path) = selected_tile.unwrap();
+        let selected_tile_cost = selected_path.cost;
         //print!("From {:?} to {:?}", jerry.get_coordinate(), selected_tile)
-        
-        let selected_tile_cost = selected_tile.1;
+
         //go to an intermediate tile if the selected tile is too expensive
         if selected_tile_cost > 1000{
             let current_coordinate = (jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col());
@@ -197,16 +176,16 @@ pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usi
             }
             //take the slice of the robot map around the intermediate tile
             for n in 0..map.len(){
-                let map_slice = robot_map_slice_n(jerry, &map, n).unwrap();
+                let Some(map_slice) = robot_map_slice_n(jerry, &map, n) else {
+                    continue;
+                };
                 'outer: for (i, row) in map_slice.iter().enumerate(){
                     for (j, tile) in row.iter().enumerate(){
 
                         //if the tile is in the cache and it's walkable, add it to the frontier to reach it on the next iteration
                         if let Some(tile) = tile{
                             if tile.tile_type.properties().walk(){
-                                let mut mission = jerry.missions.get_mut(mission_index);
-                                let data: &mut ExplorerData = mission.as_mut().unwrap()
-                                .additional_data.as_mut().unwrap().downcast_mut().unwrap();
+                                let data = explorer_data_mut(jerry, mission_index)?;
                                 data.frontier.push(ChartedCoordinate(i, j));
                                 data.frontier_hs.insert(ChartedCoordinate(i, j));
                                 break 'outer;
@@ -219,14 +198,12 @@ pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usi
         if selected_tile_cost > jerry.get_energy().get_energy_level(){
             return Err(JerryStatus::LowEnergyWarning);
         }
-        if let Err(error) =  go_to_frontier(jerry, &map, world, selected_tile, mission_index){
+        if let Err(error) =  go_to_frontier(jerry, world, selected_tile, selected_path, mission_index){
             
             match error{
                 //if the go interface failed (tool error), try to move to a tile next to the current one
                 | FailedToGo => {
-                    let mut mission = jerry.missions.get_mut(mission_index);
-                    let data: &mut ExplorerData = mission.as_mut().unwrap().additional_data.as_mut().unwrap().downcast_mut().unwrap();
-                    data.robot_moved = true;
+                    explorer_data_mut(jerry, mission_index)?.robot_moved = true;
                     continue;
                 }
                 //if not enough energy, stop executing the mission and wait to recharge
@@ -239,20 +216,34 @@ pub fn explorer_execute(jerry: &mut Jerry, world: &mut World, mission_index: usi
             return Err(JerryStatus::MissionExecutionError);
         }
         else{
-            let mut mission = jerry.missions.get_mut(mission_index);
-            let data: &mut ExplorerData = mission.as_mut().unwrap().additional_data.as_mut().unwrap().downcast_mut().unwrap();
-            data.robot_moved = true;
+            explorer_data_mut(jerry, mission_index)?.robot_moved = true;
             continue;
         }
     }
-    Err(JerryStatus::CallingNextTick)
+    Ok(MissionOutcome::InProgress)
+}
+//marks the explorer as completed and runs the analyzer on its sector
+fn complete_and_analyze(jerry: &mut Jerry, world: &mut World, mission_index: usize, spatial_index: usize) -> Result<(), JerryStatus>{
+    if let Some(mission) = jerry.missions.get_mut(mission_index){
+        mission.status = Completed;
+    }
+    let new_analyzer = new_sector_analyzer(spatial_index, jerry.world_dim);
+    log::info!(target: logging::EXPLORER, "Analyzing sector {}", spatial_index);
+    match new_analyzer.region(){
+        Some(region) => analyze_and_plan(jerry, world, region),
+        None => Ok(()),
+    }
 }
 //initialize the frontier when adding the new explorer mission
 pub fn initialize_frontier(jerry: &mut Jerry, world: &mut World) -> (Vec<ChartedCoordinate>, HashSet<ChartedCoordinate>){
     let (_, spawn_coordinates) = where_am_i(jerry, world);
-    let map = robot_map(world).unwrap();
     let mut frontier: Vec<ChartedCoordinate> = Vec::new();
     let mut frontier_hs = HashSet::new();
+    //without a map the frontier is empty and the explorer goes straight to the analyzer
+    let Some(map) = robot_map(world) else {
+        log::warn!(target: logging::EXPLORER, "No robot map to initialize the frontier");
+        return (frontier, frontier_hs);
+    };
     let row = spawn_coordinates.0;
     let col = spawn_coordinates.1;
     let rows = map.len();
@@ -294,45 +285,39 @@ fn is_frontier(map: &Vec<Vec<Option<Tile>>>, coordinate: (usize, usize)) -> bool
     }
     false
 }
-fn choose_frontier_tile(jerry: &mut Jerry, tool: ChartedPaths, mission_index: usize) -> Result<(ChartedCoordinate, u32), ExplorerError>{
+fn choose_frontier_tile(jerry: &mut Jerry, world: &World, map: &Vec<Vec<Option<Tile>>>, mission_index: usize) -> Result<(ChartedCoordinate, Path), ExplorerError>{
 
-    let mission = jerry.missions.get(mission_index);
-    let robot_coord = (jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col());
-    let robot_charted_coord = ChartedCoordinate(robot_coord.0, robot_coord.1);
-    let data: &ExplorerData = mission.as_ref().unwrap().additional_data.as_ref().unwrap().downcast_ref().unwrap();
+    let data = explorer_data(jerry, mission_index).map_err(|_| FrontierNotAccessible)?;
+    let frontier: Vec<(usize, usize)> = data.frontier.iter()
+        .filter(|coord| data.frontier_hs.contains(coord))
+        .map(|coord| (coord.0, coord.1))
+        .collect();
+    //one search from the robot gives the cost of every frontier tile, and the way to the chosen one
+    let field = fast_paths::robot_field(jerry, world, map, explorer_cost());
+    let costs: Vec<Option<usize>> = frontier.iter().map(|coord| field.cost(*coord)).collect();
 
     //define the search depth for the frontier
-    let search_depth = if data.frontier.len() > 10 { 10 } else { data.frontier.len() };
-    let mut frontier_accessible = false;
-    let mut start = data.frontier.len();
-    let mut candidate_cost = 0;
+    let search_depth = if frontier.len() > 10 { 10 } else { frontier.len() };
+    let mut start = frontier.len();
     //iterate first over the last 10 added frontier tiles then 20 then 30 and so on
     while start > 0{
         let end = start;
         start = start.saturating_sub(search_depth);
 
-        //candidate is the tile with the minimum cost to go to
-        //if the cost is u32::MAX, the tile is not accessible
-        let candidate = data.frontier[start..end].iter().rev()
-        .filter(|coord| data.frontier_hs.contains(coord))
-        .min_by_key(|coord|{
-            let cost = tool.shortest_path_cost_a_star(robot_charted_coord, **coord).unwrap_or(u32::MAX);
-            if cost != u32::MAX{
-                frontier_accessible = true;
-            }
-            candidate_cost = cost;
-            cost
-        });
-        //return the accessible tile with the minimum cost
-        if frontier_accessible{
-            return Ok((ChartedCoordinate(candidate.unwrap().0, candidate.unwrap().1), candidate_cost));
+        //candidate is the accessible tile with the minimum cost to go to, the newest one if more cost the same
+        let candidate = (start..end).rev()
+        .filter_map(|index| costs[index].map(|cost| (index, cost)))
+        .min_by_key(|(_, cost)| *cost);
+        if let Some((index, _)) = candidate{
+            let path = field.path_to(frontier[index]).ok_or(FrontierNotAccessible)?;
+            return Ok((ChartedCoordinate(frontier[index].0, frontier[index].1), path));
         }
     }
     return Err(FrontierNotAccessible);
 }
-fn go_to_frontier(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, 
+fn go_to_frontier(jerry: &mut Jerry, 
                 world: &mut World, frontier_coordinate: ChartedCoordinate,
-                mission_index: usize)
+                path: Path, mission_index: usize)
                 ->Result<(), ExplorerError>{
 
     let jerry_coordinate = ChartedCoordinate(jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col());
@@ -355,12 +340,11 @@ fn go_to_frontier(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>,
         }
     }
     //is a frontier tile in the area nxn around the robot?
-    //if yes, use fast_paths to go to it without processing the whole map
+    //if yes, walk the path of the search that chose it
     //can also try other values for n
     let n = 9;
     if is_within_n(jerry_coordinate, frontier_coordinate, n){
-        if let Ok(_) = fast_paths::go_to_coordinates
-        (jerry, map, world, false, (frontier_coordinate.0, frontier_coordinate.1), n){
+        if let Ok(_) = fast_paths::walk_path(jerry, world, path){
             remove_tile_from_frontier(jerry, frontier_coordinate, mission_index);
             return Ok(());
         }
@@ -413,9 +397,8 @@ fn update_frontier(jerry: &mut Jerry, world: &mut World, map: &Vec<Vec<Option<Ti
             //check if there's a mission for the spatial index of the tile that has the status New
             //in this case we don't need to initialize a new mission
             let jerry_immut = jerry.borrow();
-            let data: &ExplorerData = jerry_immut.missions.get(mission_index).unwrap().additional_data.as_ref().unwrap().downcast_ref().unwrap();
             let mission_exists = jerry_immut.missions.iter().any(|mission| {
-                if let Some(explorer_data) = mission.additional_data.as_ref().unwrap().downcast_ref::<ExplorerData>(){
+                if let Some(explorer_data) = mission.explorer(){
                     explorer_data.spatial_index == spatial_index && ((mission.status == New) || (mission.status == Active))
                 }
                 else{
@@ -440,15 +423,17 @@ fn update_frontier(jerry: &mut Jerry, world: &mut World, map: &Vec<Vec<Option<Ti
             if !mission_exists && new_sector_unknown{
                 let jerry_mut = jerry.clone();
                 let new_mission = new_explorer(&mut jerry_mut.borrow_mut(), world, spatial_index);
-                println!("New frontier {:?}", new_mission.additional_data.as_ref().unwrap().downcast_ref::<ExplorerData>().unwrap().frontier_hs);
+                if let Some(explorer_data) = new_mission.explorer(){
+                    log::trace!(target: logging::EXPLORER, "New frontier {:?}", explorer_data.frontier_hs);
+                }
                 jerry_mut.borrow_mut().missions.push_back(new_mission);
-                println!("New mission \"Explore\" for spatial index {}", spatial_index);
+                log::info!(target: logging::EXPLORER, "New mission \"Explore\" for spatial index {}", spatial_index);
 
             }
             
             //check if new mission exists for the spatial index of the tile
             let new_mission_exists = jerry_c.borrow().missions.iter().any(|mission| {
-                if let Some(explorer_data) = mission.additional_data.as_ref().unwrap().downcast_ref::<ExplorerData>(){
+                if let Some(explorer_data) = mission.explorer(){
                     explorer_data.spatial_index == spatial_index && (mission.status == New)
                 }
                 else{
@@ -457,24 +442,26 @@ fn update_frontier(jerry: &mut Jerry, world: &mut World, map: &Vec<Vec<Option<Ti
             });
             if new_mission_exists && new_sector_unknown{
                 let mut jerry_c = jerry_c.borrow_mut();
-                let existing_mission_index = jerry_c.missions.iter().position(|mission| {
-                    if let Some(explorer_data) = mission.additional_data.as_ref().unwrap().downcast_ref::<ExplorerData>(){
+                let existing_mission = jerry_c.missions.iter_mut().find(|mission| {
+                    if let Some(explorer_data) = mission.explorer(){
                         explorer_data.spatial_index == spatial_index && (mission.status == New)
                     }
                     else{
                         false
                     }
-                }).unwrap();
-                let mut mission = jerry_c.missions.get_mut(existing_mission_index).unwrap();
-                let data: &mut ExplorerData = mission.additional_data.as_mut().unwrap().downcast_mut().unwrap();
-                if !data.frontier_hs.contains(&tile_coord) && is_frontier(map, (tile_coord.0, tile_coord.1)){
-                    data.frontier.push(tile_coord);
-                    data.frontier_hs.insert(tile_coord);
+                });
+                if let Some(data) = existing_mission.and_then(Mission::explorer_mut){
+                    if !data.frontier_hs.contains(&tile_coord) && is_frontier(map, (tile_coord.0, tile_coord.1)){
+                        data.frontier.push(tile_coord);
+                        data.frontier_hs.insert(tile_coord);
+                    }
                 }
             }
             
             let mut jerry_mut = jerry_c.borrow_mut();
-            let data: &mut ExplorerData = jerry_mut.missions.get_mut(mission_index).unwrap().additional_data.as_mut().unwrap().downcast_mut().unwrap();
+            let Ok(data) = explorer_data_mut(&mut jerry_mut, mission_index) else{
+                return;
+            };
             
             //add a tile to the frontier if it's not already there
             if !data.frontier_hs.contains(&tile_coord) {
@@ -494,11 +481,12 @@ fn update_frontier(jerry: &mut Jerry, world: &mut World, map: &Vec<Vec<Option<Ti
     }
 }
 fn remove_tile_from_frontier(jerry: &mut Jerry, tile: ChartedCoordinate, mission_index: usize){
-    let mut mission = jerry.missions.get_mut(mission_index);
-    let data: &mut ExplorerData = mission.as_mut().unwrap().additional_data.as_mut().unwrap().downcast_mut().unwrap();
-    let index = data.frontier.iter().position(|x| *x == tile).unwrap();
-    let _ = data.frontier.remove(index);
-    let _ = data.frontier_hs.remove(&tile);
+    if let Ok(data) = explorer_data_mut(jerry, mission_index){
+        if let Some(index) = data.frontier.iter().position(|x| *x == tile){
+            let _ = data.frontier.remove(index);
+        }
+        let _ = data.frontier_hs.remove(&tile);
+    }
 }
 pub(crate) fn is_adjacent(a: ChartedCoordinate, b: ChartedCoordinate) -> bool{
     if (a.0 == b.0 && (a.1 == b.1 + 1 || a.1 == b.1.saturating_sub(1))) || (a.1 == b.1 && (a.0 == b.0 + 1 || a.0 == b.0.saturating_sub(1))){
@@ -542,8 +530,11 @@ pub(crate) fn robot_map_slice_n
     );
     robot_map_slice(robot_map, top_left, bottom_right)
 }
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ExplorerData{
+    #[serde(with = "charted")]
     pub frontier: Vec<ChartedCoordinate>,
+    #[serde(with = "charted")]
     pub frontier_hs: HashSet<ChartedCoordinate>,
     pub spatial_index: usize,
     pub robot_moved: bool,
@@ -0,0 +1,259 @@
+use std::collections::VecDeque;
+
+use robotics_lib::world::environmental_conditions::WeatherType;
+use robotics_lib::world::tile::{Tile, TileType};
+
+use super::search::DistanceField;
+use super::PathCost;
+
+// the robot asks from where it is, with the cost of its mission and the energy the inspector shows
+const FIELD_CAPACITY: usize = 4;
+
+/*
+    Everything a distance field was computed from, other than the map.
+    Every field covers the whole known map, a search in a smaller window is a lookup in it.
+    The models price the tiles with the weather, and the forecast ones with the weather to come,
+    so the same weathers give the same field whatever the tick.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FieldKey {
+    pub(crate) source: (usize, usize),
+    pub(crate) cost: PathCost,
+    pub(crate) weather: Vec<WeatherType>,
+}
+
+/*
+    Distance fields computed for the robot, kept until a tile they depend on changes.
+    The changes come from the events and from the tiles the map tracker finds changed,
+    so a field is still good after the robot destroyed, put or discovered something far from the paths it knows.
+    The active teleports the tracker found are kept here too, so a search does not look for them in the map.
+ */
+#[derive(Debug, Default)]
+pub(crate) struct PathCache {
+    fields: VecDeque<(FieldKey, DistanceField)>,
+    touched: Vec<(usize, usize)>,
+    //sorted, the search jumps to them in this order
+    teleports: Vec<(usize, usize)>,
+    hits: usize,
+    misses: usize,
+}
+
+impl PathCache {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+    pub(crate) fn touch(&mut self, position: (usize, usize)) {
+        self.touched.push(position);
+    }
+    //the robot moved, the tiles around it may have been discovered
+    pub(crate) fn touch_around(&mut self, position: (usize, usize)) {
+        for row in position.0.saturating_sub(1)..=position.0 + 1 {
+            for col in position.1.saturating_sub(1)..=position.1 + 1 {
+                self.touched.push((row, col));
+            }
+        }
+    }
+    /*
+        The map tracker found the tile changed.
+        A new teleport can shorten every path that reaches another one, so the fields are all dropped.
+     */
+    pub(crate) fn tile_changed(&mut self, position: (usize, usize), tile: Option<&Tile>) {
+        self.touch(position);
+        let is_teleport = matches!(tile, Some(tile) if tile.tile_type == TileType::Teleport(true));
+        match (self.teleports.binary_search(&position), is_teleport) {
+            (Err(index), true) => {
+                self.teleports.insert(index, position);
+                self.fields.clear();
+            }
+            (Ok(index), false) => {
+                self.teleports.remove(index);
+                self.fields.clear();
+            }
+            _ => (),
+        }
+    }
+    pub(crate) fn teleports(&self) -> &[(usize, usize)] {
+        &self.teleports
+    }
+    pub(crate) fn hits(&self) -> usize {
+        self.hits
+    }
+    pub(crate) fn misses(&self) -> usize {
+        self.misses
+    }
+
+    //drops the fields that one of the touched tiles could change
+    fn invalidate(&mut self) {
+        if self.touched.is_empty() {
+            return;
+        }
+        let touched = std::mem::take(&mut self.touched);
+        self.fields.retain(|(_, field)| !touched.iter().any(|position| field.depends_on(*position)));
+    }
+
+    //the field for the key, only computed if there is no good one already
+    pub(crate) fn field(&mut self, key: FieldKey, compute: impl FnOnce(&[(usize, usize)]) -> DistanceField) -> &DistanceField {
+        self.invalidate();
+        match self.fields.iter().position(|(cached, _)| *cached == key) {
+            Some(index) => {
+                self.hits += 1;
+                //the last used field is the last to go
+                let entry = self.fields.remove(index).unwrap();
+                self.fields.push_back(entry);
+            }
+            None => {
+                self.misses += 1;
+                if self.fields.len() == FIELD_CAPACITY {
+                    self.fields.pop_front();
+                }
+                let field = compute(&self.teleports);
+                self.fields.push_back((key, field));
+            }
+        }
+        &self.fields.back().unwrap().1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fast_paths::search::tests::{grid, ElevationCost};
+    use crate::fast_paths::search::{distance_field, Window};
+    use crate::map_tracker::MapTracker;
+
+    fn key(source: (usize, usize)) -> FieldKey {
+        FieldKey { source, cost: PathCost::Energy, weather: vec![WeatherType::Sunny] }
+    }
+
+    #[test]
+    fn test_field_kept_until_a_tile_it_depends_on_changes(){
+        //the robot is closed in the left part of the map
+        let map = grid(&[
+            "..#..",
+            "..#..",
+            "..#..",
+            "###..",
+            ".....",
+        ]);
+        let mut cache = PathCache::new();
+        let compute = |teleports: &[(usize, usize)]| distance_field(&Window::full(&map), (0, 0), &ElevationCost, teleports, 0);
+        assert_eq!(cache.field(key((0, 0)), compute).cost((2, 1)), Some(3));
+        assert_eq!(cache.field(key((0, 0)), compute).cost((4, 4)), None);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        //far from every tile the robot can reach
+        cache.touch((4, 4));
+        cache.field(key((0, 0)), compute);
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+
+        //the wall next to the reachable tiles could be gone
+        cache.touch((1, 2));
+        cache.field(key((0, 0)), compute);
+        assert_eq!((cache.hits(), cache.misses()), (2, 2));
+
+        //another source is another field, the first one is still there
+        cache.field(key((1, 1)), |teleports| distance_field(&Window::full(&map), (1, 1), &ElevationCost, teleports, 0));
+        assert_eq!(cache.misses(), 3);
+        cache.field(key((0, 0)), compute);
+        assert_eq!((cache.hits(), cache.misses()), (3, 3));
+    }
+
+    #[test]
+    fn test_field_dropped_when_an_unknown_neighbour_is_discovered(){
+        //the robot reaches the tiles around the unknown one on top, not the walled corner
+        let before = grid(&[
+            "...?.",
+            ".....",
+            "..###",
+            "..#..",
+            "..#.?",
+        ]);
+        let grass = grid(&["."])[0][0].clone();
+        let mut tracker = MapTracker::new();
+        tracker.diff(&before);
+        let mut cache = PathCache::new();
+        let compute = |teleports: &[(usize, usize)]| distance_field(&Window::full(&before), (0, 0), &ElevationCost, teleports, 0);
+        assert_eq!(cache.field(key((0, 0)), compute).cost((0, 3)), None);
+
+        //a tool discovers the corner, no path the robot knows goes near it
+        let mut after = before.clone();
+        after[4][4] = grass.clone();
+        tracker.resync();
+        for (row, col, tile) in tracker.diff(&after) {
+            cache.tile_changed((row, col), tile.as_ref());
+        }
+        cache.field(key((0, 0)), compute);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        //then the tile between the reached ones, which opens a shorter way
+        after[0][3] = grass;
+        tracker.resync();
+        for (row, col, tile) in tracker.diff(&after) {
+            cache.tile_changed((row, col), tile.as_ref());
+        }
+        let field = cache.field(key((0, 0)), |teleports| distance_field(&Window::full(&after), (0, 0), &ElevationCost, teleports, 0));
+        assert_eq!(field.cost((0, 3)), Some(3));
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+    }
+
+    #[test]
+    fn test_new_teleport_drops_the_fields(){
+        //the hole in the middle is out of reach, but not of a jump
+        let before = grid(&[
+            "T....",
+            ".###.",
+            ".#?#.",
+            ".###.",
+            ".....",
+        ]);
+        let mut tracker = MapTracker::new();
+        let mut cache = PathCache::new();
+        for (row, col, tile) in tracker.diff(&before) {
+            cache.tile_changed((row, col), tile.as_ref());
+        }
+        assert_eq!(cache.teleports(), &[(0, 0)]);
+        cache.field(key((4, 4)), |teleports| distance_field(&Window::full(&before), (4, 4), &ElevationCost, teleports, 5));
+
+        let mut after = before.clone();
+        after[2][2] = before[0][0].clone();
+        tracker.resync();
+        for (row, col, tile) in tracker.diff(&after) {
+            cache.tile_changed((row, col), tile.as_ref());
+        }
+        assert_eq!(cache.teleports(), &[(0, 0), (2, 2)]);
+        let field = cache.field(key((4, 4)), |teleports| distance_field(&Window::full(&after), (4, 4), &ElevationCost, teleports, 5));
+        assert_eq!(field.cost((2, 2)), Some(8 + 5));
+        assert_eq!((cache.hits(), cache.misses()), (0, 2));
+    }
+
+    #[test]
+    fn test_hit_rate_of_a_waiting_robot(){
+        let map = grid(&[
+            ".....",
+            ".....",
+            ".....",
+        ]);
+        let map = &map;
+        let compute = |source: (usize, usize)| move |teleports: &[(usize, usize)]| distance_field(&Window::full(map), source, &ElevationCost, teleports, 0);
+        let mission = |source: (usize, usize), weather: Vec<WeatherType>| FieldKey { source, cost: PathCost::Forecast(2), weather };
+        let mut cache = PathCache::new();
+
+        //the robot waits for energy for a few ticks with the same forecast, the inspector shows the energy cost of a tile
+        for _ in 0..5 {
+            cache.field(mission((1, 1), vec![WeatherType::Sunny; 3]), compute((1, 1)));
+            cache.field(key((1, 1)), compute((1, 1)));
+        }
+        assert_eq!((cache.hits(), cache.misses()), (8, 2));
+
+        //the rain to come changes the forecast prices, not the ones of now
+        cache.field(mission((1, 1), vec![WeatherType::Sunny, WeatherType::Rainy, WeatherType::Sunny]), compute((1, 1)));
+        cache.field(key((1, 1)), compute((1, 1)));
+        assert_eq!((cache.hits(), cache.misses()), (9, 3));
+
+        //then it moves and the tiles around it may be new
+        cache.touch_around((1, 2));
+        cache.field(key((1, 2)), compute((1, 2)));
+        cache.field(key((1, 2)), compute((1, 2)));
+        assert_eq!((cache.hits(), cache.misses()), (10, 4));
+    }
+}
@@ -0,0 +1,295 @@
+use ohcrab_weather::weather_tool::WeatherPredictionTool;
+use robotics_lib::interface::look_at_sky;
+use robotics_lib::utils::calculate_cost_go_with_environment;
+use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use robotics_lib::world::World;
+
+use crate::jerry::Jerry;
+
+pub(crate) use super::search::CostModel;
+
+/*
+    The cost model a call site wants for its paths.
+    The models are built again for every search, the weather may have changed since the last one.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PathCost {
+    // the energy the library charges with the weather of now
+    Energy,
+    // the energy with the worst weather forecast for the next ticks
+    Forecast(usize),
+    // what the road builder would have to spend to pave along the path
+    Road,
+    // another cost, plus a penalty on the tiles with content to destroy
+    AvoidContent(Box<PathCost>, usize),
+}
+
+impl PathCost {
+    pub(crate) fn model(&self, jerry: &Jerry, world: &World) -> Box<dyn CostModel> {
+        match self {
+            PathCost::Energy => Box::new(EnergyCost::new(world)),
+            PathCost::Forecast(ticks) => Box::new(ForecastCost::new(world, &jerry.weather_predictor, *ticks)),
+            PathCost::Road => Box::new(RoadCost),
+            PathCost::AvoidContent(base, penalty) => Box::new(AvoidContent::new(base.model(jerry, world), *penalty)),
+        }
+    }
+    //the weather of now, then the forecast if the cost follows it: the same weather gives the same prices
+    pub(crate) fn weather(&self, jerry: &Jerry, world: &World) -> Vec<WeatherType> {
+        let mut weather = vec![look_at_sky(world).get_weather_condition()];
+        if let Some(ticks) = self.forecast_ticks() {
+            weather.extend((0..ticks).filter_map(|n_of_ticks| jerry.weather_predictor.predict(n_of_ticks).ok()));
+        }
+        weather
+    }
+    fn forecast_ticks(&self) -> Option<usize> {
+        match self {
+            PathCost::Forecast(ticks) => Some(*ticks),
+            PathCost::AvoidContent(base, _) => base.forecast_ticks(),
+            PathCost::Energy | PathCost::Road => None,
+        }
+    }
+}
+
+const TILE_TYPES: [TileType; 12] = [
+    TileType::DeepWater,
+    TileType::ShallowWater,
+    TileType::Sand,
+    TileType::Grass,
+    TileType::Street,
+    TileType::Hill,
+    TileType::Mountain,
+    TileType::Snow,
+    TileType::Lava,
+    TileType::Teleport(true),
+    TileType::Teleport(false),
+    TileType::Wall,
+];
+
+//going up costs the elevation gained squared
+fn climb_cost(from: &Tile, to: &Tile) -> usize {
+    to.elevation.saturating_sub(from.elevation).pow(2)
+}
+
+//the cost of a step onto every walkable tile type with the given conditions
+fn price(conditions: &EnvironmentalConditions) -> Vec<(TileType, usize)> {
+    TILE_TYPES
+        .iter()
+        .filter(|tile_type| tile_type.properties().walk())
+        .map(|tile_type| {
+            let cost = calculate_cost_go_with_environment(tile_type.properties().cost(), conditions.clone(), tile_type.clone());
+            (tile_type.clone(), cost)
+        })
+        .collect()
+}
+
+fn step_cost(costs: &[(TileType, usize)], from: &Tile, to: &Tile) -> Option<usize> {
+    let (_, base_cost) = costs.iter().find(|(tile_type, _)| *tile_type == to.tile_type)?;
+    Some(base_cost + climb_cost(from, to))
+}
+
+fn min_step_cost(costs: &[(TileType, usize)]) -> usize {
+    costs.iter().map(|(_, cost)| *cost).min().unwrap_or(0)
+}
+
+//the energy the library charges for a step with the weather of now
+pub(crate) struct EnergyCost {
+    //the weather does not change during a search, so every walkable tile type is priced once
+    costs: Vec<(TileType, usize)>,
+}
+
+impl EnergyCost {
+    pub(crate) fn new(world: &World) -> Self {
+        Self::with_conditions(&look_at_sky(world))
+    }
+    pub(crate) fn with_conditions(conditions: &EnvironmentalConditions) -> Self {
+        EnergyCost { costs: price(conditions) }
+    }
+}
+
+impl CostModel for EnergyCost {
+    fn step_cost(&self, from: &Tile, to: &Tile) -> Option<usize> {
+        step_cost(&self.costs, from, to)
+    }
+    fn min_step_cost(&self) -> usize {
+        min_step_cost(&self.costs)
+    }
+    fn charges_climbing(&self) -> bool {
+        true
+    }
+}
+
+/*
+    The energy with the worst weather between now and the next ticks.
+    A path that is cheap only while the sun shines is not worth much to a robot that keeps walking it,
+    so every tile type is priced with the weather that makes it the most expensive.
+    The ticks the tool cannot predict yet are left out, with no forecast at all this is the EnergyCost.
+ */
+pub(crate) struct ForecastCost {
+    costs: Vec<(TileType, usize)>,
+}
+
+impl ForecastCost {
+    pub(crate) fn new(world: &World, tool: &WeatherPredictionTool, ticks: usize) -> Self {
+        let now = look_at_sky(world);
+        //the forecast conditions keep the hour of now
+        let hour = now.get_time_of_day_string().get(0..2).and_then(|h| h.parse::<u8>().ok()).unwrap_or(12);
+        let mut conditions = vec![now];
+        for n_of_ticks in 0..ticks {
+            if let Ok(weather) = tool.predict(n_of_ticks) {
+                if let Ok(forecast) = EnvironmentalConditions::new(&[weather], 15, hour) {
+                    conditions.push(forecast);
+                }
+            }
+        }
+        Self::with_conditions(&conditions)
+    }
+    pub(crate) fn with_conditions(conditions: &[EnvironmentalConditions]) -> Self {
+        let mut costs: Vec<(TileType, usize)> = Vec::new();
+        for (tile_type, cost) in conditions.iter().flat_map(price) {
+            match costs.iter_mut().find(|(known, _)| *known == tile_type) {
+                Some((_, worst)) => *worst = (*worst).max(cost),
+                None => costs.push((tile_type, cost)),
+            }
+        }
+        ForecastCost { costs }
+    }
+}
+
+impl CostModel for ForecastCost {
+    fn step_cost(&self, from: &Tile, to: &Tile) -> Option<usize> {
+        step_cost(&self.costs, from, to)
+    }
+    fn min_step_cost(&self) -> usize {
+        min_step_cost(&self.costs)
+    }
+    fn charges_climbing(&self) -> bool {
+        true
+    }
+}
+
+//prefers tiles that are cheap to pave and empty, and stays away from crates, which cannot be paved
+pub(crate) struct RoadCost;
+
+impl CostModel for RoadCost {
+    fn step_cost(&self, from: &Tile, to: &Tile) -> Option<usize> {
+        if !to.tile_type.properties().walk() {
+            return None;
+        }
+        let base_cost = match to.tile_type {
+            TileType::Street => 0,
+            TileType::Hill => 1,
+            TileType::Mountain => 0,
+            TileType::Sand => 1,
+            TileType::ShallowWater => 2,
+            TileType::Grass => 1,
+            TileType::DeepWater => 3,
+            TileType::Lava => 4,
+            _ => 1000
+        };
+        let content_cost = match to.content.to_default(){
+            Content::Crate(_) => 1000,
+            Content::None => 0,
+            _ => 2,
+        };
+        Some(base_cost + climb_cost(from, to) + content_cost)
+    }
+    fn charges_climbing(&self) -> bool {
+        true
+    }
+    fn uses_teleports(&self) -> bool {
+        false
+    }
+}
+
+/*
+    Any other model, plus a penalty on the tiles with content the robot would have to destroy.
+    The penalty only makes steps more expensive, so the lower bounds of the inner model still hold.
+ */
+pub(crate) struct AvoidContent {
+    inner: Box<dyn CostModel>,
+    penalty: usize,
+}
+
+impl AvoidContent {
+    pub(crate) fn new(inner: Box<dyn CostModel>, penalty: usize) -> Self {
+        AvoidContent { inner, penalty }
+    }
+}
+
+impl CostModel for AvoidContent {
+    fn step_cost(&self, from: &Tile, to: &Tile) -> Option<usize> {
+        let cost = self.inner.step_cost(from, to)?;
+        if to.content != Content::None && to.content.properties().destroy() {
+            Some(cost + self.penalty)
+        } else {
+            Some(cost)
+        }
+    }
+    fn min_step_cost(&self) -> usize {
+        self.inner.min_step_cost()
+    }
+    fn charges_climbing(&self) -> bool {
+        self.inner.charges_climbing()
+    }
+    fn uses_teleports(&self) -> bool {
+        self.inner.uses_teleports()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robotics_lib::world::environmental_conditions::WeatherType;
+
+    fn tile(tile_type: TileType, content: Content, elevation: usize) -> Tile {
+        Tile { tile_type, content, elevation }
+    }
+
+    fn conditions(weather: WeatherType) -> EnvironmentalConditions {
+        EnvironmentalConditions::new(&[weather], 15, 12).unwrap()
+    }
+
+    #[test]
+    fn test_forecast_takes_the_worst_weather(){
+        let sunny = EnergyCost::with_conditions(&conditions(WeatherType::Sunny));
+        let forecast = ForecastCost::with_conditions(&[conditions(WeatherType::Sunny), conditions(WeatherType::Rainy)]);
+        let rainy = EnergyCost::with_conditions(&conditions(WeatherType::Rainy));
+        let from = tile(TileType::Grass, Content::None, 0);
+        for tile_type in TILE_TYPES.iter().filter(|tile_type| tile_type.properties().walk()) {
+            let to = tile(tile_type.clone(), Content::None, 0);
+            let worst = sunny.step_cost(&from, &to).max(rainy.step_cost(&from, &to));
+            assert_eq!(forecast.step_cost(&from, &to), worst);
+        }
+        assert_eq!(forecast.step_cost(&from, &tile(TileType::Wall, Content::None, 0)), None);
+        //with only the weather of now the forecast is the energy cost
+        let now = ForecastCost::with_conditions(&[conditions(WeatherType::Sunny)]);
+        assert_eq!(now.min_step_cost(), sunny.min_step_cost());
+    }
+
+    #[test]
+    fn test_avoid_content(){
+        let energy = EnergyCost::with_conditions(&conditions(WeatherType::Sunny));
+        let from = tile(TileType::Grass, Content::None, 0);
+        let grass = energy.step_cost(&from, &tile(TileType::Grass, Content::None, 0)).unwrap();
+        let min_step_cost = energy.min_step_cost();
+        let model = AvoidContent::new(Box::new(energy), 10);
+        assert_eq!(model.step_cost(&from, &tile(TileType::Grass, Content::None, 0)), Some(grass));
+        assert_eq!(model.step_cost(&from, &tile(TileType::Grass, Content::Rock(2), 0)), Some(grass + 10));
+        assert_eq!(model.step_cost(&from, &tile(TileType::Grass, Content::Tree(1), 2)), Some(grass + 4 + 10));
+        assert_eq!(model.step_cost(&from, &tile(TileType::Wall, Content::Rock(2), 0)), None);
+        assert_eq!(model.min_step_cost(), min_step_cost);
+        assert!(model.charges_climbing() && model.uses_teleports());
+    }
+
+    #[test]
+    fn test_road_cost_charges_any_content(){
+        let from = tile(TileType::Grass, Content::None, 0);
+        assert_eq!(RoadCost.step_cost(&from, &tile(TileType::Grass, Content::None, 0)), Some(1));
+        assert_eq!(RoadCost.step_cost(&from, &tile(TileType::Grass, Content::Rock(2), 0)), Some(3));
+        assert_eq!(RoadCost.step_cost(&from, &tile(TileType::Sand, Content::Tree(1), 2)), Some(7));
+        assert_eq!(RoadCost.step_cost(&from, &tile(TileType::Grass, Content::Crate(0..5), 0)), Some(1001));
+        assert_eq!(RoadCost.step_cost(&from, &tile(TileType::Wall, Content::None, 0)), None);
+        assert!(!RoadCost.uses_teleports());
+    }
+}
@@ -1,20 +1,26 @@
 
-use crate::interface::Jerry;
-use crate::explorer::robot_map_slice_n;
+use crate::jerry::Jerry;
+use crate::logging;
 use std::collections::{HashSet, VecDeque};
 use charting_tools::charted_coordinate::ChartedCoordinate;
 use robotics_lib::interface::Direction;
 use robotics_lib::runner::Runnable;
 use robotics_lib::world::World;
 use robotics_lib::world::{tile::TileType};
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap};
-use robotics_lib::interface::{look_at_sky};
-use robotics_lib::utils::calculate_cost_go_with_environment;
-use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::tile::Tile;
 use robotics_lib::interface::teleport;
 use robotics_lib::interface::go;
 
+use self::cache::FieldKey;
+use self::search::{distance_field, find_path, DistanceField, SearchMode, Window};
+
+pub(crate) use self::cache::PathCache;
+pub(crate) use self::costs::{CostModel, PathCost};
+
+mod cache;
+mod costs;
+mod search;
+
 
 pub fn go_to_coordinates(
     robot: &mut Jerry,
@@ -23,35 +29,48 @@ pub fn go_to_coordinates(
     adjacent: bool,
     destination: (usize, usize),
     n: usize,
+    cost: PathCost,
 ) -> Result<Path, String> {
-    match get_path_to_coordinates(world, robot,  map,  adjacent, destination, n) {
-        Err(e) => Err(e),
-        Ok(path) => {
-            if !robot.get_energy().has_enough_energy(path.cost) {
-                return Err(String::from("Not enough energy!"));
+    match get_path_to_coordinates(world, robot,  map,  adjacent, destination, n, cost) {
+        Err(e) => {
+            log::debug!(target: logging::FAST_PATHS, "No path to {:?}: {}", destination, e);
+            Err(e)
+        }
+        Ok(path) => walk_path(robot, world, path),
+    }
+}
+//walks a path the robot has already found, if it has the energy for all of it
+pub(crate) fn walk_path(robot: &mut Jerry, world: &mut World, path: Path) -> Result<Path, String> {
+    if !robot.get_energy().has_enough_energy(path.cost) {
+        log::debug!(target: logging::FAST_PATHS, "Path to {:?} costs {}, not enough energy", path.destination, path.cost);
+        return Err(String::from("Not enough energy!"));
+    }
+    log::trace!(target: logging::FAST_PATHS, "Going to {:?}, {} actions, cost {}", path.destination, path.actions.len(), path.cost);
+    robot.current_path = Some(path.clone());
+
+    for action in path.actions.iter() {
+
+        match action {
+            Action::Go(d) => {
+                if let Err(error) = go(robot, world, d.clone()) {
+                    log::warn!(target: logging::FAST_PATHS, "Go {:?} failed: {:?}", d, error);
+                    robot.current_path = None;
+                    return Err(String::from("Error while calling go interface!"));
+                }
             }
-
-            for action in path.actions.iter() {
-
-                match action {
-                    Action::Go(d) => {
-                        if let Err(_) = go(robot, world, d.clone()) {
-                            return Err(String::from("Error while calling go interface!"));
-                        }
-                    }
-                    Action::Teleport((row, col)) => {
-                        if let Err(_) = teleport(robot, world, (*row, *col)) {
-                            return Err(String::from(
-                                "Error while calling teleport interface!",
-                            ));
-                        }
-                    }
+            Action::Teleport((row, col)) => {
+                if let Err(error) = teleport(robot, world, (*row, *col)) {
+                    log::warn!(target: logging::FAST_PATHS, "Teleport to {:?} failed: {:?}", (row, col), error);
+                    robot.current_path = None;
+                    return Err(String::from(
+                        "Error while calling teleport interface!",
+                    ));
                 }
             }
-
-            Ok(path)
         }
     }
+
+    Ok(path)
 }
 pub fn get_path_to_coordinates(
     world: &World,
@@ -60,25 +79,42 @@ pub fn get_path_to_coordinates(
     adjacent: bool,
     destination: (usize, usize),
     n: usize,
+    cost: PathCost,
     ) -> Result<Path, String> {
-    match robot_map_slice_n(robot, map, n) {
-        None => Err(String::from("Map not visible!")),
-        Some(map) => {
-            let source = (
-                robot.get_coordinate().get_row(),
-                robot.get_coordinate().get_col(),
-            );
+    let source = (
+        robot.get_coordinate().get_row(),
+        robot.get_coordinate().get_col(),
+    );
+    //the destination has to be in the n x n square around the robot, the path can go around it
+    let window = Window::around(map, source, n);
+    if window.is_empty() {
+        return Err(String::from("Map not visible!"));
+    }
 
-            let mut targets = HashSet::new();
+    let mut targets = HashSet::new();
 
-            if adjacent {
-                targets.extend(get_adjacent_tiles(&map, destination));
-            } else {
-                targets.insert(destination);
-            }
-            dijkstra(robot, world, &map, source, targets, None)
-        }
+    if adjacent {
+        targets.extend(get_adjacent_tiles(map, destination));
+    } else {
+        targets.insert(destination);
     }
+    targets.retain(|target| window.contains(*target));
+    robot_field(robot, world, map, cost).closest(&targets)
+}
+
+/*
+    The cheapest way from the robot to every tile of the known map.
+    The robot asks again and again from the same place, the field is kept until a tile changes,
+    and every question from there is answered by it whatever the window.
+ */
+pub(crate) fn robot_field<'a>(robot: &'a mut Jerry, world: &World, map: &Vec<Vec<Option<Tile>>>, cost: PathCost) -> &'a DistanceField {
+    let source = (robot.get_coordinate().get_row(), robot.get_coordinate().get_col());
+    let model = cost.model(robot, world);
+    let key = FieldKey { source, weather: cost.weather(robot, world), cost };
+    robot.path_cache.field(key, |teleports| {
+        let window = Window::full(map);
+        distance_field(&window, source, &*model, &usable_teleports(teleports, &window, &*model), TELEPORT_COST)
+    })
 }
 #[derive(Debug, Clone)]
 pub enum Action {
@@ -105,40 +141,17 @@ impl Path {
     }
 }
 
-#[derive(Eq)]
-struct State {
-    node: (usize, usize),
-    distance: usize,
-}
-
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other.distance.cmp(&self.distance)
-    }
-}
-
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl PartialEq for State {
-    fn eq(&self, other: &Self) -> bool {
-        self.node == other.node && self.distance == other.distance
-    }
-}
-
 pub(crate) fn dijkstra(
     jerry: &Jerry,
     world: &World,
     map: &Vec<Vec<Option<Tile>>>,
     source: (usize, usize),
     mut targets: HashSet<(usize, usize)>,
-    road: Option<Option<&HashSet<ChartedCoordinate>>>
+    road: Option<Option<&HashSet<ChartedCoordinate>>>,
     //None -> normal dijkstra
     //Some(None) -> dijkstra with global road
     //Some(Some(HashSet<ChartedCoordinate>) -> dijkstra with local road
+    cost: PathCost,
 ) -> Result<Path, String> {
     if targets.is_empty() && road.is_none() {
         return Err(String::from("Path not found!"));
@@ -157,231 +170,44 @@ pub(crate) fn dijkstra(
             }
         }
     }
-    let (source_row, source_col) = (source.0, source.1);
-    let (mut start_row,mut end_row) = (source_row.saturating_sub(map.len() / 2), 
-    source_row.saturating_sub(map.len() / 2) + map.len() - 1);
-    let (mut start_col,mut end_col) = (source_col.saturating_sub(map[0].len() / 2), 
-    source_col.saturating_sub(map[0].len() / 2) + map[0].len() - 1);
-
-    //case when we use the whole map for the rroad planning
-    if map.len() == jerry.world_dim{
-        (start_row, end_row) = (0, map.len() - 1);
-        (start_col, end_col) = (0, map[0].len() - 1);
-    }
-
-    //row - start_row and col - start_col are the coordinates of the map slice
-    //row and col are the actual coordinates of the map
-    let mut paths = Vec::new();
-    let mut teleports = Vec::new();
-
-    for row in start_row..=end_row {
-        paths.push(Vec::new());
-        for col in start_col..=end_col{
-            paths[row - start_row].push(Path::new(source, (row, col), usize::MAX));
-
-            if let Some(tile) = map[row - start_row][col - start_col].as_ref() {
-                if tile.tile_type == TileType::Teleport(true) {
-                    teleports.push((row, col));
-                }
-            }
-        }
-    }
-
-    paths[source_row - start_row][source_col - start_col].cost = 0;
-
-    let mut heap = BinaryHeap::new();
-    heap.push(State {
-        node: source,
-        distance: 0,
-    });
-
-    while !heap.is_empty() {
-        let (row, col) = heap.peek().unwrap().node;
-        let distance = heap.pop().unwrap().distance;
-        let len = map[0].len();
-        if col - start_col + 1 < len && map[row - start_row][col - start_col + 1].is_some() {
-            if let Some(_) = road{
-                if let Ok(cost) = calculate_go_cost_for_road(jerry, world, map, (row, col), Direction::Right) {
-                    if distance + cost < paths[row - start_row][col - start_col + 1].cost {
-                        paths[row - start_row][col - start_col + 1].cost = distance + cost;
-                        paths[row - start_row][col - start_col + 1].actions = paths[row - start_row][col - start_col].actions.clone();
-                        paths[row - start_row][col - start_col + 1]
-                            .actions
-                            .push_back(Action::Go(Direction::Right));
-                        heap.push(State {
-                            node: (row, col + 1),
-                            distance: distance + cost,
-                        });
-                    }
-                }
-            }
-            else {
-                if let Ok(cost) = calculate_go_cost(jerry, world, map, (row, col), Direction::Right) {
-                    if distance + cost < paths[row - start_row][col - start_col + 1].cost {
-                        paths[row - start_row][col - start_col + 1].cost = distance + cost;
-                        paths[row - start_row][col - start_col + 1].actions = paths[row - start_row][col - start_col].actions.clone();
-                        paths[row - start_row][col - start_col + 1]
-                            .actions
-                            .push_back(Action::Go(Direction::Right));
-                        heap.push(State {
-                            node: (row, col + 1),
-                            distance: distance + cost,
-                        });
-                    }
-                }
-            }
-        }
-        let len = map.len();
-        if row - start_row + 1 < len && map[row - start_row + 1][col - start_col].is_some() {
-            if let Some(_) = road{
-                if let Ok(cost) = calculate_go_cost_for_road(jerry, world, map, (row, col), Direction::Down) {
-                    if distance + cost < paths[row - start_row + 1][col - start_col].cost {
-                        paths[row - start_row + 1][col - start_col].cost = distance + cost;
-                        paths[row - start_row + 1][col - start_col].actions = paths[row - start_row][col - start_col].actions.clone();
-                        paths[row - start_row + 1][col - start_col]
-                            .actions
-                            .push_back(Action::Go(Direction::Down));
-                        heap.push(State {
-                            node: (row + 1, col),
-                            distance: distance + cost,
-                        });
-                    }
-                }
-            }
-            else{
-                if let Ok(cost) = calculate_go_cost(jerry, world, map, (row, col), Direction::Down) {
-                    if distance + cost < paths[row - start_row + 1][col - start_col].cost {
-                        paths[row - start_row + 1][col - start_col].cost = distance + cost;
-                        paths[row - start_row + 1][col - start_col].actions = paths[row - start_row][col - start_col].actions.clone();
-                        paths[row - start_row + 1][col - start_col]
-                            .actions
-                            .push_back(Action::Go(Direction::Down));
-                        heap.push(State {
-                            node: (row + 1, col),
-                            distance: distance + cost,
-                        });
-                    }
-                }
-            }
-        }
-
-        if col - start_col > 0 && map[row - start_row][col - start_col - 1].is_some() {
-            if let Some(_) = road{
-                if let Ok(cost) = calculate_go_cost_for_road(jerry, world, map, (row, col), Direction::Left) {
-                    if distance + cost < paths[row - start_row][col - start_col - 1].cost {
-                        paths[row - start_row][col - start_col - 1].cost = distance + cost;
-                        paths[row - start_row][col - start_col - 1].actions = paths[row - start_row][col - start_col].actions.clone();
-                        paths[row - start_row][col - start_col - 1]
-                            .actions
-                            .push_back(Action::Go(Direction::Left));
-                        heap.push(State {
-                            node: (row, col - 1),
-                            distance: distance + cost,
-                        });
-                    }
-                }
-            }
-            else{
-                if let Ok(cost) = calculate_go_cost(jerry, world, map, (row, col), Direction::Left) {
-                    if distance + cost < paths[row - start_row][col - start_col - 1].cost {
-                        paths[row - start_row][col - start_col - 1].cost = distance + cost;
-                        paths[row - start_row][col - start_col - 1].actions = paths[row - start_row][col - start_col].actions.clone();
-                        paths[row - start_row][col - start_col - 1]
-                            .actions
-                            .push_back(Action::Go(Direction::Left));
-                        heap.push(State {
-                            node: (row, col - 1),
-                            distance: distance + cost,
-                        });
-                    }
-                }
-            }
-        }
-
-        if row - start_row > 0 && map[row - start_row - 1][col - start_col].is_some() {
-            if let Some(_) = road{
-                if let Ok(cost) = calculate_go_cost_for_road(jerry, world, map, (row, col), Direction::Up) {
-                    if distance + cost < paths[row - start_row - 1][col - start_col].cost {
-                        paths[row - start_row - 1][col - start_col].cost = distance + cost;
-                        paths[row - start_row - 1][col - start_col].actions = paths[row - start_row][col - start_col].actions.clone();
-                        paths[row - start_row - 1][col - start_col]
-                            .actions
-                            .push_back(Action::Go(Direction::Up));
-                        heap.push(State {
-                            node: (row - 1, col),
-                            distance: distance + cost,
-                        });
-                    }
-                }
-            }
-            else{
-                if let Ok(cost) = calculate_go_cost(jerry, world, map, (row, col), Direction::Up) {
-                    if distance + cost < paths[row - start_row - 1][col - start_col].cost {
-                        paths[row - start_row - 1][col - start_col].cost = distance + cost;
-                        paths[row - start_row - 1][col - start_col].actions = paths[row - start_row][col - start_col].actions.clone();
-                        paths[row - start_row - 1][col - start_col]
-                            .actions
-                            .push_back(Action::Go(Direction::Up));
-                        heap.push(State {
-                            node: (row - 1, col),
-                            distance: distance + cost,
-                        });
-                    }
-                }
-            }
-        }
+    //the road planning always gets the whole map
+    let window = Window::full(map);
+    route(&window, source, &targets, &*cost.model(jerry, world), jerry.path_cache.teleports())
+}
 
-        if let Some(tile) = map[row - start_row][col - start_col].as_ref() {
-            if tile.tile_type == TileType::Teleport(true) {
-                for (teleport_row, teleport_col) in teleports.iter() {
-                    if let Ok(cost) =
-                        calculate_teleport_cost(jerry, map, (*teleport_row, *teleport_col))
-                    {
-                        if distance + cost < paths[*teleport_row - start_row][*teleport_col - start_col].cost {
-                            paths[*teleport_row - start_row][*teleport_col - start_col].cost = distance + cost;
-                            paths[*teleport_row - start_row][*teleport_col - start_col].actions =
-                                paths[row - start_row][col - start_col].actions.clone();
-                            paths[*teleport_row - start_row][*teleport_col - start_col]
-                                .actions
-                                .push_back(Action::Teleport((*teleport_row, *teleport_col)));
-                            heap.push(State {
-                                node: (*teleport_row, *teleport_col),
-                                distance: distance + cost,
-                            });
-                        }
-                    }
-                }
-            }
-        }
-    }
+// teleporting costs the same wherever the robot goes
+const TELEPORT_COST: usize = 30;
+// with more targets than this the A* heuristic costs more than it saves
+const A_STAR_MAX_TARGETS: usize = 8;
 
-    let mut ret = Path::default();
-    ret.cost = usize::MAX;
-    for (target_row, target_col) in targets {
-        if target_row < start_row || target_row > end_row || target_col < start_col || target_col > end_col {
-            return Err(String::from("Path not found!"));
-        }
-        if paths[target_row - start_row][target_col - start_col].cost < ret.cost {
-            ret = paths[target_row - start_row][target_col - start_col].clone();
-        }
-    }
-
-    if ret.cost == usize::MAX {
-        Err(String::from("Path not found!"))
-    } else {
-        Ok(ret)
-    }
+fn route(
+    window: &Window,
+    source: (usize, usize),
+    targets: &HashSet<(usize, usize)>,
+    model: &dyn CostModel,
+    teleports: &[(usize, usize)],
+) -> Result<Path, String> {
+    let mode = if targets.len() <= A_STAR_MAX_TARGETS { SearchMode::AStar } else { SearchMode::Dijkstra };
+    let teleports = usable_teleports(teleports, window, model);
+    find_path(window, source, targets, model, &teleports, TELEPORT_COST, mode)
 }
 
-fn get_coords_row_col(source: (usize, usize), direction: Direction) -> (usize, usize) {
-    let (row, col) = source;
-
-    match direction {
-        Direction::Up => (row - 1, col),
-        Direction::Down => (row + 1, col),
-        Direction::Left => (row, col - 1),
-        Direction::Right => (row, col + 1),
-    }
+/*
+    Every active teleport the search may jump to, out of the ones the path cache knows.
+    The jumps only start from a Teleport(true) tile, but a path can walk to any teleport in the window
+    and jump from there, so the robot does not have to stand on one when it asks.
+    Over the whole known map the far teleports the tools discovered are used for long routes.
+    Only the ones inside the window count, a path never leaves it.
+ */
+fn usable_teleports(teleports: &[(usize, usize)], window: &Window, model: &dyn CostModel) -> Vec<(usize, usize)> {
+    if !model.uses_teleports() {
+        return Vec::new();
+    }
+    teleports
+        .iter()
+        .filter(|position| matches!(window.tile(**position), Some(tile) if tile.tile_type == TileType::Teleport(true)))
+        .copied()
+        .collect()
 }
 
 pub(crate) fn get_adjacent_tiles(
@@ -413,149 +239,6 @@ pub(crate) fn get_adjacent_tiles(
 }
 
 
-pub(crate) fn calculate_go_cost(
-    robot: &impl Runnable,
-    world: &World,
-    map: &Vec<Vec<Option<Tile>>>,
-    source: (usize, usize),
-    direction: Direction,
-) -> Result<usize, String> {
-    let (source_row, source_col) = source;
-    let start_row = source_row.saturating_sub(map.len() / 2);
-    let start_col = source_col.saturating_sub(map[0].len() / 2);
-    let (destination_row, destination_col) = get_coords_row_col(source, direction);
-
-    if map[source_row - start_row][source_col - start_col].is_none() {
-        return Err(String::from("Source is None!"));
-    }
-    if map[destination_row - start_row][destination_col - start_col].is_none() {
-        return Err(String::from("Destination is None!"));
-    }
-
-    let source = map[source_row - start_row][source_col - start_col].clone().unwrap();
-    let destination = map[destination_row - start_row][destination_col - start_col].clone().unwrap();
-    if destination.tile_type.properties().walk() == false {
-        return Err(String::from("Go not allowed!"));
-    }
-
-    let mut base_cost = destination.tile_type.properties().cost();
-    let mut elevation_cost = 0;
-
-    base_cost =
-        calculate_cost_go_with_environment(base_cost, look_at_sky(world), destination.tile_type);
-
-    if destination.elevation > source.elevation {
-        elevation_cost = (destination.elevation - source.elevation).pow(2);
-    }
-
-    Ok(base_cost + elevation_cost)
-}
-pub(crate) fn calculate_go_cost_for_road(
-    jerry: &Jerry,
-    world: &World,
-    map: &Vec<Vec<Option<Tile>>>,
-    source: (usize, usize),
-    direction: Direction,
-) -> Result<usize, String>{
-    let (source_row, source_col) = source;
-    let mut start_row = source_row.saturating_sub(map.len() / 2);
-    let mut start_col = source_col.saturating_sub(map[0].len() / 2);
-
-    if map.len() == jerry.world_dim{
-        start_row = 0;
-        start_col = 0;
-    }
-    let (destination_row, destination_col) = get_coords_row_col(source, direction);
-
-    if map[source_row - start_row][source_col - start_col].is_none() {
-        return Err(String::from("Source is None!"));
-    }
-    if map[destination_row - start_row][destination_col - start_col].is_none() {
-        return Err(String::from("Destination is None!"));
-    }
-
-    let source = map[source_row - start_row][source_col - start_col].clone().unwrap();
-    let destination = map[destination_row - start_row][destination_col - start_col].clone().unwrap();
-    if destination.tile_type.properties().walk() == false {
-        return Err(String::from("Go not allowed!"));
-    }
-
-    let mut base_cost = match destination.tile_type {
-        TileType::Street => 0,
-        TileType::Hill => 1,
-        TileType::Mountain => 0,
-        TileType::Sand => 1,
-        TileType::ShallowWater => 2,
-        TileType::Grass => 1,
-        TileType::DeepWater => 3,
-        TileType::Lava => 4,
-        _ => 1000
-    };
-    let content_cost = match destination.content.to_default(){
-        Content::Crate(_) => 1000,
-        Content::None => 0,
-        _ => 2,
-    };
-    let mut elevation_cost = 0;
-
-    if destination.elevation > source.elevation {
-        elevation_cost = (destination.elevation - source.elevation).pow(2);
-    }
-    Ok(base_cost + elevation_cost + content_cost)
-}
-
-
-
-
-
-pub(crate) fn calculate_teleport_cost(
-    robot: &impl Runnable,
-    map: &Vec<Vec<Option<Tile>>>,
-    destination: (usize, usize),
-) -> Result<usize, String> {
-    let (source_row, source_col) = (
-        robot.get_coordinate().get_row(),
-        robot.get_coordinate().get_col(),
-    );
-    let start_row = source_row.saturating_sub(map.len() / 2);
-    let start_col = source_col.saturating_sub(map[0].len() / 2);
-
-    let (destination_row, destination_col) = (destination.0, destination.1);
-
-    let size = map.len();
-
-    if source_row >= size || source_col >= size {
-        return Err(String::from("Source out of bounds!"));
-    }
-
-    if destination_row >= size || destination_col >= size {
-        return Err(String::from("Destination out of bounds!"));
-    }
-
-    match &map[source_row - start_row][source_col - start_col] {
-        None => {
-            return Err(String::from("Source is None!"));
-        }
-        Some(tile) => {
-            if tile.tile_type != TileType::Teleport(true) {
-                return Err(String::from("Source is not a teleport!"));
-            }
-        }
-    }
-
-    match &map[destination_row - start_row][destination_col - start_col] {
-        None => {
-            return Err(String::from("Destination is None!"));
-        }
-        Some(tile) => {
-            if tile.tile_type != TileType::Teleport(true) {
-                return Err(String::from("Destination is not a teleport!"));
-            }
-        }
-    }
-
-    Ok(30)
-}
 pub fn path_to_coordinates(path: &Path) -> Vec<ChartedCoordinate>{
     let mut ret = Vec::new();
     let mut row = path.source.0;
@@ -583,3 +266,44 @@ pub fn path_to_coordinates(path: &Path) -> Vec<ChartedCoordinate>{
     }
     ret
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::costs::RoadCost;
+    use super::search::tests::{grid, ElevationCost};
+
+    #[test]
+    fn test_usable_teleports(){
+        let map = grid(&[
+            "T....",
+            ".....",
+            "....T",
+        ]);
+        let known = [(0, 0), (1, 2), (2, 4)];
+        //all of them, a path can walk to any of them first
+        assert_eq!(usable_teleports(&known, &Window::full(&map), &ElevationCost), vec![(0, 0), (2, 4)]);
+        assert_eq!(usable_teleports(&known, &Window::around(&map, (1, 1), 3), &ElevationCost), vec![(0, 0)]);
+        //roads are paved tile by tile
+        assert!(usable_teleports(&known, &Window::full(&map), &RoadCost).is_empty());
+    }
+
+    #[test]
+    fn test_long_range_route_through_teleports(){
+        let map = grid(&[
+            ".T#....",
+            "..#....",
+            "###.#T.",
+            "...1#..",
+        ]);
+        let targets = HashSet::from([(3, 0)]);
+        //the far side is only reached by the teleport the robot does not stand on
+        let teleports = [(0, 1), (2, 5)];
+        let path = route(&Window::full(&map), (1, 0), &targets, &ElevationCost, &teleports).unwrap();
+        assert_eq!(path.destination, (3, 0));
+        assert!(path.actions.iter().any(|action| matches!(action, Action::Teleport((2, 5)))));
+        assert_eq!(path.cost, 2 + TELEPORT_COST + path.actions.len() - 3);
+        //around the robot the other teleport is out of sight
+        assert!(route(&Window::around(&map, (1, 0), 5), (1, 0), &targets, &ElevationCost, &teleports).is_err());
+    }
+}
@@ -0,0 +1,652 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+use robotics_lib::interface::Direction;
+use robotics_lib::world::tile::{Tile, TileType};
+
+use super::{Action, Path};
+
+/*
+    What a step between two adjacent tiles costs.
+    The search does not know about energy, roads or weather, every notion of "shortest" is a cost model.
+ */
+pub(crate) trait CostModel {
+    //None if the robot cannot go from one tile to the other
+    fn step_cost(&self, from: &Tile, to: &Tile) -> Option<usize>;
+    //the cheapest a step can be without climbing, the A* heuristic multiplies it by the manhattan distance
+    fn min_step_cost(&self) -> usize {
+        0
+    }
+    //true if going up costs at least the elevation gained, so the heuristic can count the climb
+    fn charges_climbing(&self) -> bool {
+        false
+    }
+    //false if the path has to be walked tile by tile, like a road that gets paved
+    fn uses_teleports(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SearchMode {
+    Dijkstra,
+    //only worth it with a few targets, the heuristic looks at all of them for every node
+    AStar,
+}
+
+/*
+    The part of the map a search may use. Positions are always the ones of the whole map,
+    so nothing has to be copied out of the robot map to search around the robot.
+ */
+#[derive(Clone, Copy)]
+pub(crate) struct Window<'a> {
+    map: &'a Vec<Vec<Option<Tile>>>,
+    top_left: (usize, usize),
+    rows: usize,
+    cols: usize,
+}
+
+impl<'a> Window<'a> {
+    pub(crate) fn full(map: &'a Vec<Vec<Option<Tile>>>) -> Self {
+        let cols = map.first().map_or(0, |row| row.len());
+        Window { map, top_left: (0, 0), rows: map.len(), cols }
+    }
+    //the n x n square centered on the position, cut by the borders of the map like robot_map_slice_n
+    pub(crate) fn around(map: &'a Vec<Vec<Option<Tile>>>, center: (usize, usize), n: usize) -> Self {
+        let full = Window::full(map);
+        if full.rows == 0 || full.cols == 0 {
+            return full;
+        }
+        let top_left = (center.0.saturating_sub(n / 2), center.1.saturating_sub(n / 2));
+        let bottom_right = ((center.0 + n / 2).min(full.rows - 1), (center.1 + n / 2).min(full.cols - 1));
+        Window {
+            map,
+            top_left,
+            rows: (bottom_right.0 + 1).saturating_sub(top_left.0),
+            cols: (bottom_right.1 + 1).saturating_sub(top_left.1),
+        }
+    }
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rows == 0 || self.cols == 0
+    }
+    pub(crate) fn contains(&self, position: (usize, usize)) -> bool {
+        position.0 >= self.top_left.0
+            && position.1 >= self.top_left.1
+            && position.0 < self.top_left.0 + self.rows
+            && position.1 < self.top_left.1 + self.cols
+    }
+    pub(crate) fn tile(&self, position: (usize, usize)) -> Option<&'a Tile> {
+        if !self.contains(position) {
+            return None;
+        }
+        self.map.get(position.0)?.get(position.1)?.as_ref()
+    }
+    fn len(&self) -> usize {
+        self.rows * self.cols
+    }
+    fn index(&self, position: (usize, usize)) -> usize {
+        (position.0 - self.top_left.0) * self.cols + position.1 - self.top_left.1
+    }
+    fn position(&self, index: usize) -> (usize, usize) {
+        (self.top_left.0 + index / self.cols, self.top_left.1 + index % self.cols)
+    }
+    fn neighbour(&self, position: (usize, usize), direction: &Direction) -> Option<(usize, usize)> {
+        let neighbour = match direction {
+            Direction::Up => (position.0.checked_sub(1)?, position.1),
+            Direction::Down => (position.0 + 1, position.1),
+            Direction::Left => (position.0, position.1.checked_sub(1)?),
+            Direction::Right => (position.0, position.1 + 1),
+        };
+        self.contains(neighbour).then_some(neighbour)
+    }
+}
+
+// same order as the old search, so ties are broken the same way
+const DIRECTIONS: [Direction; 4] = [Direction::Right, Direction::Down, Direction::Left, Direction::Up];
+const NO_PARENT: usize = usize::MAX;
+
+#[derive(Debug, PartialEq, Eq)]
+struct Node {
+    //cost so far plus the heuristic
+    priority: usize,
+    cost: usize,
+    index: usize,
+}
+
+impl Ord for Node {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority).then_with(|| self.cost.cmp(&other.cost))
+    }
+}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/*
+    State of a search, kept between calls so a search does not allocate.
+    A slot is only valid if its stamp is the generation of the current search,
+    which resets the whole grid without touching it.
+ */
+#[derive(Default)]
+pub(crate) struct SearchBuffers {
+    cost: Vec<usize>,
+    parent: Vec<usize>,
+    teleported: Vec<bool>,
+    stamp: Vec<u32>,
+    target: Vec<u32>,
+    generation: u32,
+    heap: BinaryHeap<Node>,
+}
+
+impl SearchBuffers {
+    fn reset(&mut self, len: usize) {
+        if self.stamp.len() < len {
+            self.cost.resize(len, usize::MAX);
+            self.parent.resize(len, NO_PARENT);
+            self.teleported.resize(len, false);
+            self.stamp.resize(len, 0);
+            self.target.resize(len, 0);
+        }
+        self.generation = self.generation.wrapping_add(1);
+        if self.generation == 0 {
+            self.stamp.fill(0);
+            self.target.fill(0);
+            self.generation = 1;
+        }
+        self.heap.clear();
+    }
+    fn cost(&self, index: usize) -> usize {
+        if self.stamp[index] == self.generation { self.cost[index] } else { usize::MAX }
+    }
+    fn set(&mut self, index: usize, cost: usize, parent: usize, teleported: bool) {
+        self.stamp[index] = self.generation;
+        self.cost[index] = cost;
+        self.parent[index] = parent;
+        self.teleported[index] = teleported;
+    }
+    fn is_target(&self, index: usize) -> bool {
+        self.target[index] == self.generation
+    }
+}
+
+thread_local! {
+    static BUFFERS: RefCell<SearchBuffers> = RefCell::new(SearchBuffers::default());
+}
+
+fn manhattan(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+//never more than the real cost: every step costs at least the cheapest one, and climbing at least the elevation gained
+fn lower_bound(model: &dyn CostModel, from: (usize, usize), from_tile: &Tile, to: (usize, usize), to_tile: &Tile) -> usize {
+    let climb = if model.charges_climbing() { to_tile.elevation.saturating_sub(from_tile.elevation) } else { 0 };
+    model.min_step_cost() * manhattan(from, to) + climb
+}
+
+/*
+    Cheapest path from the source to the closest of the targets, inside the window.
+    Teleport(true) tiles are connected to every tile in `teleports` for `teleport_cost`.
+ */
+pub(crate) fn find_path(
+    window: &Window,
+    source: (usize, usize),
+    targets: &HashSet<(usize, usize)>,
+    model: &dyn CostModel,
+    teleports: &[(usize, usize)],
+    teleport_cost: usize,
+    mode: SearchMode,
+) -> Result<Path, String> {
+    BUFFERS.with(|buffers| {
+        search(&mut buffers.borrow_mut(), window, source, targets, model, teleports, teleport_cost, mode)
+    })
+}
+
+fn search(
+    buffers: &mut SearchBuffers,
+    window: &Window,
+    source: (usize, usize),
+    targets: &HashSet<(usize, usize)>,
+    model: &dyn CostModel,
+    teleports: &[(usize, usize)],
+    teleport_cost: usize,
+    mode: SearchMode,
+) -> Result<Path, String> {
+    //targets outside the window or not known can never be reached
+    let targets: Vec<((usize, usize), &Tile)> = targets
+        .iter()
+        .filter_map(|target| window.tile(*target).map(|tile| (*target, tile)))
+        .collect();
+    if targets.is_empty() || !window.contains(source) {
+        return Err(String::from("Path not found!"));
+    }
+    let teleports: Vec<((usize, usize), &Tile)> = teleports
+        .iter()
+        .filter_map(|teleport| window.tile(*teleport).map(|tile| (*teleport, tile)))
+        .collect();
+
+    //for each target, the least a path through a teleport can cost from anywhere
+    let through_teleport: Vec<usize> = targets
+        .iter()
+        .map(|(target, target_tile)| {
+            teleports
+                .iter()
+                .map(|(teleport, tile)| lower_bound(model, *teleport, tile, *target, target_tile))
+                .min()
+                .map_or(usize::MAX, |bound| bound + teleport_cost)
+        })
+        .collect();
+    let heuristic = |position: (usize, usize), tile: &Tile| -> usize {
+        if mode == SearchMode::Dijkstra {
+            return 0;
+        }
+        targets
+            .iter()
+            .zip(through_teleport.iter())
+            .map(|((target, target_tile), teleport)| lower_bound(model, position, tile, *target, target_tile).min(*teleport))
+            .min()
+            .unwrap_or(0)
+    };
+
+    buffers.reset(window.len());
+    let generation = buffers.generation;
+    for (target, _) in targets.iter() {
+        buffers.target[window.index(*target)] = generation;
+    }
+    let source_index = window.index(source);
+    buffers.set(source_index, 0, NO_PARENT, false);
+    let source_priority = window.tile(source).map_or(0, |tile| heuristic(source, tile));
+    buffers.heap.push(Node { priority: source_priority, cost: 0, index: source_index });
+
+    while let Some(node) = buffers.heap.pop() {
+        //a cheaper way to this node was found after it was pushed
+        if node.cost > buffers.cost(node.index) {
+            continue;
+        }
+        if buffers.is_target(node.index) {
+            let target = window.position(node.index);
+            return Ok(rebuild_path(&buffers.parent, &buffers.teleported, |index| window.position(index), source, target, node.index, node.cost));
+        }
+        relax(buffers, window, model, &teleports, teleport_cost, &node, &heuristic);
+    }
+    Err(String::from("Path not found!"))
+}
+
+//pushes the neighbours of a node that got cheaper, and the teleports if the node is one
+fn relax(
+    buffers: &mut SearchBuffers,
+    window: &Window,
+    model: &dyn CostModel,
+    teleports: &[((usize, usize), &Tile)],
+    teleport_cost: usize,
+    node: &Node,
+    heuristic: &dyn Fn((usize, usize), &Tile) -> usize,
+) {
+    let position = window.position(node.index);
+    let Some(tile) = window.tile(position) else {
+        return;
+    };
+    for direction in DIRECTIONS.iter() {
+        let Some(next) = window.neighbour(position, direction) else {
+            continue;
+        };
+        let Some(next_tile) = window.tile(next) else {
+            continue;
+        };
+        let Some(step) = model.step_cost(tile, next_tile) else {
+            continue;
+        };
+        let cost = node.cost + step;
+        let index = window.index(next);
+        if cost < buffers.cost(index) {
+            buffers.set(index, cost, node.index, false);
+            buffers.heap.push(Node { priority: cost + heuristic(next, next_tile), cost, index });
+        }
+    }
+    if tile.tile_type == TileType::Teleport(true) {
+        for (teleport, teleport_tile) in teleports.iter() {
+            let cost = node.cost + teleport_cost;
+            let index = window.index(*teleport);
+            if cost < buffers.cost(index) {
+                buffers.set(index, cost, node.index, true);
+                buffers.heap.push(Node { priority: cost + heuristic(*teleport, teleport_tile), cost, index });
+            }
+        }
+    }
+}
+
+/*
+    Cost of the cheapest path from one source to every tile of a window, with the way back.
+    It owns its grid, so it can be kept after the map it was computed on changed,
+    as long as none of the tiles it depends on did.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct DistanceField {
+    source: (usize, usize),
+    top_left: (usize, usize),
+    rows: usize,
+    cols: usize,
+    cost: Vec<usize>,
+    parent: Vec<usize>,
+    teleported: Vec<bool>,
+}
+
+impl DistanceField {
+    pub(crate) fn source(&self) -> (usize, usize) {
+        self.source
+    }
+    fn index(&self, position: (usize, usize)) -> Option<usize> {
+        let row = position.0.checked_sub(self.top_left.0).filter(|row| *row < self.rows)?;
+        let col = position.1.checked_sub(self.top_left.1).filter(|col| *col < self.cols)?;
+        Some(row * self.cols + col)
+    }
+    fn position(&self, index: usize) -> (usize, usize) {
+        (self.top_left.0 + index / self.cols, self.top_left.1 + index % self.cols)
+    }
+    //None if the tile cannot be reached from the source
+    pub(crate) fn cost(&self, position: (usize, usize)) -> Option<usize> {
+        self.index(position).map(|index| self.cost[index]).filter(|cost| *cost != usize::MAX)
+    }
+    pub(crate) fn path_to(&self, target: (usize, usize)) -> Option<Path> {
+        let cost = self.cost(target)?;
+        let index = self.index(target)?;
+        Some(rebuild_path(&self.parent, &self.teleported, |index| self.position(index), self.source, target, index, cost))
+    }
+    //the cheapest of the targets, ties go to the smallest position so the choice does not depend on the set order
+    pub(crate) fn closest(&self, targets: &HashSet<(usize, usize)>) -> Result<Path, String> {
+        targets
+            .iter()
+            .filter_map(|target| self.cost(*target).map(|cost| (cost, *target)))
+            .min()
+            .and_then(|(_, target)| self.path_to(target))
+            .ok_or(String::from("Path not found!"))
+    }
+    /*
+        True if a change of the tile could change the field.
+        Only the tiles that were reached and the ones next to them were ever looked at,
+        a change anywhere else cannot open or close a path.
+     */
+    pub(crate) fn depends_on(&self, position: (usize, usize)) -> bool {
+        let (row, col) = position;
+        [Some((row, col)), Some((row + 1, col)), Some((row, col + 1)), row.checked_sub(1).map(|r| (r, col)), col.checked_sub(1).map(|c| (row, c))]
+            .into_iter()
+            .flatten()
+            .any(|neighbour| self.cost(neighbour).is_some())
+    }
+}
+
+//every tile of the window that can be reached from the source, without a target to stop at
+pub(crate) fn distance_field(
+    window: &Window,
+    source: (usize, usize),
+    model: &dyn CostModel,
+    teleports: &[(usize, usize)],
+    teleport_cost: usize,
+) -> DistanceField {
+    BUFFERS.with(|buffers| fill(&mut buffers.borrow_mut(), window, source, model, teleports, teleport_cost))
+}
+
+fn fill(
+    buffers: &mut SearchBuffers,
+    window: &Window,
+    source: (usize, usize),
+    model: &dyn CostModel,
+    teleports: &[(usize, usize)],
+    teleport_cost: usize,
+) -> DistanceField {
+    let teleports: Vec<((usize, usize), &Tile)> = teleports
+        .iter()
+        .filter_map(|teleport| window.tile(*teleport).map(|tile| (*teleport, tile)))
+        .collect();
+    let len = window.len();
+    buffers.reset(len);
+    if window.contains(source) {
+        let source_index = window.index(source);
+        buffers.set(source_index, 0, NO_PARENT, false);
+        buffers.heap.push(Node { priority: 0, cost: 0, index: source_index });
+    }
+    let no_heuristic = |_: (usize, usize), _: &Tile| 0;
+    while let Some(node) = buffers.heap.pop() {
+        if node.cost > buffers.cost(node.index) {
+            continue;
+        }
+        relax(buffers, window, model, &teleports, teleport_cost, &node, &no_heuristic);
+    }
+
+    let cost: Vec<usize> = (0..len).map(|index| buffers.cost(index)).collect();
+    let parent = (0..len).map(|index| if cost[index] == usize::MAX { NO_PARENT } else { buffers.parent[index] }).collect();
+    DistanceField {
+        source,
+        top_left: window.top_left,
+        rows: window.rows,
+        cols: window.cols,
+        cost,
+        parent,
+        teleported: buffers.teleported[..len].to_vec(),
+    }
+}
+
+//follows the parents back from the target, the actions are only built for the path that is returned
+fn rebuild_path(
+    parent: &[usize],
+    teleported: &[bool],
+    position: impl Fn(usize) -> (usize, usize),
+    source: (usize, usize),
+    destination: (usize, usize),
+    target: usize,
+    cost: usize,
+) -> Path {
+    let mut path = Path::new(source, destination, cost);
+    let mut index = target;
+    while parent[index] != NO_PARENT {
+        let (from, to) = (position(parent[index]), position(index));
+        let action = if teleported[index] {
+            Action::Teleport(to)
+        } else if to.0 < from.0 {
+            Action::Go(Direction::Up)
+        } else if to.0 > from.0 {
+            Action::Go(Direction::Down)
+        } else if to.1 < from.1 {
+            Action::Go(Direction::Left)
+        } else {
+            Action::Go(Direction::Right)
+        };
+        path.actions.push_front(action);
+        index = parent[index];
+    }
+    path
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use robotics_lib::world::tile::Content;
+
+    //one step costs the elevation of the tile it lands on, walls cannot be walked
+    pub(crate) struct ElevationCost;
+
+    impl CostModel for ElevationCost {
+        fn step_cost(&self, _from: &Tile, to: &Tile) -> Option<usize> {
+            to.tile_type.properties().walk().then_some(to.elevation)
+        }
+        fn min_step_cost(&self) -> usize {
+            1
+        }
+    }
+
+    //'.' is grass, '#' is a wall, 'T' is a teleport, digits are grass at that elevation
+    pub(crate) fn grid(rows: &[&str]) -> Vec<Vec<Option<Tile>>> {
+        rows.iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| {
+                        let (tile_type, elevation) = match c {
+                            '#' => (TileType::Wall, 1),
+                            'T' => (TileType::Teleport(true), 1),
+                            '?' => return None,
+                            c => (TileType::Grass, c.to_digit(10).unwrap_or(1) as usize),
+                        };
+                        Some(Tile { tile_type, content: Content::None, elevation })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn shortest(map: &Vec<Vec<Option<Tile>>>, source: (usize, usize), target: (usize, usize), mode: SearchMode) -> Result<Path, String> {
+        find_path(&Window::full(map), source, &HashSet::from([target]), &ElevationCost, &[], 0, mode)
+    }
+
+    #[test]
+    fn test_path_around_walls(){
+        let map = grid(&[
+            "....",
+            ".##.",
+            "..#.",
+            "....",
+        ]);
+        let path = shortest(&map, (2, 1), (1, 3), SearchMode::Dijkstra).unwrap();
+        assert_eq!(path.cost, 5);
+        assert_eq!(path.actions.len(), 5);
+        assert_eq!(path.destination, (1, 3));
+        assert!(matches!(path.actions[0], Action::Go(Direction::Down) | Action::Go(Direction::Left)));
+        assert!(shortest(&map, (2, 1), (5, 5), SearchMode::Dijkstra).is_err());
+        assert_eq!(shortest(&map, (2, 1), (2, 1), SearchMode::AStar).unwrap().cost, 0);
+    }
+
+    #[test]
+    fn test_a_star_agrees_with_dijkstra(){
+        let map = grid(&[
+            "1119111",
+            "1#19191",
+            "1#11191",
+            "1####91",
+            "1111111",
+        ]);
+        for target in [(0, 6), (2, 3), (4, 6), (0, 3)] {
+            let dijkstra = shortest(&map, (0, 0), target, SearchMode::Dijkstra).unwrap();
+            let a_star = shortest(&map, (0, 0), target, SearchMode::AStar).unwrap();
+            assert_eq!(dijkstra.cost, a_star.cost);
+        }
+    }
+
+    #[test]
+    fn test_window_limits_the_search(){
+        let map = grid(&[
+            ".....",
+            ".###.",
+            ".#...",
+            ".###.",
+            ".....",
+        ]);
+        let window = Window::around(&map, (2, 2), 3);
+        assert!(window.contains((1, 1)) && !window.contains((0, 0)));
+        //the target is known, but outside the window
+        let targets = HashSet::from([(2, 0)]);
+        assert!(find_path(&window, (2, 2), &targets, &ElevationCost, &[], 0, SearchMode::Dijkstra).is_err());
+        //a bigger search before and after does not leave anything behind
+        assert_eq!(shortest(&map, (2, 2), (2, 0), SearchMode::AStar).unwrap().cost, 10);
+        let targets = HashSet::from([(2, 3)]);
+        assert_eq!(find_path(&window, (2, 2), &targets, &ElevationCost, &[], 0, SearchMode::Dijkstra).unwrap().cost, 1);
+    }
+
+    #[test]
+    fn test_teleports(){
+        let map = grid(&[
+            "T#...",
+            ".#...",
+            ".#..T",
+        ]);
+        let teleports = [(0, 0), (2, 4)];
+        let targets = HashSet::from([(0, 4)]);
+        let path = find_path(&Window::full(&map), (1, 0), &targets, &ElevationCost, &teleports, 5, SearchMode::AStar).unwrap();
+        assert_eq!(path.cost, 1 + 5 + 2);
+        assert!(matches!(path.actions[1], Action::Teleport((2, 4))));
+    }
+
+    #[test]
+    fn test_walking_beats_a_teleport_that_costs_more(){
+        let map = grid(&[
+            "T...T",
+        ]);
+        let teleports = [(0, 0), (0, 4)];
+        let targets = HashSet::from([(0, 4)]);
+        let path = find_path(&Window::full(&map), (0, 0), &targets, &ElevationCost, &teleports, 10, SearchMode::AStar).unwrap();
+        assert_eq!(path.cost, 4);
+        assert!(path.actions.iter().all(|action| matches!(action, Action::Go(_))));
+        let path = find_path(&Window::full(&map), (0, 0), &targets, &ElevationCost, &teleports, 3, SearchMode::AStar).unwrap();
+        assert_eq!(path.cost, 3);
+    }
+
+    #[test]
+    fn test_teleport_network(){
+        //three islands, only the teleports connect them
+        let map = grid(&[
+            ".T#T.",
+            "###..",
+            "T.#..",
+        ]);
+        let teleports = [(0, 1), (0, 3), (2, 0)];
+        let targets = HashSet::from([(2, 1)]);
+        let path = find_path(&Window::full(&map), (0, 4), &targets, &ElevationCost, &teleports, 5, SearchMode::AStar).unwrap();
+        //a step to the teleport, the jump and a step off it
+        assert_eq!(path.cost, 1 + 5 + 1);
+        assert!(matches!(path.actions[1], Action::Teleport((2, 0))));
+        //without teleports the islands cannot be reached
+        assert!(find_path(&Window::full(&map), (0, 4), &targets, &ElevationCost, &[], 5, SearchMode::AStar).is_err());
+        //the field jumps the same way
+        let field = distance_field(&Window::full(&map), (0, 4), &ElevationCost, &teleports, 5);
+        assert_eq!(field.cost((0, 0)), Some(1 + 5 + 1));
+    }
+
+    #[test]
+    fn test_teleports_outside_the_window_are_not_used(){
+        let map = grid(&[
+            "...#.T",
+            ".T.#..",
+            "...#..",
+        ]);
+        let teleports = [(1, 1), (0, 5)];
+        let window = Window::around(&map, (1, 1), 3);
+        let field = distance_field(&window, (1, 0), &ElevationCost, &teleports, 1);
+        assert_eq!(field.cost((0, 5)), None);
+        assert_eq!(field.cost((1, 1)), Some(1));
+        let targets = HashSet::from([(0, 5)]);
+        assert!(find_path(&window, (1, 0), &targets, &ElevationCost, &teleports, 1, SearchMode::Dijkstra).is_err());
+        //the whole map has room for the jump
+        let path = find_path(&Window::full(&map), (1, 0), &targets, &ElevationCost, &teleports, 1, SearchMode::Dijkstra).unwrap();
+        assert_eq!(path.cost, 2);
+        assert_eq!(path.actions.len(), 2);
+    }
+
+    #[test]
+    fn test_distance_field_agrees_with_the_search(){
+        let map = grid(&[
+            "T119111",
+            "1#19191",
+            "1#11191",
+            "1####9?",
+            "111111T",
+        ]);
+        let teleports = [(0, 0), (4, 6)];
+        let field = distance_field(&Window::full(&map), (1, 0), &ElevationCost, &teleports, 3);
+        for row in 0..map.len() {
+            for col in 0..map[row].len() {
+                let targets = HashSet::from([(row, col)]);
+                let path = find_path(&Window::full(&map), (1, 0), &targets, &ElevationCost, &teleports, 3, SearchMode::Dijkstra);
+                assert_eq!(field.cost((row, col)), path.as_ref().ok().map(|path| path.cost));
+                if let Some(from_field) = field.path_to((row, col)) {
+                    assert_eq!(from_field.actions.len(), path.unwrap().actions.len());
+                }
+            }
+        }
+        assert_eq!(field.closest(&HashSet::from([(4, 5), (2, 3)])).unwrap().destination, (4, 5));
+        assert!(field.depends_on((3, 6)) && field.cost((3, 6)).is_none());
+        //a window that does not contain the source reaches nothing
+        let field = distance_field(&Window::around(&map, (4, 6), 3), (0, 0), &ElevationCost, &[], 0);
+        assert_eq!(field.cost((4, 6)), None);
+    }
+}
@@ -0,0 +1,131 @@
+use robotics_lib::runner::Runner;
+use robotics_lib::utils::LibError;
+
+use crate::jerry::Jerry;
+use crate::logging;
+use crate::procedural::{ProceduralConfig, ProceduralGenerator};
+use crate::replay::{Replay, ReplaySink};
+use crate::scheduler::SchedulerPolicy;
+use crate::sink::StateSink;
+use crate::telemetry::{to_csv, TelemetryLog};
+use crate::worldloader::{WorldLoadError, WorldgeneratorUnwrap};
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+/*
+    Runs the robot without the frontend, so that the AI can be run and tested natively.
+    The robot gets a sink that only keeps the telemetry, and the replay if asked for.
+ */
+#[derive(Debug, Clone)]
+pub struct HeadlessConfig {
+    pub ticks: usize,
+    // world file to load, the embedded one if None
+    pub world: Option<PathBuf>,
+    // generate the world instead of loading it
+    pub procedural: Option<ProceduralConfig>,
+    // seed of the robot's rng
+    pub seed: u64,
+    // where to save the replay of the run, nothing is recorded if None
+    pub record: Option<PathBuf>,
+    // where to save the telemetry of every tick as csv
+    pub telemetry: Option<PathBuf>,
+}
+
+impl Default for HeadlessConfig {
+    fn default() -> Self {
+        Self { ticks: 1000, world: None, procedural: None, seed: 0, record: None, telemetry: None }
+    }
+}
+
+#[derive(Debug)]
+pub enum HeadlessError {
+    World(WorldLoadError),
+    Runner(LibError),
+    Record(String),
+    Telemetry(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeadlessReport {
+    pub ticks: usize,
+    pub failed_ticks: usize,
+}
+
+pub fn run_headless(config: &HeadlessConfig) -> Result<HeadlessReport, HeadlessError> {
+    let replay = Rc::new(RefCell::new(Replay::default()));
+    let telemetry = TelemetryLog::new();
+    let sink: Box<dyn StateSink> = match config.record {
+        Some(_) => Box::new(ReplaySink::new(Box::new(telemetry.clone()), replay.clone())),
+        None => Box::new(telemetry.clone()),
+    };
+    let jerry = Jerry::new(sink, SchedulerPolicy::Alternating.build(), config.seed);
+    let runner = match &config.procedural {
+        Some(procedural) => {
+            let mut generator = ProceduralGenerator::new(procedural.clone());
+            Runner::new(Box::new(jerry), &mut generator)
+        }
+        None => {
+            let mut generator = WorldgeneratorUnwrap::init(false, config.world.clone());
+            // the runner panics on a bad world, so it is checked first
+            generator.try_gen().map_err(HeadlessError::World)?;
+            Runner::new(Box::new(jerry), &mut generator)
+        }
+    };
+    let mut runner = runner.map_err(HeadlessError::Runner)?;
+
+    let mut report = HeadlessReport { ticks: 0, failed_ticks: 0 };
+    for _ in 0..config.ticks {
+        // same as in the browser: a failing tick is reported and the game goes on
+        if let Err(error) = runner.game_tick() {
+            log::error!(target: logging::RUNNER, "Error with run: {:?}", error);
+            report.failed_ticks += 1;
+        }
+        report.ticks += 1;
+    }
+
+    if let Some(path) = &config.record {
+        let bytes = replay.borrow().to_bytes().map_err(HeadlessError::Record)?;
+        fs::write(path, bytes).map_err(|e| HeadlessError::Record(e.to_string()))?;
+    }
+    if let Some(path) = &config.telemetry {
+        fs::write(path, to_csv(&telemetry.samples())).map_err(|e| HeadlessError::Telemetry(e.to_string()))?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_headless(){
+        let report = run_headless(&HeadlessConfig { ticks: 20, ..Default::default() }).unwrap();
+        assert_eq!(report.ticks, 20);
+    }
+
+    #[test]
+    fn test_run_headless_procedural(){
+        let procedural = ProceduralConfig { seed: 3, size: 40, ..Default::default() };
+        let config = HeadlessConfig { ticks: 20, procedural: Some(procedural), ..Default::default() };
+        assert_eq!(run_headless(&config).unwrap().ticks, 20);
+    }
+
+    #[test]
+    fn test_run_headless_telemetry(){
+        let path = std::env::temp_dir().join("headless_telemetry_test.csv");
+        let config = HeadlessConfig { ticks: 10, telemetry: Some(path.clone()), ..Default::default() };
+        run_headless(&config).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        //header and one line per tick
+        assert_eq!(csv.lines().count(), 11);
+    }
+
+    #[test]
+    fn test_run_headless_missing_world(){
+        let config = HeadlessConfig { ticks: 20, world: Some(PathBuf::from("does/not/exist.bin")), ..Default::default() };
+        assert!(matches!(run_headless(&config), Err(HeadlessError::World(WorldLoadError::Io(_)))));
+    }
+}
@@ -0,0 +1,93 @@
+use charting_tools::charted_coordinate::ChartedCoordinate;
+use robotics_lib::runner::Runnable;
+use robotics_lib::world::tile::Tile;
+use robotics_lib::world::World;
+
+use crate::fast_paths::{get_path_to_coordinates, PathCost};
+use crate::jerry::Jerry;
+
+// tiles further than this from the robot are not worth a dijkstra every tick
+const INSPECT_WINDOW: usize = 41;
+
+/*
+    What the AI knows about a tile picked in the frontend.
+    The tile itself is already in the frontend map, this is only the part that needs the robot.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TileReport {
+    pub(crate) position: (usize, usize),
+    pub(crate) in_frontier: bool,
+    pub(crate) in_planned_road: bool,
+    pub(crate) on_road: bool,
+    // cost of the cheapest way there from where the robot is now
+    pub(crate) go_cost: Result<usize, String>,
+}
+
+pub(crate) fn inspect_tile(jerry: &mut Jerry, world: &World, map: &Vec<Vec<Option<Tile>>>, position: (usize, usize)) -> TileReport {
+    let coordinate = ChartedCoordinate(position.0, position.1);
+    let in_frontier = jerry.missions.iter()
+        .filter_map(|mission| mission.explorer())
+        .any(|explorer| explorer.frontier_hs.contains(&coordinate));
+    let in_planned_road = jerry.missions.iter()
+        .filter_map(|mission| mission.road_builder())
+        .any(|road_builder| road_builder.to_pave().contains(&coordinate));
+    let on_road = jerry.road_tiles.contains(&coordinate);
+
+    let robot = (jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col());
+    let distance = robot.0.abs_diff(position.0).max(robot.1.abs_diff(position.1));
+    let go_cost = if position.0 >= map.len() || position.1 >= map.len() {
+        Err(String::from("Outside the map!"))
+    } else if position == robot {
+        Ok(0)
+    } else if distance > INSPECT_WINDOW / 2 {
+        Err(String::from("Too far from the robot!"))
+    } else {
+        //the window around the robot just needs to contain the tile
+        get_path_to_coordinates(world, jerry, map, false, position, 2 * distance + 1, PathCost::Energy).map(|path| path.cost)
+    };
+
+    TileReport { position, in_frontier, in_planned_road, on_road, go_cost }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::SchedulerPolicy;
+    use crate::sink::StateSink;
+    use crate::worldloader::WorldgeneratorUnwrap;
+    use robotics_lib::runner::Runner;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    struct InspectingSink {
+        position: (usize, usize),
+        reports: Rc<RefCell<Vec<TileReport>>>,
+    }
+
+    impl StateSink for InspectingSink {
+        fn inspected_tile(&mut self) -> Option<(usize, usize)> {
+            Some(self.position)
+        }
+        fn tile_inspected(&mut self, report: TileReport) {
+            self.reports.borrow_mut().push(report);
+        }
+    }
+
+    #[test]
+    fn test_inspected_every_tick(){
+        let reports = Rc::new(RefCell::new(Vec::new()));
+        let sink = InspectingSink { position: (10000, 0), reports: reports.clone() };
+        let jerry = Jerry::new(Box::new(sink), SchedulerPolicy::Alternating.build(), 0);
+        let mut generator = WorldgeneratorUnwrap::init(false, None);
+        let mut runner = Runner::new(Box::new(jerry), &mut generator).unwrap();
+        for _ in 0..3 {
+            let _ = runner.game_tick();
+        }
+
+        let reports = reports.borrow();
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].position, (10000, 0));
+        assert!(reports[0].go_cost.is_err());
+        assert!(!reports[0].in_frontier);
+    }
+}
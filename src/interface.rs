@@ -1,44 +1,48 @@
-use charting_tools::charted_coordinate::ChartedCoordinate;
-use charting_tools::ChartingTools;
-use ohcrab_weather::weather_tool::WeatherPredictionTool;
 // Project imports
-use robotics_lib::energy::Energy;
-use robotics_lib::event::events::Event;
-use robotics_lib::interface::{look_at_sky, robot_map};
-
-use robotics_lib::runner::backpack::BackPack;
-use robotics_lib::runner::{Robot, Runnable, Runner};
+use robotics_lib::event::events::Event as RobotEvent;
+use robotics_lib::runner::Runner;
 use robotics_lib::utils::LibError;
-use robotics_lib::world::coordinates::Coordinate;
 use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
 
-use crate::explorer::new_explorer;
-use crate::utils::{
-    calculate_spatial_index, execute_mission, get_world_dimension, ActiveRegion, Mission,
-};
+use crate::control::{RunAction, RunControl};
+use crate::jerry::Jerry;
+use crate::logging::{self, LogEntry};
+use crate::procedural::{ProceduralConfig, ProceduralGenerator};
+use crate::inspector::TileReport;
+use crate::map_tracker::MapDiff;
+use crate::mission_control::{MissionCommand, MissionSummary};
+use crate::overlay::{sector_bounds, AiOverlay, OverlayLayer};
+use crate::replay::{Replay, ReplayCursor, ReplaySink};
+use crate::snapshot::{RobotSnapshot, SavedWorld, Snapshot, SnapshotGenerator};
+use crate::scheduler::SchedulerPolicy;
+use crate::score::{effective_score_map, parse_score_map, ScoreTracker};
+use crate::sink::StateSink;
+use crate::telemetry::{TelemetrySample, TelemetrySeries};
+use crate::utils::{calculate_spatial_index, MissionStatus};
+use crate::worldloader::WorldgeneratorUnwrap;
 
 use robotics_lib::world::tile::TileType::{
     DeepWater, Grass, Hill, Lava, Mountain, Sand, ShallowWater, Snow, Street, Teleport,
 };
 use robotics_lib::world::tile::{Content, Tile, TileType};
-use robotics_lib::world::World;
-use rust_and_furious_dynamo::dynamo::Dynamo;
-use rust_eze_tomtom::TomTom;
-use std::collections::{HashMap, HashSet, VecDeque};
-use vent_tool_ascii_crab::Vent;
+use std::collections::HashMap;
+use std::ops::Range;
 
 // Frontend
-include!("worldloader.rs");
 use bounce::*;
-use log::info;
-use std::cell::RefCell;
-use std::path::PathBuf;
+use log::{error, info};
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
-use wasm_bindgen::JsCast;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{window, Element, HtmlInputElement};
+use web_sys::{
+    window, CanvasRenderingContext2d, Element, HtmlCanvasElement, HtmlImageElement, HtmlInputElement,
+    HtmlSelectElement,
+};
 use yew::prelude::*;
 use yew::{function_component, html, Html, Properties};
+use yew_hooks::use_interval;
 
 // enums to allow updates inside the impl
 #[derive(Clone, PartialEq, Atom)]
@@ -56,16 +60,55 @@ impl Default for BackpackState {
     }
 }
 
-#[derive(Clone, PartialEq, Atom)]
+/*
+    The robot map as the frontend knows it.
+    The robot only sends the tiles that changed, they are applied here and remembered
+    until the map view draws them, so neither side goes through the whole map every tick.
+ */
+#[derive(Default)]
+pub(crate) struct SharedMap {
+    tiles: Vec<Vec<Option<Tile>>>,
+    dirty: Vec<(usize, usize)>,
+    redraw_all: bool,
+}
+
+impl SharedMap {
+    fn apply(&mut self, size: usize, diff: &MapDiff) {
+        if self.tiles.len() != size {
+            self.tiles = vec![vec![None; size]; size];
+            self.redraw_all = true;
+        }
+        for (row, col, tile) in diff.iter() {
+            if let Some(cell) = self.tiles.get_mut(*row).and_then(|map_row| map_row.get_mut(*col)) {
+                *cell = tile.clone();
+                self.dirty.push((*row, *col));
+            }
+        }
+    }
+    fn replace(&mut self, tiles: Vec<Vec<Option<Tile>>>) {
+        self.tiles = tiles;
+        self.dirty.clear();
+        self.redraw_all = true;
+    }
+}
+
+#[derive(Clone, Atom)]
 pub(crate) struct WorldState {
-    world: Vec<Vec<Option<Tile>>>,
+    // always the same map, the counter tells when it changed
+    map: Rc<RefCell<SharedMap>>,
     counter: usize,
 }
 
+impl PartialEq for WorldState {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.map, &other.map) && self.counter == other.counter
+    }
+}
+
 impl Default for WorldState {
     fn default() -> Self {
         Self {
-            world: vec![vec![None]], // Placeholders
+            map: Rc::new(RefCell::new(SharedMap::default())),
             counter: 0,
         }
     }
@@ -113,11 +156,18 @@ impl Default for EnergyState {
 #[derive(Clone, PartialEq, Atom)]
 pub(crate) struct ExtrasState {
     pub(crate) score: f32,
+    // ticks where the score changed, for the sparkline
+    pub(crate) history: Rc<Vec<(usize, f32)>>,
+    pub(crate) breakdown: Vec<(Content, f32)>,
 }
 
 impl Default for ExtrasState {
     fn default() -> Self {
-        Self { score: 0.0 }
+        Self {
+            score: 0.0,
+            history: Rc::new(Vec::new()),
+            breakdown: Vec::new(),
+        }
     }
 }
 
@@ -127,6 +177,14 @@ pub(crate) struct StartingSettings {
     tile_size: f32,
     follow_robot: bool,
     tick_time: u32,
+    // None runs until stopped from the Menu
+    tick_limit: Option<usize>,
+    scheduler: SchedulerPolicy,
+    world_kind: WorldKind,
+    seed: u64,
+    world_size: usize,
+    // custom scores like "Rock=2, Tree=0.5", empty for the default ones
+    score_map: String,
 }
 
 impl Default for StartingSettings {
@@ -136,13 +194,166 @@ impl Default for StartingSettings {
             tile_size: 40.0,
             follow_robot: true,
             tick_time: 0,
+            tick_limit: Some(100000),
+            scheduler: SchedulerPolicy::Alternating,
+            world_kind: WorldKind::File,
+            seed: 0,
+            world_size: 100,
+            score_map: String::new(),
+        }
+    }
+}
+
+// overlay layers turned on from the Menu
+#[derive(Clone, PartialEq, Default, Atom)]
+pub(crate) struct OverlayState {
+    layers: Vec<OverlayLayer>,
+    // read by the sink, so the robot only collects its state when something is shown
+    wanted: Rc<Cell<bool>>,
+}
+
+// internal state of the AI sent by the robot
+#[derive(Clone, PartialEq, Default, Atom)]
+pub(crate) struct OverlayData {
+    overlay: Rc<AiOverlay>,
+}
+
+// tile under the mouse and tile clicked in the map, the clicked one wins
+#[derive(Clone, PartialEq, Default, Atom)]
+pub(crate) struct InspectorState {
+    hovered: Option<(usize, usize)>,
+    pinned: Option<(usize, usize)>,
+    // read by the sink, the robot reports on this tile every tick
+    request: Rc<Cell<Option<(usize, usize)>>>,
+}
+
+impl InspectorState {
+    fn shown(&self) -> Option<(usize, usize)> {
+        self.pinned.or(self.hovered)
+    }
+}
+
+#[derive(Clone, PartialEq, Default, Atom)]
+pub(crate) struct TileReportState {
+    report: Option<TileReport>,
+}
+
+// the mission queue as sent by the robot, and the commands waiting for the next tick
+#[derive(Clone, PartialEq, Default, Atom)]
+pub(crate) struct MissionState {
+    missions: Vec<MissionSummary>,
+    commands: Rc<RefCell<Vec<MissionCommand>>>,
+}
+
+// points drawn in every telemetry chart, at most twice as many are kept
+const TELEMETRY_POINTS: usize = 200;
+// the charts are updated once every this many ticks
+const TELEMETRY_PUBLISH_TICKS: usize = 10;
+
+// the telemetry samples of the current game, downsampled for the charts
+#[derive(Clone, Atom)]
+pub(crate) struct TelemetryState {
+    series: Rc<RefCell<TelemetrySeries>>,
+    counter: usize,
+}
+
+impl PartialEq for TelemetryState {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.series, &other.series) && self.counter == other.counter
+    }
+}
+
+impl Default for TelemetryState {
+    fn default() -> Self {
+        Self {
+            series: Rc::new(RefCell::new(TelemetrySeries::new(TELEMETRY_POINTS))),
+            counter: 0,
+        }
+    }
+}
+
+// replay recorded while the robot runs, the robot is paused while it is played back
+#[derive(Clone, Default, Atom)]
+pub(crate) struct ReplayState {
+    replay: Rc<RefCell<Replay>>,
+    paused: Rc<Cell<bool>>,
+    // replay loaded from a file, played instead of the recorded one
+    loaded: Option<Rc<RefCell<Replay>>>,
+}
+
+impl ReplayState {
+    fn shown(&self) -> Rc<RefCell<Replay>> {
+        self.loaded.clone().unwrap_or_else(|| self.replay.clone())
+    }
+}
+
+impl PartialEq for ReplayState {
+    fn eq(&self, other: &Self) -> bool {
+        let same_loaded = match (&self.loaded, &other.loaded) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        Rc::ptr_eq(&self.replay, &other.replay) && Rc::ptr_eq(&self.paused, &other.paused) && same_loaded
+    }
+}
+
+// snapshot asked from the Menu, the robot fills it at the end of the next tick
+#[derive(Default)]
+pub(crate) struct SnapshotSlot {
+    requested: Cell<bool>,
+    robot: RefCell<Option<RobotSnapshot>>,
+    score: RefCell<Option<ScoreTracker>>,
+    // world made by the generator when the game started
+    world: RefCell<Option<SavedWorld>>,
+}
+
+// the game that is running, a new generation is started when a snapshot is loaded
+#[derive(Clone, Default, Atom)]
+pub(crate) struct SessionState {
+    generation: usize,
+    live: Rc<Cell<usize>>,
+    resume: Option<Rc<Snapshot>>,
+    snapshots: Rc<SnapshotSlot>,
+    control: Rc<RunControl>,
+}
+
+impl PartialEq for SessionState {
+    fn eq(&self, other: &Self) -> bool {
+        self.generation == other.generation
+    }
+}
+
+// which generator builds the world
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum WorldKind {
+    File,
+    Procedural,
+}
+
+impl WorldKind {
+    const ALL: [WorldKind; 2] = [WorldKind::File, WorldKind::Procedural];
+
+    fn label(&self) -> &'static str {
+        match self {
+            WorldKind::File => "World File",
+            WorldKind::Procedural => "Procedural",
         }
     }
 }
 
+// world file uploaded on the start screen, the embedded world is used if there is none
+#[derive(Clone, PartialEq, Default, Atom)]
+pub(crate) struct UploadedWorld {
+    name: String,
+    bytes: Option<Rc<Vec<u8>>>,
+    error: Option<String>,
+}
+
 #[function_component(Main)]
 pub fn main() -> Html {
     let settings = use_atom::<StartingSettings>();
+    let uploaded_world = use_atom::<UploadedWorld>();
     // info!("Rendered Main");
 
     html! {
@@ -152,14 +363,19 @@ pub fn main() -> Html {
                 // Normal robot display
                 html! {
                     <div id="info">
-                        // <BackP/>
+                        <BackP/>
                         <EnergyBar/>
                         <EnviromentBar />
                         // <Zoom />
                         <br/>
                         <MapView/>
+                        <TileInspector />
                         <Menu />
-                        // <ScoreDisplay />
+                        <ReplayControls />
+                        <ScoreDisplay />
+                        <TelemetryDashboard />
+                        <MissionQueue />
+                        <LogConsole />
                         <TimoAi />
                     </div>
                 }
@@ -171,7 +387,98 @@ pub fn main() -> Html {
                     Callback::from(move |e: InputEvent| {
                         let input: HtmlInputElement = e.target_unchecked_into();
 
-                        settings.set(StartingSettings { follow_robot: settings.follow_robot.clone(), start_ai: settings.start_ai.clone(), tile_size: settings.tile_size.clone(), tick_time: input.value().parse::<u32>().expect("Expected u32 as tick time") });
+                        settings.set(StartingSettings { tick_time: input.value().parse::<u32>().expect("Expected u32 as tick time"), ..(*settings).clone() });
+                    })
+                };
+
+                let on_tick_limit_input = {
+                    let settings = settings.clone();
+
+                    Callback::from(move |e: InputEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        settings.set(StartingSettings { tick_limit: input.value().parse::<usize>().ok(), ..(*settings).clone() });
+                    })
+                };
+
+                let on_scheduler_change = {
+                    let settings = settings.clone();
+
+                    Callback::from(move |e: yew::prelude::Event| {
+                        let select: HtmlSelectElement = e.target_unchecked_into();
+                        if let Some(policy) = SchedulerPolicy::from_label(&select.value()) {
+                            settings.set(StartingSettings { scheduler: policy, ..(*settings).clone() });
+                        }
+                    })
+                };
+
+                let on_world_kind_change = {
+                    let settings = settings.clone();
+
+                    Callback::from(move |e: yew::prelude::Event| {
+                        let select: HtmlSelectElement = e.target_unchecked_into();
+                        if let Some(kind) = WorldKind::ALL.iter().find(|kind| kind.label() == select.value()) {
+                            settings.set(StartingSettings { world_kind: *kind, ..(*settings).clone() });
+                        }
+                    })
+                };
+
+                let on_seed_input = {
+                    let settings = settings.clone();
+
+                    Callback::from(move |e: InputEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        if let Ok(seed) = input.value().parse::<u64>() {
+                            settings.set(StartingSettings { seed, ..(*settings).clone() });
+                        }
+                    })
+                };
+
+                let on_world_size_input = {
+                    let settings = settings.clone();
+
+                    Callback::from(move |e: InputEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        if let Ok(world_size) = input.value().parse::<usize>() {
+                            settings.set(StartingSettings { world_size, ..(*settings).clone() });
+                        }
+                    })
+                };
+
+                let on_score_map_input = {
+                    let settings = settings.clone();
+
+                    Callback::from(move |e: InputEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        settings.set(StartingSettings { score_map: input.value(), ..(*settings).clone() });
+                    })
+                };
+                let score_map_error = parse_score_map(&settings.score_map).err();
+
+                let on_world_upload = {
+                    let uploaded_world = uploaded_world.clone();
+
+                    Callback::from(move |e: yew::prelude::Event| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                            uploaded_world.set(UploadedWorld::default());
+                            return;
+                        };
+                        let uploaded_world = uploaded_world.clone();
+                        wasm_bindgen_futures::spawn_local(async move {
+                            let name = file.name();
+                            let bytes = match read_file(&file).await {
+                                Ok(bytes) => bytes,
+                                Err(e) => {
+                                    uploaded_world.set(UploadedWorld { name, bytes: None, error: Some(e) });
+                                    return;
+                                }
+                            };
+                            // checked now, so that the runner never gets a broken world
+                            match WorldgeneratorUnwrap::from_bytes(bytes.clone()).try_gen() {
+                                Ok(_) => uploaded_world.set(UploadedWorld { name, bytes: Some(Rc::new(bytes)), error: None }),
+                                Err(e) => uploaded_world.set(UploadedWorld { name, bytes: None, error: Some(e.to_string()) }),
+                            }
+                        });
                     })
                 };
 
@@ -180,7 +487,7 @@ pub fn main() -> Html {
                     let settings = settings.clone();
 
                     Callback::from(move |_| {
-                        settings.set(StartingSettings { follow_robot: settings.follow_robot.clone(), start_ai: true, tile_size: settings.tile_size.clone(), tick_time: settings.tick_time.clone() });
+                        settings.set(StartingSettings { start_ai: true, ..(*settings).clone() });
                     })
                 };
 
@@ -189,7 +496,48 @@ pub fn main() -> Html {
                     <div id="start">
                         <label for={"ticktime"}>{"Tick Delay (ms)"}</label>
                         <input id={"ticktime"} type={"text"} oninput={on_tick_time_input} value={settings.tick_time.to_string()}/>
-                        <button onclick={start_game} >{"Start Game"}</button>
+                        <label for={"ticklimit"}>{"Tick Limit"}</label>
+                        <input id={"ticklimit"} type={"text"} oninput={on_tick_limit_input} placeholder={"none"} value={settings.tick_limit.map(|limit| limit.to_string()).unwrap_or_default()}/>
+                        <label for={"scheduler"}>{"Scheduler"}</label>
+                        <select id={"scheduler"} onchange={on_scheduler_change}>
+                            { for SchedulerPolicy::ALL.iter().map(|policy| html! {
+                                <option value={policy.to_string()} selected={*policy == settings.scheduler}>{policy.to_string()}</option>
+                            })}
+                        </select>
+                        // used by the robot and by the procedural generator
+                        <label for={"seed"}>{"Seed"}</label>
+                        <input id={"seed"} type={"text"} oninput={on_seed_input} value={settings.seed.to_string()}/>
+                        <label for={"worldkind"}>{"World"}</label>
+                        <select id={"worldkind"} onchange={on_world_kind_change}>
+                            { for WorldKind::ALL.iter().map(|kind| html! {
+                                <option value={kind.label()} selected={*kind == settings.world_kind}>{kind.label()}</option>
+                            })}
+                        </select>
+                        { match settings.world_kind {
+                            WorldKind::File => html! {
+                                <>
+                                    <label for={"worldfile"}>{"World (.bin)"}</label>
+                                    <input id={"worldfile"} type={"file"} accept={".bin"} onchange={on_world_upload}/>
+                                    { match &uploaded_world.error {
+                                        Some(error) => html! { <p class={"error"}>{format!("{}: {}", uploaded_world.name, error)}</p> },
+                                        None => html! {},
+                                    }}
+                                </>
+                            },
+                            WorldKind::Procedural => html! {
+                                <>
+                                    <label for={"worldsize"}>{"World Size"}</label>
+                                    <input id={"worldsize"} type={"text"} oninput={on_world_size_input} value={settings.world_size.to_string()}/>
+                                </>
+                            },
+                        }}
+                        <label for={"scoremap"}>{"Score Map"}</label>
+                        <input id={"scoremap"} type={"text"} oninput={on_score_map_input} placeholder={"Rock=2, Tree=0.5"} value={settings.score_map.clone()}/>
+                        { match &score_map_error {
+                            Some(error) => html! { <p class={"error"}>{error}</p> },
+                            None => html! {},
+                        }}
+                        <button onclick={start_game} disabled={(settings.world_kind == WorldKind::File && uploaded_world.error.is_some()) || score_map_error.is_some()}>{"Start Game"}</button>
                     </div>
                 }
             }
@@ -205,6 +553,10 @@ pub fn main() -> Html {
 #[function_component(Menu)]
 fn menu() -> Html {
     let settings = use_atom::<StartingSettings>();
+    let session = use_atom::<SessionState>();
+    let replay_state = use_atom::<ReplayState>();
+    let overlay_state = use_atom::<OverlayState>();
+    let snapshot_message = use_state_eq(String::new);
 
     // Input Callbacks
     let onchange_slider = {
@@ -215,10 +567,8 @@ fn menu() -> Html {
             let value = input.value().parse::<f32>().unwrap_or(30.0);
             log::info!("value = {}", &value);
             settings.set(StartingSettings {
-                start_ai: settings.start_ai.clone(),
-                follow_robot: settings.follow_robot.clone(),
                 tile_size: value,
-                tick_time: settings.tick_time.clone(),
+                ..(*settings).clone()
             });
         })
     };
@@ -230,17 +580,438 @@ fn menu() -> Html {
             settings.set(StartingSettings {
                 follow_robot: !settings.follow_robot.clone(),
                 start_ai: true,
-                tile_size: settings.tile_size.clone(),
-                tick_time: settings.tick_time.clone(),
+                ..(*settings).clone()
+            });
+        })
+    };
+
+    let save_snapshot = {
+        let snapshots = session.snapshots.clone();
+        let snapshot_message = snapshot_message.clone();
+
+        Callback::from(move |_| {
+            let snapshots = snapshots.clone();
+            let snapshot_message = snapshot_message.clone();
+            snapshots.requested.set(true);
+            wasm_bindgen_futures::spawn_local(async move {
+                // the robot takes the snapshot at the end of the next tick
+                for _ in 0..SNAPSHOT_WAIT_TRIES {
+                    let taken = snapshots.robot.borrow_mut().take();
+                    if let Some(robot) = taken {
+                        let Some(world) = snapshots.world.borrow().clone() else {
+                            snapshot_message.set("The world of this game cannot be saved".to_string());
+                            return;
+                        };
+                        let score = snapshots.score.borrow_mut().take().unwrap_or_default();
+                        let result = Snapshot { robot, world, score }
+                            .to_bytes()
+                            .and_then(|bytes| download(&bytes, "snapshot.bin").map_err(|e| format!("{:?}", e)));
+                        match result {
+                            Ok(_) => snapshot_message.set(String::new()),
+                            Err(e) => snapshot_message.set(e),
+                        }
+                        return;
+                    }
+                    sleep(50).await;
+                }
+                snapshots.requested.set(false);
+                snapshot_message.set("The robot is not running, try again".to_string());
+            });
+        })
+    };
+
+    let load_snapshot = {
+        let session = session.clone();
+        let replay_state = replay_state.clone();
+        let snapshot_message = snapshot_message.clone();
+
+        Callback::from(move |e: yew::prelude::Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            let session = session.clone();
+            let replay_state = replay_state.clone();
+            let snapshot_message = snapshot_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match read_file(&file).await.and_then(|bytes| Snapshot::from_bytes(&bytes)) {
+                    Ok(snapshot) => {
+                        // the old game stops and a new one starts from the snapshot, with a new replay
+                        // and its own controls, the old ones may be stopped
+                        replay_state.set(ReplayState::default());
+                        session.set(SessionState {
+                            generation: session.generation + 1,
+                            resume: Some(Rc::new(snapshot)),
+                            control: Rc::new(session.control.next_game()),
+                            ..(*session).clone()
+                        });
+                        snapshot_message.set(String::new());
+                    }
+                    Err(e) => snapshot_message.set(format!("{}: {}", file.name(), e)),
+                }
+            });
+        })
+    };
+
+    // the control is shared with the game loop, the state only updates the buttons
+    let control = session.control.clone();
+    let paused = use_state_eq(|| control.is_paused());
+    let stopped = use_state_eq(|| control.is_stopped());
+    let tick_time = use_state_eq(|| settings.tick_time);
+    let tick_limit = use_state_eq(|| settings.tick_limit);
+    {
+        // every game has its own control, the buttons follow the one of the game that is running
+        let control = control.clone();
+        let paused = paused.clone();
+        let stopped = stopped.clone();
+        use_effect_with(session.generation, move |_| {
+            paused.set(control.is_paused());
+            stopped.set(control.is_stopped());
+            || ()
+        });
+    }
+
+    let toggle_pause = {
+        let control = control.clone();
+        let paused = paused.clone();
+
+        Callback::from(move |_| {
+            if control.is_paused() {
+                control.resume();
+            } else {
+                control.pause();
+            }
+            paused.set(control.is_paused());
+        })
+    };
+
+    let step = {
+        let control = control.clone();
+        Callback::from(move |_| control.step())
+    };
+
+    let stop = {
+        let control = control.clone();
+        let stopped = stopped.clone();
+
+        Callback::from(move |_| {
+            control.stop();
+            stopped.set(true);
+        })
+    };
+
+    let on_tick_time = {
+        let control = control.clone();
+        let tick_time = tick_time.clone();
+
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Ok(value) = input.value().parse::<u32>() {
+                control.set_tick_time(value);
+                tick_time.set(value);
+            }
+        })
+    };
+
+    let on_tick_limit = {
+        let control = control.clone();
+        let tick_limit = tick_limit.clone();
+
+        Callback::from(move |e: yew::prelude::Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            control.set_tick_limit(input.value().parse::<usize>().ok());
+            tick_limit.set(control.tick_limit());
+        })
+    };
+
+    let toggle_layer = {
+        let overlay_state = overlay_state.clone();
+
+        Callback::from(move |layer: OverlayLayer| {
+            let mut layers = overlay_state.layers.clone();
+            match layers.iter().position(|enabled| *enabled == layer) {
+                Some(index) => {
+                    layers.remove(index);
+                }
+                None => layers.push(layer),
+            }
+            overlay_state.wanted.set(!layers.is_empty());
+            overlay_state.set(OverlayState {
+                layers,
+                ..(*overlay_state).clone()
             });
         })
     };
 
+    // the levels live in the logger, the menu only has to render again after a change
+    let redraw = use_force_update();
+    let on_log_level = {
+        move |target: &'static str| {
+            let redraw = redraw.clone();
+            Callback::from(move |e: yew::prelude::Event| {
+                let select: HtmlSelectElement = e.target_unchecked_into();
+                if let Ok(level) = select.value().parse::<log::LevelFilter>() {
+                    logging::set_level(target, level);
+                    redraw.force_update();
+                }
+            })
+        }
+    };
+
+    // the tick limit stops the game without going through the buttons
+    let stopped = *stopped || control.is_stopped();
+
     html! {
         <div id="menu">
+            <button onclick={toggle_pause} disabled={stopped}>{ if *paused { "Resume" } else { "Pause" } }</button>
+            <button onclick={step} disabled={!*paused || stopped}>{"Step"}</button>
+            <button onclick={stop} disabled={stopped}>{ if stopped { "Stopped" } else { "Stop" } }</button>
+            <label for={"tickdelay"}>{format!("Tick Delay {}ms", *tick_time)}</label>
+            <input type="range" min="0" max="2000" step="10" id="tickdelay" value={tick_time.to_string()} oninput={on_tick_time} />
+            <label for={"menuticklimit"}>{"Tick Limit"}</label>
+            <input id={"menuticklimit"} type={"text"} placeholder={"none"} value={tick_limit.map(|limit| limit.to_string()).unwrap_or_default()} onchange={on_tick_limit} />
             <label for={"tilesize"}>{"Tile Size"}</label>
             <input type="range" class="form-range" min="1" max="100" id="tilesize" onchange={onchange_slider.clone()} />
             <button type={"checkbox"} onclick={follow_bot_fn}>{"Toggle Freelook"}</button>
+            <button onclick={save_snapshot}>{"Save"}</button>
+            <label for={"snapshotfile"}>{"Load"}</label>
+            <input id={"snapshotfile"} type={"file"} accept={".bin"} onchange={load_snapshot}/>
+            <div id={"overlays"}>
+                { for OverlayLayer::ALL.iter().map(|layer| {
+                    let layer = *layer;
+                    let toggle_layer = toggle_layer.clone();
+                    html! {
+                        <label>
+                            <input type={"checkbox"} checked={overlay_state.layers.contains(&layer)} onchange={Callback::from(move |_| toggle_layer.emit(layer))} />
+                            {layer.label()}
+                        </label>
+                    }
+                })}
+            </div>
+            <div id={"loglevels"}>
+                { for logging::TARGETS.iter().map(|target| {
+                    let current = logging::level(target);
+                    html! {
+                        <label>
+                            {*target}
+                            <select onchange={on_log_level(target)}>
+                                { for LOG_LEVELS.iter().map(|level| html! {
+                                    <option value={level.to_string()} selected={*level == current}>{level.to_string()}</option>
+                                })}
+                            </select>
+                        </label>
+                    }
+                })}
+            </div>
+            { if snapshot_message.is_empty() {
+                html! {}
+            } else {
+                html! { <p class={"error"}>{(*snapshot_message).clone()}</p> }
+            }}
+        </div>
+    }
+}
+
+// ticks to wait for the robot to take a snapshot
+const SNAPSHOT_WAIT_TRIES: usize = 100;
+
+// milliseconds between two frames while playing a replay
+const REPLAY_FRAME_TIME: u32 = 200;
+
+#[function_component(ReplayControls)]
+fn replay_controls() -> Html {
+    let replay_state = use_atom::<ReplayState>();
+    let back_state = use_atom::<BackpackState>();
+    let world_state = use_atom::<WorldState>();
+    let robot_state = use_atom::<RobotState>();
+    let env_state = use_atom::<EnviromentalState>();
+    let energy_state = use_atom::<EnergyState>();
+    let extra_state = use_atom::<ExtrasState>();
+
+    let cursor = use_mut_ref(|| ReplayCursor::new(0));
+    let playback = use_state_eq(|| false);
+    let playing = use_state_eq(|| false);
+    let position = use_state_eq(|| 0usize);
+    let replay_message = use_state_eq(String::new);
+
+    // moves the cursor and shows its state in the map
+    let show = {
+        let cursor = cursor.clone();
+        let position = position.clone();
+        Rc::new(move |replay: &Replay, target: usize| {
+            let mut cursor = cursor.borrow_mut();
+            cursor.seek(replay, target);
+            world_state.map.borrow_mut().replace(cursor.map.clone());
+            world_state.set(WorldState {
+                map: world_state.map.clone(),
+                counter: world_state.counter + 1,
+            });
+            robot_state.set(RobotState { coord: cursor.robot });
+            energy_state.set(EnergyState { energy: cursor.energy });
+            if let Some((forecast, time)) = &cursor.conditions {
+                env_state.set(EnviromentalState {
+                    forecast: forecast.clone(),
+                    time: time.clone(),
+                });
+            }
+            back_state.set(BackpackState {
+                size: cursor.backpack.0,
+                content: cursor.backpack.1.clone(),
+            });
+            extra_state.set(ExtrasState {
+                score: cursor.score,
+                ..(*extra_state).clone()
+            });
+            position.set(cursor.position);
+        })
+    };
+
+    {
+        let show = show.clone();
+        let replay_state = replay_state.clone();
+        let playing = playing.clone();
+        let position = position.clone();
+        let millis = if *playing { REPLAY_FRAME_TIME } else { 0 };
+        use_interval(
+            move || {
+                let replay = replay_state.shown();
+                let replay = replay.borrow();
+                if *position >= replay.len() {
+                    playing.set(false);
+                } else {
+                    show(&replay, *position + 1);
+                }
+            },
+            millis,
+        );
+    }
+
+    let toggle_playback = {
+        let show = show.clone();
+        let cursor = cursor.clone();
+        let replay_state = replay_state.clone();
+        let playback = playback.clone();
+        let playing = playing.clone();
+        Callback::from(move |_| {
+            // both ways the map ends on the latest recorded tick, which is where the robot is
+            let live = replay_state.replay.clone();
+            let live = live.borrow();
+            if replay_state.loaded.is_some() {
+                //the cursor was moved on another replay
+                *cursor.borrow_mut() = ReplayCursor::new(0);
+            }
+            show(&live, live.len());
+            playing.set(false);
+            replay_state.paused.set(!*playback);
+            if replay_state.loaded.is_some() {
+                replay_state.set(ReplayState { loaded: None, ..(*replay_state).clone() });
+            }
+            playback.set(!*playback);
+        })
+    };
+
+    let toggle_playing = {
+        let playing = playing.clone();
+        Callback::from(move |_| playing.set(!*playing))
+    };
+
+    let step = {
+        let show = show.clone();
+        let replay_state = replay_state.clone();
+        let position = position.clone();
+        Callback::from(move |_| show(&replay_state.shown().borrow(), *position + 1))
+    };
+
+    let on_seek = {
+        let show = show.clone();
+        let replay_state = replay_state.clone();
+        Callback::from(move |e: yew::prelude::Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Ok(target) = input.value().parse::<usize>() {
+                show(&replay_state.shown().borrow(), target);
+            }
+        })
+    };
+
+    let save_replay = {
+        let replay_state = replay_state.clone();
+        let replay_message = replay_message.clone();
+        Callback::from(move |_| {
+            let result = replay_state.shown().borrow().to_bytes()
+                .and_then(|bytes| download(&bytes, "replay.bin").map_err(|e| format!("{:?}", e)));
+            match result {
+                Ok(_) => replay_message.set(String::new()),
+                Err(e) => replay_message.set(e),
+            }
+        })
+    };
+
+    // a replay from a file, like the ones of the headless runner, is played with the robot paused
+    let load_replay = {
+        let show = show.clone();
+        let cursor = cursor.clone();
+        let replay_state = replay_state.clone();
+        let playback = playback.clone();
+        let playing = playing.clone();
+        let replay_message = replay_message.clone();
+        Callback::from(move |e: yew::prelude::Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            let show = show.clone();
+            let cursor = cursor.clone();
+            let replay_state = replay_state.clone();
+            let playback = playback.clone();
+            let playing = playing.clone();
+            let replay_message = replay_message.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                match read_file(&file).await.and_then(|bytes| Replay::from_bytes(&bytes)) {
+                    Ok(replay) => {
+                        let first = replay.first();
+                        let loaded = Rc::new(RefCell::new(replay));
+                        replay_state.paused.set(true);
+                        replay_state.set(ReplayState { loaded: Some(loaded.clone()), ..(*replay_state).clone() });
+                        playing.set(false);
+                        playback.set(true);
+                        *cursor.borrow_mut() = ReplayCursor::new(0);
+                        show(&loaded.borrow(), first);
+                        replay_message.set(String::new());
+                    }
+                    Err(e) => replay_message.set(format!("{}: {}", file.name(), e)),
+                }
+            });
+        })
+    };
+
+    let shown = replay_state.shown();
+    let (first, frames) = {
+        let replay = shown.borrow();
+        (replay.first(), replay.len())
+    };
+    html! {
+        <div id={"replay"}>
+            <button onclick={toggle_playback}>{ if *playback { "Back to Live" } else { "Replay" } }</button>
+            <button onclick={save_replay}>{"Save Replay"}</button>
+            <label for={"replayfile"}>{"Load Replay"}</label>
+            <input id={"replayfile"} type={"file"} accept={".bin"} onchange={load_replay}/>
+            { if *playback {
+                html! {
+                    <>
+                        <button onclick={toggle_playing}>{ if *playing { "Pause" } else { "Play" } }</button>
+                        <button onclick={step}>{"Step"}</button>
+                        <input type="range" id="seek" min={first.to_string()} max={frames.to_string()} value={position.to_string()} onchange={on_seek} />
+                        <span>{format!("Tick {}/{}", *position, frames)}</span>
+                    </>
+                }
+            } else {
+                html! {}
+            }}
+            { if replay_message.is_empty() {
+                html! {}
+            } else {
+                html! { <p class={"error"}>{(*replay_message).clone()}</p> }
+            }}
         </div>
     }
 }
@@ -248,21 +1019,21 @@ fn menu() -> Html {
 #[function_component(BackP)]
 pub fn backpack() -> Html {
     let back_state = use_atom::<BackpackState>();
+    let used: usize = back_state.content.values().sum();
+    // the map has no order, sorting keeps the items from jumping around between ticks
+    let mut contents: Vec<(&Content, &usize)> = back_state.content.iter().filter(|(_, amount)| **amount > 0).collect();
+    contents.sort_by_key(|(content, _)| format!("{:?}", content.to_default()));
+
     html! {
         <div id={"backpack"}>
             <h2>{"Backpack"}</h2>
             <hr/>
-            {"Size: "}{ &back_state.size}
+            {format!("Size: {}/{}", used, back_state.size)}
+            <progress id={"capacity"} max={back_state.size.max(1).to_string()} value={used.to_string()}></progress>
             <br/>
             {"Contents: "}
-            { for back_state.content.iter().map(|content| {
-                match content.1 {
-                    0 => html! {<></>},
-                    _ => html! {
-                        <BackItem content={content.0.clone()} size={content.1.clone()}/>
-                    }
-                }
-
+            { for contents.into_iter().map(|(content, amount)| html! {
+                <BackItem content={content.clone()} size={*amount}/>
             })}
         </div>
     }
@@ -346,172 +1117,466 @@ pub fn map_view() -> Html {
     let robot_state = use_atom::<RobotState>();
     let settings = use_atom::<StartingSettings>();
     let cond_state = use_atom::<EnviromentalState>();
-    
-    const ROBOT_IMG: &'static str = "img/robot-min.png";
-
-    let world_styles: String;
-
-    let hour: u8 = if cond_state.time.len() >= 2 {
-        cond_state.time[0..2].parse::<u8>().unwrap_or_default()
-    } else {
-        12
+    let overlay_state = use_atom::<OverlayState>();
+    let overlay_data = use_atom::<OverlayData>();
+    let inspector = use_atom::<InspectorState>();
+
+    let canvas_ref = use_node_ref();
+    let overlay_ref = use_node_ref();
+    let view_ref = use_node_ref();
+    let stack_ref = use_node_ref();
+    // the canvases only cover the part of the map that can be seen
+    let viewport = use_state_eq(Viewport::default);
+    // what is on the canvas right now, to only draw what changed
+    let drawn = use_mut_ref(DrawnMap::default);
+    let images = use_mut_ref(HashMap::<&'static str, HtmlImageElement>::new);
+    let force_update = use_force_update();
+
+    let daytime = is_daytime(&cond_state.time);
+    let background = if daytime { "var(--background-color)" } else { "black" };
+
+    // Images are loaded once, the map is drawn again when one of them is ready
+    {
+        let images = images.clone();
+        use_effect_with((), move |_| {
+            let sources = ALL_CONTENT.iter().map(content_match_day).chain([ROBOT_IMG]);
+            for src in sources.filter(|src| !src.is_empty()) {
+                if images.borrow().contains_key(src) {
+                    continue;
+                }
+                if let Ok(image) = HtmlImageElement::new() {
+                    let force_update = force_update.clone();
+                    let onload = Closure::<dyn Fn()>::new(move || force_update.force_update());
+                    image.set_onload(Some(onload.as_ref().unchecked_ref()));
+                    onload.forget();
+                    image.set_src(src);
+                    images.borrow_mut().insert(src, image);
+                }
+            }
+            || ()
+        });
+    }
+
+    // Measured again after every render and on every scroll, the map or the window may have changed size
+    let measure_viewport = {
+        let view_ref = view_ref.clone();
+        let stack_ref = stack_ref.clone();
+        let viewport = viewport.clone();
+        move || {
+            if let (Some(view), Some(stack)) = (view_ref.cast::<Element>(), stack_ref.cast::<Element>()) {
+                viewport.set(Viewport::of(&view, &stack));
+            }
+        }
     };
-    match hour {
-        19..=23 | 00..=05 => {
-            world_styles = format!(
-                "width: {}px; height: {}px; background-color: black;",
-                settings.tile_size.clone().to_string(),
-                settings.tile_size.clone().to_string()
-            );
+    {
+        let measure_viewport = measure_viewport.clone();
+        use_effect(move || {
+            measure_viewport();
+            || ()
+        });
+    }
+    let on_scroll = Callback::from(move |_: yew::prelude::Event| measure_viewport());
+
+    // Draws after every render, only the visible tiles that changed
+    {
+        let canvas_ref = canvas_ref.clone();
+        let world_state = world_state.clone();
+        let robot = robot_state.coord;
+        let tile_size = settings.tile_size;
+        let viewport = *viewport;
+        use_effect(move || {
+            if let Some(canvas) = canvas_ref.cast::<HtmlCanvasElement>() {
+                draw_map(&canvas, &mut drawn.borrow_mut(), &mut world_state.map.borrow_mut(), robot, tile_size, daytime, &images.borrow(), viewport);
+            }
+            || ()
+        });
+    }
+
+    // Overlays are on their own canvas and drawn again only when they change
+    {
+        let overlay_ref = overlay_ref.clone();
+        let size = world_state.map.borrow().tiles.len();
+        let tile_size = settings.tile_size;
+        use_effect_with(
+            (overlay_state.layers.clone(), overlay_data.overlay.clone(), size, tile_size, *viewport),
+            move |(layers, overlay, size, tile_size, viewport)| {
+                if let Some(canvas) = overlay_ref.cast::<HtmlCanvasElement>() {
+                    draw_overlay(&canvas, layers, overlay, *size, *tile_size, viewport);
+                }
+                || ()
+            },
+        );
+    }
+
+    // Tile under the mouse, rows are drawn as columns
+    let size = world_state.map.borrow().tiles.len();
+    let tile_size = settings.tile_size;
+    let origin = (viewport.x, viewport.y);
+    let tile_at = move |e: &MouseEvent| -> Option<(usize, usize)> {
+        if e.offset_x() < 0 || e.offset_y() < 0 {
+            return None;
         }
-        _ => {
-            world_styles = format!(
-                "width: {}px; height: {}px; background-color: var(--background-color);",
-                settings.tile_size.clone().to_string(),
-                settings.tile_size.clone().to_string()
-            );
+        // the canvas starts where the viewport does
+        let (x, y) = (e.offset_x() as f64 + origin.0, e.offset_y() as f64 + origin.1);
+        let coord = ((x / tile_size as f64) as usize, (y / tile_size as f64) as usize);
+        (coord.0 < size && coord.1 < size).then_some(coord)
+    };
+    let inspect = {
+        let inspector = inspector.clone();
+        move |hovered: Option<(usize, usize)>, pinned: Option<(usize, usize)>| {
+            let state = InspectorState {
+                hovered,
+                pinned,
+                request: inspector.request.clone(),
+            };
+            state.request.set(state.shown());
+            inspector.set(state);
         }
-    }
-    
-    
+    };
+    let on_mouse_move = {
+        let inspector = inspector.clone();
+        let inspect = inspect.clone();
+        Callback::from(move |e: MouseEvent| {
+            let hovered = tile_at(&e);
+            if hovered != inspector.hovered {
+                inspect(hovered, inspector.pinned);
+            }
+        })
+    };
+    let on_mouse_leave = {
+        let inspector = inspector.clone();
+        let inspect = inspect.clone();
+        Callback::from(move |_: MouseEvent| inspect(None, inspector.pinned))
+    };
+    // clicking the pinned tile again unpins it
+    let on_click = {
+        let inspector = inspector.clone();
+        Callback::from(move |e: MouseEvent| {
+            let clicked = tile_at(&e);
+            let pinned = if clicked == inspector.pinned { None } else { clicked };
+            inspect(inspector.hovered, pinned);
+        })
+    };
 
     // Use effect for following the robot
-    use_effect_with((settings.follow_robot, robot_state.coord), move |_| {
-        if settings.follow_robot {
-            if let Some(robot_element) = window().and_then(|win| win.document()).and_then(|doc| doc.get_element_by_id("robot")) {
-                let robot_html_element = robot_element.dyn_into::<Element>().expect("Failed to cast to HtmlElement");
-                robot_html_element.scroll_into_view();
+    use_effect_with((settings.follow_robot, robot_state.coord), move |(follow_robot, coord)| {
+        if *follow_robot {
+            if let Some(view) = view_ref.cast::<Element>() {
+                // rows are drawn as columns
+                let (x, y) = tile_origin(*coord, settings.tile_size);
+                view.set_scroll_left(x as i32 - view.client_width() / 2);
+                view.set_scroll_top(y as i32 - view.client_height() / 2);
             }
         }
         || ()
     });
 
+    // the stack has the size of the whole map, so the view scrolls as before
+    let side = (size as f32 * tile_size).ceil();
+    let placed = format!("left: {}px; top: {}px;", viewport.x, viewport.y);
     html! {
-        <div id={"robot_view"}>
-            {for world_state.world.iter().enumerate().map(|(i, row)| {
-                html! {
-                    <div class={classes!("map_row")}>
-                        {for row.iter().enumerate().map(|(j, tile_option)| {
-                            html! {
-                                <div class={classes!("tile")} style={world_styles.to_owned()}>
-                                    {if let Some(tile) = tile_option {
-                                        html! {<MapTile tile={tile.to_owned()} />}
-                                    } else {
-                                        html! {}
-                                    }}
-                                    {if i == robot_state.coord.0 && j == robot_state.coord.1 {
-                                        html! {<img id={"robot"} src={ROBOT_IMG} />}
-                                    } else {
-                                        html! {}
-                                    }}
-                                </div>
-                            }
-                        })}
-                    </div>
-                }
-            })}
+        <div id={"robot_view"} ref={view_ref} onscroll={on_scroll}>
+            <div id={"map_stack"} ref={stack_ref} style={format!("width: {}px; height: {}px;", side, side)}>
+                <canvas id={"map_canvas"} ref={canvas_ref} style={format!("background-color: {}; {}", background, placed)}
+                    onmousemove={on_mouse_move} onmouseleave={on_mouse_leave} onclick={on_click} />
+                <canvas id={"overlay_canvas"} ref={overlay_ref} style={placed} />
+            </div>
         </div>
     }
 }
 
-#[derive(Properties, PartialEq)]
-pub struct MapTileProps {
-    tile: Tile,
+const ROBOT_IMG: &'static str = "img/robot-min.png";
+
+const ALL_CONTENT: [Content; 16] = [
+    Content::Rock(0),
+    Content::Tree(0),
+    Content::Garbage(0),
+    Content::Fire,
+    Content::Coin(0),
+    Content::Bin(0..0),
+    Content::Crate(0..0),
+    Content::Bank(0..0),
+    Content::Water(0),
+    Content::Market(0),
+    Content::Fish(0),
+    Content::Building,
+    Content::Bush(0),
+    Content::JollyBlock(0),
+    Content::Scarecrow,
+    Content::None,
+];
+
+#[derive(Default)]
+struct DrawnMap {
+    size: usize,
+    robot: Option<(usize, usize)>,
+    tile_size: f32,
+    daytime: bool,
+    loaded_images: usize,
+    viewport: Viewport,
 }
 
-#[function_component(MapTile)]
-pub fn map_tile(props: &MapTileProps) -> Html {
-    let cond_state = use_atom::<EnviromentalState>();
-    let settings = use_atom::<StartingSettings>();
-    // let extra_state = use_atom::<ExtrasState>();
-    let world_style: &str = &format!(
-        "width: {}px; height: {}px;",
-        settings.tile_size.clone().to_string(),
-        settings.tile_size.clone().to_string()
-    )[..];
-    let hour: u8 = cond_state.time[0..2]
-        .to_owned()
-        .parse::<u8>()
-        .expect("Expected hour");
-    let tile_style: String;
-    let daytime: bool;
-    match hour {
-        19..=23 | 00..=05 => {
-            daytime = false;
-        }
-        _ => {
-            daytime = true;
+// part of the map shown in #robot_view, in pixels from the top left corner of the map
+#[derive(Clone, Copy, PartialEq, Default, Debug)]
+struct Viewport {
+    x: f64,
+    y: f64,
+    width: u32,
+    height: u32,
+}
+
+impl Viewport {
+    // the visible part of the stack, clipped to the inside of the view
+    fn of(view: &Element, stack: &Element) -> Viewport {
+        let view_rect = view.get_bounding_client_rect();
+        let stack_rect = stack.get_bounding_client_rect();
+        let left = view_rect.left() + view.client_left() as f64 - stack_rect.left();
+        let top = view_rect.top() + view.client_top() as f64 - stack_rect.top();
+        let (x, y) = (left.max(0.0).floor(), top.max(0.0).floor());
+        let right = (left + view.client_width() as f64).min(stack_rect.width());
+        let bottom = (top + view.client_height() as f64).min(stack_rect.height());
+        Viewport {
+            x,
+            y,
+            width: (right - x).max(0.0).ceil() as u32,
+            height: (bottom - y).max(0.0).ceil() as u32,
         }
     }
-
-    match daytime {
-        true => match props.tile.tile_type {
-            TileType::Wall => {
-                tile_style = format!("{} background-color: rgb(125, 125, 125);", world_style)
-            }
-            DeepWater => tile_style = format!("{} background-color: #2B00FF;", world_style),
-            ShallowWater => tile_style = format!("{} background-color: #00B3FF;", world_style),
-            Sand => tile_style = format!("{} background-color: #FFC400;", world_style),
-            Grass => tile_style = format!("{} background-color: #23B606;", world_style),
-            Street => tile_style = format!("{} background-color: #000000;", world_style),
-            Hill => tile_style = format!("{} background-color: #FFBD4A;", world_style),
-            Mountain => tile_style = format!("{} background-color: #8C8CF9;", world_style),
-            Snow => tile_style = format!("{} background-color: #F5F5F5;", world_style),
-            Lava => tile_style = format!("{} background-color: #F2DA3E;", world_style),
-            Teleport(_) => tile_style = format!("{} background-color: #BC1FEC;", world_style),
-        },
-        false => match props.tile.tile_type {
-            TileType::Wall => {
-                tile_style = format!("{} background-color: rgb(125, 125, 125);", world_style)
-            }
-            DeepWater => tile_style = format!("{} background-color: #030C58;", world_style),
-            ShallowWater => tile_style = format!("{} background-color: #074A84;", world_style),
-            Sand => tile_style = format!("{} background-color: #A5931B;", world_style),
-            Grass => tile_style = format!("{} background-color: #0E5411;", world_style),
-            Street => tile_style = format!("{} background-color: #000000;", world_style),
-            Hill => tile_style = format!("{} background-color: #573708;", world_style),
-            Mountain => tile_style = format!("{} background-color: #20314A;", world_style),
-            Snow => tile_style = format!("{} background-color: #C9C9C9;", world_style),
-            Lava => tile_style = format!("{} background-color: #F2DA3E;", world_style),
-            Teleport(_) => tile_style = format!("{} background-color: #56038D;", world_style),
-        },
+    // rows and columns with at least a pixel in the viewport, rows are drawn as columns
+    fn tiles(&self, size: usize, tile_size: f32) -> (Range<usize>, Range<usize>) {
+        let tile = tile_size as f64;
+        if tile <= 0.0 {
+            return (0..0, 0..0);
+        }
+        let span = |from: f64, length: u32| {
+            let first = ((from / tile).floor() as usize).min(size);
+            let last = (((from + length as f64) / tile).ceil() as usize).min(size);
+            first..last
+        };
+        (span(self.x, self.width), span(self.y, self.height))
     }
+    // resizes the canvas, which clears it, and moves its drawing to the viewport
+    fn place(&self, canvas: &HtmlCanvasElement, ctx: &CanvasRenderingContext2d) {
+        canvas.set_width(self.width);
+        canvas.set_height(self.height);
+        let _ = ctx.set_transform(1.0, 0.0, 0.0, 1.0, -self.x, -self.y);
+    }
+}
 
-    html! {
-        <div class={classes!("tile")}>
-            <div class={classes!("tile_type")} style={tile_style}/>
-            <MapTileContent tile={props.tile.clone()}/>
-        </div>
+fn is_daytime(time: &str) -> bool {
+    let hour: u8 = if time.len() >= 2 {
+        time[0..2].parse::<u8>().unwrap_or(12)
+    } else {
+        12
+    };
+    !matches!(hour, 19..=23 | 00..=05)
+}
+
+// the map used to be a row of columns, so the row of a tile is its x
+fn tile_origin(coord: (usize, usize), tile_size: f32) -> (f64, f64) {
+    (coord.0 as f64 * tile_size as f64, coord.1 as f64 * tile_size as f64)
+}
+
+fn draw_map(
+    canvas: &HtmlCanvasElement,
+    drawn: &mut DrawnMap,
+    map: &mut SharedMap,
+    robot: (usize, usize),
+    tile_size: f32,
+    daytime: bool,
+    images: &HashMap<&'static str, HtmlImageElement>,
+    viewport: Viewport,
+) {
+    let Some(ctx) = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().ok())
+    else {
+        return;
+    };
+    let loaded_images = images.values().filter(|image| image.complete()).count();
+
+    // anything that changes every tile, or a scroll, means drawing every visible tile again
+    let world = &map.tiles;
+    let (rows, cols) = viewport.tiles(world.len(), tile_size);
+    let dirty: Vec<(usize, usize)> = if map.redraw_all
+        || drawn.size != world.len()
+        || drawn.tile_size != tile_size
+        || drawn.daytime != daytime
+        || drawn.loaded_images != loaded_images
+        || drawn.viewport != viewport
+    {
+        viewport.place(canvas, &ctx);
+        drawn.size = world.len();
+        drawn.robot = None;
+        drawn.tile_size = tile_size;
+        drawn.daytime = daytime;
+        drawn.loaded_images = loaded_images;
+        drawn.viewport = viewport;
+        map.dirty.clear();
+        map.redraw_all = false;
+        rows.flat_map(|i| cols.clone().map(move |j| (i, j)))
+            .filter(|(i, j)| world[*i][*j].is_some())
+            .collect()
+    } else {
+        // the tiles out of sight are drawn when they are scrolled into view
+        std::mem::take(&mut map.dirty)
+            .into_iter()
+            .filter(|(i, j)| rows.contains(i) && cols.contains(j))
+            .collect()
+    };
 
+    for (i, j) in dirty.iter() {
+        draw_tile(&ctx, (*i, *j), &world[*i][*j], tile_size, daytime, images);
+    }
+    // the robot leaves its old tile
+    if drawn.robot != Some(robot) || !dirty.is_empty() {
+        if let Some(old) = drawn.robot {
+            if let Some(tile) = world.get(old.0).and_then(|row| row.get(old.1)) {
+                draw_tile(&ctx, old, tile, tile_size, daytime, images);
+            }
+        }
+        if let Some(image) = images.get(ROBOT_IMG).filter(|image| image.complete()) {
+            let (x, y) = tile_origin(robot, tile_size);
+            let _ = ctx.draw_image_with_html_image_element_and_dw_and_dh(image, x, y, tile_size as f64, tile_size as f64);
+        }
+        drawn.robot = Some(robot);
     }
 }
 
-#[function_component(MapTileContent)]
-pub fn map_tile_content(props: &MapTileProps) -> Html {
-    let cond_state = use_atom::<EnviromentalState>();
-    let img_display: &str;
+fn overlay_color(layer: &OverlayLayer) -> &'static str {
+    match layer {
+        OverlayLayer::Frontier => "rgba(255, 0, 255, 0.45)",
+        OverlayLayer::Sectors => "rgba(255, 255, 255, 0.8)",
+        OverlayLayer::Roads => "rgba(255, 140, 0, 0.5)",
+        OverlayLayer::PlannedRoads => "rgba(255, 255, 0, 0.5)",
+        OverlayLayer::PavedRoads => "rgba(0, 255, 120, 0.5)",
+        OverlayLayer::SectorNodes => "rgba(255, 0, 0, 0.9)",
+        OverlayLayer::Path => "rgba(0, 200, 255, 0.9)",
+    }
+}
 
-    let hour: u8 = cond_state.time[0..2]
-        .to_owned()
-        .parse::<u8>()
-        .expect("Expected a number for time");
-    img_display = content_match_day(&props.tile.content);
+// the layers are drawn in the order they were turned on
+fn draw_overlay(canvas: &HtmlCanvasElement, layers: &Vec<OverlayLayer>, overlay: &AiOverlay, size: usize, tile_size: f32, viewport: &Viewport) {
+    let Some(ctx) = canvas
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|ctx| ctx.dyn_into::<CanvasRenderingContext2d>().ok())
+    else {
+        return;
+    };
+    // setting the size also clears the canvas, what falls outside the viewport is clipped
+    viewport.place(canvas, &ctx);
+    let tile = tile_size as f64;
+    let center = |coord: (usize, usize)| {
+        let (x, y) = tile_origin(coord, tile_size);
+        (x + tile / 2.0, y + tile / 2.0)
+    };
 
-    if img_display == "" {
-        html! {<></>}
-    } else {
-        match hour {
-            19..=23 | 00..=05 => {
-                html! {
-                    <img style={"filter: brightness(50%);"} class={classes!("tile_content")} src={img_display}/>
+    for layer in layers.iter() {
+        let color = JsValue::from_str(overlay_color(layer));
+        ctx.set_fill_style(&color);
+        ctx.set_stroke_style(&color);
+        let tiles = match layer {
+            OverlayLayer::Frontier => &overlay.frontier,
+            OverlayLayer::Roads => &overlay.road_tiles,
+            OverlayLayer::PlannedRoads => &overlay.to_pave,
+            OverlayLayer::PavedRoads => &overlay.paved,
+            OverlayLayer::Sectors => {
+                for (index, (top_left, bottom_right)) in sector_bounds(size).into_iter().enumerate() {
+                    ctx.set_line_width(if index == overlay.active_sector { 4.0 } else { 1.0 });
+                    let (x, y) = tile_origin(top_left, tile_size);
+                    let (w, h) = tile_origin((bottom_right.0 - top_left.0 + 1, bottom_right.1 - top_left.1 + 1), tile_size);
+                    ctx.stroke_rect(x, y, w, h);
                 }
+                continue;
             }
-            _ => {
-                html! {
-                    <img  class={classes!("tile_content")} src={img_display}/>
+            OverlayLayer::SectorNodes => {
+                for node in overlay.sector_nodes.iter() {
+                    let (x, y) = center(*node);
+                    ctx.begin_path();
+                    let _ = ctx.arc(x, y, (tile / 2.0).max(3.0), 0.0, std::f64::consts::TAU);
+                    ctx.fill();
+                }
+                continue;
+            }
+            OverlayLayer::Path => {
+                ctx.set_line_width((tile / 4.0).max(1.0));
+                ctx.begin_path();
+                for (index, coord) in overlay.path.iter().enumerate() {
+                    let (x, y) = center(*coord);
+                    if index == 0 {
+                        ctx.move_to(x, y);
+                    } else {
+                        ctx.line_to(x, y);
+                    }
                 }
+                ctx.stroke();
+                continue;
             }
+        };
+        for coord in tiles.iter() {
+            let (x, y) = tile_origin(*coord, tile_size);
+            ctx.fill_rect(x, y, tile, tile);
+        }
+    }
+}
+
+fn draw_tile(
+    ctx: &CanvasRenderingContext2d,
+    coord: (usize, usize),
+    tile: &Option<Tile>,
+    tile_size: f32,
+    daytime: bool,
+    images: &HashMap<&'static str, HtmlImageElement>,
+) {
+    let (x, y) = tile_origin(coord, tile_size);
+    let size = tile_size as f64;
+    ctx.clear_rect(x, y, size, size);
+    let Some(tile) = tile else {
+        return;
+    };
+    ctx.set_fill_style(&JsValue::from_str(tile_color(&tile.tile_type, daytime)));
+    ctx.fill_rect(x, y, size, size);
+    if let Some(image) = images.get(content_match_day(&tile.content)).filter(|image| image.complete()) {
+        if !daytime {
+            ctx.set_filter("brightness(50%)");
         }
+        let _ = ctx.draw_image_with_html_image_element_and_dw_and_dh(image, x, y, size, size);
+        ctx.set_filter("none");
+    }
+}
+
+fn tile_color(tile_type: &TileType, daytime: bool) -> &'static str {
+    match daytime {
+        true => match tile_type {
+            TileType::Wall => "rgb(125, 125, 125)",
+            DeepWater => "#2B00FF",
+            ShallowWater => "#00B3FF",
+            Sand => "#FFC400",
+            Grass => "#23B606",
+            Street => "#000000",
+            Hill => "#FFBD4A",
+            Mountain => "#8C8CF9",
+            Snow => "#F5F5F5",
+            Lava => "#F2DA3E",
+            Teleport(_) => "#BC1FEC",
+        },
+        false => match tile_type {
+            TileType::Wall => "rgb(125, 125, 125)",
+            DeepWater => "#030C58",
+            ShallowWater => "#074A84",
+            Sand => "#A5931B",
+            Grass => "#0E5411",
+            Street => "#000000",
+            Hill => "#573708",
+            Mountain => "#20314A",
+            Snow => "#C9C9C9",
+            Lava => "#F2DA3E",
+            Teleport(_) => "#56038D",
+        },
     }
 }
 
@@ -557,6 +1622,54 @@ fn content_match_day(input: &Content) -> &'static str {
     }
 }
 
+#[function_component(TileInspector)]
+fn tile_inspector() -> Html {
+    let inspector = use_atom::<InspectorState>();
+    let report_state = use_atom::<TileReportState>();
+    let world_state = use_atom::<WorldState>();
+
+    let Some(position) = inspector.shown() else {
+        return html! {};
+    };
+    let map = world_state.map.borrow();
+    let size = map.tiles.len();
+    let tile = map.tiles.get(position.0).and_then(|row| row.get(position.1)).cloned().flatten();
+    let yes_no = |value: bool| if value { "yes" } else { "no" };
+
+    html! {
+        <div id={"inspector"}>
+            <h2>{format!("Tile ({}, {})", position.0, position.1)}{ if inspector.pinned.is_some() { " (pinned)" } else { "" } }</h2>
+            <hr/>
+            { match &tile {
+                Some(tile) => html! {
+                    <>
+                        <p>{format!("Type: {:?}", tile.tile_type)}</p>
+                        <p>{format!("Elevation: {}", tile.elevation)}</p>
+                        <p>{format!("Content: {:?}", tile.content)}</p>
+                        <p>{format!("Walkable: {}", yes_no(tile.tile_type.properties().walk()))}</p>
+                    </>
+                },
+                None => html! { <p>{"Not discovered yet"}</p> },
+            }}
+            <p>{format!("Sector: {}", calculate_spatial_index(position.0, position.1, size))}</p>
+            { match report_state.report.as_ref().filter(|report| report.position == position) {
+                Some(report) => html! {
+                    <>
+                        <p>{format!("Frontier: {}", yes_no(report.in_frontier))}</p>
+                        <p>{format!("Planned road: {}", yes_no(report.in_planned_road))}</p>
+                        <p>{format!("Road: {}", yes_no(report.on_road))}</p>
+                        <p>{ match &report.go_cost {
+                            Ok(cost) => format!("Cost from the robot: {}", cost),
+                            Err(error) => format!("Cost from the robot: {}", error),
+                        }}</p>
+                    </>
+                },
+                None => html! { <p>{"Waiting for the next tick"}</p> },
+            }}
+        </div>
+    }
+}
+
 #[function_component(ScoreDisplay)]
 fn score_display() -> Html {
     let extras = use_atom::<ExtrasState>();
@@ -565,213 +1678,564 @@ fn score_display() -> Html {
         <div id="score">
             <h2>{"Score"}</h2>
             <p>{&extras.score}</p>
+            { sparkline(&extras.history) }
+            { for extras.breakdown.iter().map(|(content, score)| html! {
+                <p class={"score_item"}>
+                    { match content {
+                        Content::None => html! { {"Other"} },
+                        _ => html! { <img src={content_match_day(content)}/> },
+                    }}
+                    {format!(" {:.1}", score)}
+                </p>
+            })}
         </div>
     }
 }
 
-// TIMO CODE
-pub(crate) struct Jerry {
-    pub(crate) robot: Robot,
-    pub(crate) bps: UseAtomHandle<BackpackState>,
-    pub(crate) ws: UseAtomHandle<WorldState>,
-    pub(crate) rs: UseAtomHandle<RobotState>,
-    pub(crate) env: UseAtomHandle<EnviromentalState>,
-    pub(crate) en: UseAtomHandle<EnergyState>,
-    pub(crate) extras: UseAtomHandle<ExtrasState>,
-    pub(crate) tick_counter: usize,
-    pub(crate) world_dim: usize,
-    pub(crate) active_region: ActiveRegion,
-    pub(crate) road_tiles: HashSet<ChartedCoordinate>,
-    pub(crate) vent: Rc<RefCell<Vent>>,
-    pub(crate) dynamo: Dynamo,
-    pub(crate) weather_predictor: WeatherPredictionTool,
-    pub(crate) tom_tom: TomTom,
-    pub(crate) charting_tools: ChartingTools,
-    pub(crate) missions: VecDeque<Mission>,
+const SPARKLINE_WIDTH: f32 = 200.0;
+const SPARKLINE_HEIGHT: f32 = 50.0;
+
+// score over the ticks, it only changes at the recorded points so it is drawn as steps
+fn sparkline(history: &Vec<(usize, f32)>) -> Html {
+    let (Some(first), Some(last)) = (history.first(), history.last()) else {
+        return html! {};
+    };
+    let ticks = (last.0 - first.0).max(1) as f32;
+    let (min, max) = history.iter().fold((f32::MAX, f32::MIN), |(min, max), (_, score)| (min.min(*score), max.max(*score)));
+    let range = (max - min).max(1.0);
+    let mut points = String::new();
+    let mut previous: Option<f32> = None;
+    for (tick, score) in history.iter() {
+        let x = (*tick - first.0) as f32 / ticks * SPARKLINE_WIDTH;
+        let y = SPARKLINE_HEIGHT - (score - min) / range * SPARKLINE_HEIGHT;
+        if let Some(previous) = previous {
+            points.push_str(&format!("{:.1},{:.1} ", x, previous));
+        }
+        points.push_str(&format!("{:.1},{:.1} ", x, y));
+        previous = Some(y);
+    }
+
+    html! {
+        <svg class={"sparkline"} width={SPARKLINE_WIDTH.to_string()} height={SPARKLINE_HEIGHT.to_string()}>
+            <polyline points={points} fill={"none"} stroke={"#23B606"} stroke-width={"2"} />
+        </svg>
+    }
 }
 
-#[function_component(TimoAi)]
-pub fn timo_ai() -> Html {
-    // USESTATES
-    let back_state = use_atom::<BackpackState>();
-    let world_state = use_atom::<WorldState>();
-    let robot_state = use_atom::<RobotState>();
-    let env_state = use_atom::<EnviromentalState>();
-    let energy_state = use_atom::<EnergyState>();
-    let extra_state = use_atom::<ExtrasState>();
-    let settings = use_atom::<StartingSettings>();
+// the charts are only rendered while the panel is open
+#[function_component(TelemetryDashboard)]
+fn telemetry_dashboard() -> Html {
+    let open = use_state_eq(|| false);
+    let on_toggle = {
+        let open = open.clone();
+        Callback::from(move |e: yew::prelude::Event| {
+            let details: Element = e.target_unchecked_into();
+            open.set(details.has_attribute("open"));
+        })
+    };
 
-    // info!("Ai Running");
-    // let runner_ref = use_state_eq(|| None); timeout_jerry
-    {
-        impl Runnable for Jerry {
-            fn process_tick(&mut self, world: &mut World) {
-                if self.tick_counter == 0 {
-                    first_tick(self, world);
-                }
-                execute_mission(self, world);
-                println!("{:?} {}", self.robot.energy, self.tick_counter);
-                self.tick_counter += 1;
-
-                // Update UI State
-                let tmp_map = robot_map(&world).unwrap_or_default();
-                let tmp_conditions = look_at_sky(&world);
-                // info!("{:?} Internal Map", tmp_map);
-                if tmp_map != self.ws.world {
-                    self.ws.set(WorldState {
-                        world: tmp_map,
-                        counter: self.ws.counter.clone() + 1,
-                    });
-                    // info!("CHANGED WORLD");
-                }
-                let tmp_time = tmp_conditions.get_time_of_day_string();
-                if self.env.time != tmp_time {
-                    self.env.set(EnviromentalState {
-                        forecast: tmp_conditions.get_weather_condition(),
-                        time: tmp_time,
-                    });
-                }
-                // let tmp_score = get_score(&world);
-                // info!("{}", format!("SCORE {}", tmp_score.to_string()));
-                // if tmp_score != self.extras.score {
-                //     self.extras.set(ExtrasState {score: tmp_score});
-                // }
-                // info!("CHANGED CONDITIONS");
-            }
+    html! {
+        <details id={"telemetry"} ontoggle={on_toggle}>
+            <summary>{"Telemetry"}</summary>
+            { if *open { html! { <TelemetryCharts /> } } else { html! {} } }
+        </details>
+    }
+}
 
-            fn handle_event(&mut self, event: Event) {
-                println!();
-                println!("{:?}", event);
-                // Logs the event to the console
-                // info!("[ EVENT ]{}", format!("{:?}", event));
-                // Backpack Updates
-                match event {
-                    Event::AddedToBackpack(_, _)  => {
-                        // let new_back = self.get_backpack();
-                        // let new_back_content = new_back.get_contents();
-                        // let new_inside: HashMap<Content, usize> = (new_back_content.iter())
-                        //     .map(|content| (content.0.to_owned(), content.1.to_owned()))
-                        //     .collect();
-                        // HERE Implement the code to update a state inside the ai function component with the value of backpack size and content
-                        //info!("[ State Update ] New Backpack State {:?}", new_back_content);
-                        // if self.bps.content != new_inside {
-                        //     self.bps.set(BackpackState {
-                        //         size: new_back.get_size(),
-                        //         content: new_inside,
-                        //     });
-                        //     info!("[ State Update ] New Backpack State");
-                        // }
-                    }
-                    Event::Moved(_, position) => {
-                        if position.0 >= self.active_region.bottom_right.0 {
-                            self.active_region.bottom_right.0 = if position.0 == self.world_dim - 1
-                            {
-                                self.world_dim - 1
-                            } else {
-                                position.0 + 1
-                            };
-                        }
-                        if position.1 >= self.active_region.bottom_right.1 {
-                            self.active_region.bottom_right.1 = if position.1 == self.world_dim - 1
-                            {
-                                self.world_dim - 1
-                            } else {
-                                position.1 + 1
-                            };
-                        }
-                        if position.0 <= self.active_region.top_left.0 {
-                            self.active_region.top_left.0 =
-                                if position.0 == 0 { 0 } else { position.0 - 1 };
-                        }
-                        if position.1 <= self.active_region.top_left.1 {
-                            self.active_region.top_left.1 =
-                                if position.1 == 0 { 0 } else { position.1 - 1 };
-                        }
-                        let tmp_coords = self.get_coordinate();
-                        // info!("[ State Update ] NEW COORDS: {:?}", tmp_coords);
-                        self.rs.set(RobotState {
-                            coord: (tmp_coords.get_row(), tmp_coords.get_col()),
-                        });
-                    }
-                    Event::EnergyRecharged(_) | Event::EnergyConsumed(_) => {
-                        let tmp_energy = self.get_energy().get_energy_level();
-                        if self.en.energy != tmp_energy {
-                            self.en.set(EnergyState { energy: tmp_energy })
+#[function_component(TelemetryCharts)]
+fn telemetry_charts() -> Html {
+    let telemetry = use_atom::<TelemetryState>();
+    let kept = telemetry.series.borrow();
+    let samples = kept.samples();
+    let series = |value: fn(&TelemetrySample) -> f32| samples.iter().map(value).collect::<Vec<f32>>();
+    const STATUS_COLORS: [&'static str; 5] = ["#8C8CF9", "#23B606", "#FFC400", "#7D7D7D", "#FF3300"];
+
+    html! {
+        <>
+            { line_chart("Energy", vec![("Energy", "#FFC400", series(|sample| sample.energy as f32))]) }
+            { line_chart("Energy totals", vec![
+                ("Consumed", "#FF3300", series(|sample| sample.energy_consumed as f32)),
+                ("Recharged", "#00B3FF", series(|sample| sample.energy_recharged as f32)),
+            ]) }
+            { line_chart("Discovered %", vec![("Discovered", "#23B606", series(|sample| sample.discovered))]) }
+            { line_chart("Frontier tiles", vec![("Frontier", "#FF00FF", series(|sample| sample.frontier as f32))]) }
+            { line_chart("Road tiles paved", vec![("Paved", "#000000", series(|sample| sample.paved as f32))]) }
+            { line_chart("Missions", MissionStatus::ALL.iter().enumerate().map(|(index, status)| {
+                let counts = samples.iter().map(|sample| sample.missions[index] as f32).collect();
+                (status_label(status), STATUS_COLORS[index], counts)
+            }).collect()) }
+        </>
+    }
+}
+
+#[function_component(MissionQueue)]
+fn mission_queue() -> Html {
+    let mission_state = use_atom::<MissionState>();
+    let send = {
+        let commands = mission_state.commands.clone();
+        move |command: MissionCommand| {
+            let commands = commands.clone();
+            Callback::from(move |_| commands.borrow_mut().push(command))
+        }
+    };
+
+    html! {
+        <details id={"missions"}>
+            <summary>{format!("Missions ({})", mission_state.missions.len())}</summary>
+            <table>
+                <tr>
+                    <th>{"Id"}</th><th>{"Type"}</th><th>{"Status"}</th><th>{"Sector"}</th><th>{"Remaining"}</th><th>{"Priority"}</th><th></th>
+                </tr>
+                { for mission_state.missions.iter().map(|mission| {
+                    let id = mission.id;
+                    let commands = mission_state.commands.clone();
+                    let on_priority = Callback::from(move |e: yew::prelude::Event| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        if let Ok(priority) = input.value().parse::<u8>() {
+                            commands.borrow_mut().push(MissionCommand::SetPriority(id, priority));
                         }
+                    });
+                    let finished = mission.status.is_finished();
+                    html! {
+                        <tr>
+                            <td>{id}</td>
+                            <td>{mission.kind}</td>
+                            <td>{ if mission.held { format!("{} (held)", status_label(&mission.status)) } else { status_label(&mission.status).to_string() } }</td>
+                            <td>{mission.spatial_index.map(|index| index.to_string()).unwrap_or_default()}</td>
+                            <td>{mission.remaining.map(|remaining| remaining.to_string()).unwrap_or_default()}</td>
+                            <td><input type={"number"} min={"0"} max={"255"} value={mission.priority.to_string()} onchange={on_priority} /></td>
+                            <td>
+                                { if mission.held {
+                                    html! { <button onclick={send(MissionCommand::Resume(id))}>{"Resume"}</button> }
+                                } else {
+                                    html! { <button onclick={send(MissionCommand::Pause(id))} disabled={finished}>{"Pause"}</button> }
+                                }}
+                                <button onclick={send(MissionCommand::MoveToFront(id))}>{"Top"}</button>
+                                <button onclick={send(MissionCommand::Cancel(id))}>{"Cancel"}</button>
+                            </td>
+                        </tr>
                     }
-                    _ => (),
+                })}
+            </table>
+        </details>
+    }
+}
+
+const LOG_LEVELS: [log::LevelFilter; 6] = [
+    log::LevelFilter::Off,
+    log::LevelFilter::Error,
+    log::LevelFilter::Warn,
+    log::LevelFilter::Info,
+    log::LevelFilter::Debug,
+    log::LevelFilter::Trace,
+];
+
+// milliseconds between two reads of the logger
+const LOG_POLL_TIME: u32 = 500;
+// lines kept by the console and lines shown after filtering
+const LOG_CONSOLE_LINES: usize = 2000;
+const LOG_SHOWN_LINES: usize = 300;
+
+#[function_component(LogConsole)]
+fn log_console() -> Html {
+    let entries = use_mut_ref(Vec::<LogEntry>::new);
+    // kept apart from the entries so that clearing the console does not read everything again
+    let last_id = use_mut_ref(|| None::<usize>);
+    let redraw = use_force_update();
+    let text = use_state_eq(String::new);
+    let min_level = use_state_eq(|| log::LevelFilter::Trace);
+    let target = use_state_eq(String::new);
+
+    {
+        let entries = entries.clone();
+        let last_id = last_id.clone();
+        let redraw = redraw.clone();
+        use_interval(
+            move || {
+                let new_entries = logging::entries_after(*last_id.borrow());
+                let Some(last) = new_entries.last() else {
+                    return;
                 };
+                *last_id.borrow_mut() = Some(last.id);
+                let mut entries = entries.borrow_mut();
+                entries.extend(new_entries);
+                let extra = entries.len().saturating_sub(LOG_CONSOLE_LINES);
+                entries.drain(..extra);
+                redraw.force_update();
+            },
+            LOG_POLL_TIME,
+        );
+    }
 
-                println!();
+    let on_text = {
+        let text = text.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            text.set(input.value());
+        })
+    };
+    let on_level = {
+        let min_level = min_level.clone();
+        Callback::from(move |e: yew::prelude::Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Ok(level) = select.value().parse::<log::LevelFilter>() {
+                min_level.set(level);
             }
+        })
+    };
+    let on_target = {
+        let target = target.clone();
+        Callback::from(move |e: yew::prelude::Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            target.set(select.value());
+        })
+    };
+    let clear = {
+        let entries = entries.clone();
+        let redraw = redraw.clone();
+        Callback::from(move |_| {
+            entries.borrow_mut().clear();
+            redraw.force_update();
+        })
+    };
 
-            fn get_energy(&self) -> &Energy {
-                &self.robot.energy
-            }
-            fn get_energy_mut(&mut self) -> &mut Energy {
-                &mut self.robot.energy
-            }
+    let entries = entries.borrow();
+    let needle = text.to_lowercase();
+    let shown: Vec<&LogEntry> = entries
+        .iter()
+        .rev()
+        .filter(|entry| entry.level <= *min_level)
+        .filter(|entry| target.is_empty() || entry.target == *target)
+        .filter(|entry| needle.is_empty() || entry.message.to_lowercase().contains(&needle))
+        .take(LOG_SHOWN_LINES)
+        .collect();
 
-            fn get_coordinate(&self) -> &Coordinate {
-                &self.robot.coordinate
-            }
-            fn get_coordinate_mut(&mut self) -> &mut Coordinate {
-                &mut self.robot.coordinate
-            }
+    html! {
+        <details id={"logconsole"}>
+            <summary>{format!("Log ({})", entries.len())}</summary>
+            <div>
+                <input type={"text"} placeholder={"filter"} value={(*text).clone()} oninput={on_text} />
+                <select onchange={on_level}>
+                    { for LOG_LEVELS.iter().skip(1).map(|level| html! {
+                        <option value={level.to_string()} selected={*level == *min_level}>{level.to_string()}</option>
+                    })}
+                </select>
+                <select onchange={on_target}>
+                    <option value={""} selected={target.is_empty()}>{"all"}</option>
+                    { for logging::TARGETS.iter().map(|name| html! {
+                        <option value={*name} selected={*target == *name}>{*name}</option>
+                    })}
+                </select>
+                <button onclick={clear}>{"Clear"}</button>
+            </div>
+            <pre>
+                { for shown.iter().rev().map(|entry| html! {
+                    <div class={classes!("log", entry.level.as_str().to_lowercase())}>
+                        {format!("{:>5} [{}] {}", entry.level, entry.target, entry.message)}
+                    </div>
+                })}
+            </pre>
+        </details>
+    }
+}
 
-            fn get_backpack(&self) -> &BackPack {
-                &self.robot.backpack
-            }
-            fn get_backpack_mut(&mut self) -> &mut BackPack {
-                &mut self.robot.backpack
-            }
+fn status_label(status: &MissionStatus) -> &'static str {
+    match status {
+        MissionStatus::New => "New",
+        MissionStatus::Active => "Active",
+        MissionStatus::Paused => "Paused",
+        MissionStatus::Completed => "Completed",
+        MissionStatus::Failed => "Failed",
+    }
+}
+
+const CHART_WIDTH: f32 = 300.0;
+const CHART_HEIGHT: f32 = 80.0;
+// long runs are drawn with at most this many points per line
+const CHART_POINTS: usize = 300;
+
+// one line per series, all on the same scale, with the last value of each in the legend
+fn line_chart(title: &'static str, series: Vec<(&'static str, &'static str, Vec<f32>)>) -> Html {
+    let len = series.iter().map(|(_, _, values)| values.len()).max().unwrap_or(0);
+    let (min, max) = series
+        .iter()
+        .flat_map(|(_, _, values)| values.iter())
+        .fold((0.0f32, f32::MIN), |(min, max), value| (min.min(*value), max.max(*value)));
+    let range = (max - min).max(1.0);
+    let step = (len / CHART_POINTS).max(1);
+
+    html! {
+        <div class={"chart"}>
+            <h3>{title}</h3>
+            <svg width={CHART_WIDTH.to_string()} height={CHART_HEIGHT.to_string()}>
+                { for series.iter().map(|(_, color, values)| {
+                    let points: String = values
+                        .iter()
+                        .enumerate()
+                        .filter(|(index, _)| index % step == 0 || index + 1 == values.len())
+                        .map(|(index, value)| {
+                            let x = index as f32 / (len.max(2) - 1) as f32 * CHART_WIDTH;
+                            let y = CHART_HEIGHT - (value - min) / range * CHART_HEIGHT;
+                            format!("{:.1},{:.1} ", x, y)
+                        })
+                        .collect();
+                    html! { <polyline points={points} fill={"none"} stroke={*color} stroke-width={"2"} /> }
+                })}
+            </svg>
+            <p class={"legend"}>
+                { for series.iter().map(|(label, color, values)| html! {
+                    <span style={format!("color: {};", color)}>{format!("{} {} ", label, values.last().map(|value| format!("{:.1}", value)).unwrap_or_default())}</span>
+                })}
+            </p>
+        </div>
+    }
+}
+
+// State sink that forwards the robot updates to the Bounce atoms
+pub(crate) struct BounceSink {
+    bps: UseAtomHandle<BackpackState>,
+    ws: UseAtomHandle<WorldState>,
+    rs: UseAtomHandle<RobotState>,
+    env: UseAtomHandle<EnviromentalState>,
+    en: UseAtomHandle<EnergyState>,
+    extras: UseAtomHandle<ExtrasState>,
+    snapshots: Rc<SnapshotSlot>,
+    overlay: UseAtomHandle<OverlayData>,
+    overlay_wanted: Rc<Cell<bool>>,
+    report: UseAtomHandle<TileReportState>,
+    telemetry: UseAtomHandle<TelemetryState>,
+    missions: UseAtomHandle<MissionState>,
+    inspect_request: Rc<Cell<Option<(usize, usize)>>>,
+    // the handles only hold the value from when they were created, so the last values are kept here
+    world_counter: usize,
+    last_time: String,
+    last_energy: Option<usize>,
+    last_backpack: HashMap<Content, usize>,
+    last_score: f32,
+    score_tracker: ScoreTracker,
+    last_overlay: AiOverlay,
+    last_report: Option<TileReport>,
+    last_missions: Vec<MissionSummary>,
+}
+
+impl StateSink for BounceSink {
+    fn map_changed(&mut self, size: usize, diff: &MapDiff) {
+        self.ws.map.borrow_mut().apply(size, diff);
+        self.world_counter += 1;
+        self.ws.set(WorldState {
+            map: self.ws.map.clone(),
+            counter: self.world_counter,
+        });
+    }
+    fn robot_moved(&mut self, coord: (usize, usize)) {
+        self.rs.set(RobotState { coord });
+    }
+    fn energy_changed(&mut self, energy: usize) {
+        if self.last_energy != Some(energy) {
+            self.last_energy = Some(energy);
+            self.en.set(EnergyState { energy })
         }
-        fn first_tick(jerry: &mut Jerry, world: &mut World) {
-            let size = get_world_dimension(world);
-            jerry.world_dim = size;
-            jerry.active_region.spatial_index = calculate_spatial_index(
-                jerry.get_coordinate().get_row(),
-                jerry.get_coordinate().get_col(),
+    }
+    fn backpack_changed(&mut self, size: usize, content: &HashMap<Content, usize>) {
+        if *content != self.last_backpack {
+            self.last_backpack = content.clone();
+            self.bps.set(BackpackState {
                 size,
-            );
-            let explorer = new_explorer(jerry, world, jerry.active_region.spatial_index);
-            jerry.missions.push_back(explorer);
+                content: content.clone(),
+            });
         }
-        // RUNNING THE GAME
-        let r = Jerry {
-            robot: Robot::new(),
+    }
+    fn conditions_changed(&mut self, conditions: &EnvironmentalConditions) {
+        let tmp_time = conditions.get_time_of_day_string();
+        if self.last_time != tmp_time {
+            self.last_time = tmp_time.clone();
+            self.env.set(EnviromentalState {
+                forecast: conditions.get_weather_condition(),
+                time: tmp_time,
+            });
+        }
+    }
+    fn score_changed(&mut self, score: f32) {
+        self.last_score = score;
+    }
+    fn event_received(&mut self, event: &RobotEvent) {
+        self.score_tracker.event(event);
+    }
+    fn telemetry_sampled(&mut self, sample: &TelemetrySample) {
+        self.telemetry.series.borrow_mut().push(sample);
+        if sample.tick % TELEMETRY_PUBLISH_TICKS == 0 {
+            self.telemetry.set(TelemetryState {
+                series: self.telemetry.series.clone(),
+                counter: sample.tick,
+            });
+        }
+    }
+    fn missions_changed(&mut self, missions: &[MissionSummary]) {
+        if self.last_missions != missions {
+            self.last_missions = missions.to_vec();
+            self.missions.set(MissionState {
+                missions: missions.to_vec(),
+                commands: self.missions.commands.clone(),
+            });
+        }
+    }
+    fn mission_commands(&mut self) -> Vec<MissionCommand> {
+        self.missions.commands.borrow_mut().drain(..).collect()
+    }
+    fn tick_finished(&mut self, tick: usize) {
+        if self.score_tracker.tick(tick, self.last_score) {
+            self.extras.set(ExtrasState {
+                score: self.score_tracker.score(),
+                history: Rc::new(self.score_tracker.history().clone()),
+                breakdown: self.score_tracker.breakdown(),
+            });
+        }
+    }
+    fn wants_snapshot(&mut self) -> bool {
+        self.snapshots.requested.get()
+    }
+    fn snapshot_taken(&mut self, snapshot: RobotSnapshot) {
+        self.snapshots.requested.set(false);
+        *self.snapshots.robot.borrow_mut() = Some(snapshot);
+        *self.snapshots.score.borrow_mut() = Some(self.score_tracker.clone());
+    }
+    fn wants_overlay(&mut self) -> bool {
+        self.overlay_wanted.get()
+    }
+    fn overlay_changed(&mut self, overlay: AiOverlay) {
+        if overlay != self.last_overlay {
+            self.last_overlay = overlay.clone();
+            self.overlay.set(OverlayData {
+                overlay: Rc::new(overlay),
+            });
+        }
+    }
+    fn inspected_tile(&mut self) -> Option<(usize, usize)> {
+        self.inspect_request.get()
+    }
+    fn tile_inspected(&mut self, report: TileReport) {
+        if self.last_report.as_ref() != Some(&report) {
+            self.last_report = Some(report.clone());
+            self.report.set(TileReportState { report: Some(report) });
+        }
+    }
+}
+
+#[function_component(TimoAi)]
+pub fn timo_ai() -> Html {
+    // USESTATES
+    let back_state = use_atom::<BackpackState>();
+    let world_state = use_atom::<WorldState>();
+    let robot_state = use_atom::<RobotState>();
+    let env_state = use_atom::<EnviromentalState>();
+    let energy_state = use_atom::<EnergyState>();
+    let extra_state = use_atom::<ExtrasState>();
+    let settings = use_atom::<StartingSettings>();
+    let uploaded_world = use_atom::<UploadedWorld>();
+    let replay_state = use_atom::<ReplayState>();
+    let session = use_atom::<SessionState>();
+    let overlay_state = use_atom::<OverlayState>();
+    let overlay_data = use_atom::<OverlayData>();
+    let inspector = use_atom::<InspectorState>();
+    let report_state = use_atom::<TileReportState>();
+    let telemetry_state = use_atom::<TelemetryState>();
+    let mission_state = use_atom::<MissionState>();
+
+    // RUNNING THE GAME, again every time a snapshot is loaded
+    use_effect_with(session.generation, move |generation| {
+        // the new robot sends everything it knows again
+        world_state.map.borrow_mut().replace(Vec::new());
+        telemetry_state.series.borrow_mut().clear();
+        mission_state.commands.borrow_mut().clear();
+        // checked on the start screen, empty means the default scores
+        let score_map = parse_score_map(&settings.score_map).ok().filter(|score_map| !score_map.is_empty());
+        // a resumed game goes on with the score it had
+        let score_tracker = match &session.resume {
+            Some(snapshot) => snapshot.score.clone(),
+            None => ScoreTracker::new(effective_score_map(score_map.as_ref())),
+        };
+        extra_state.set(ExtrasState {
+            score: score_tracker.score(),
+            history: Rc::new(score_tracker.history().clone()),
+            breakdown: score_tracker.breakdown(),
+        });
+        let sink = BounceSink {
             bps: back_state.clone(),
             ws: world_state.clone(),
             rs: robot_state.clone(),
             env: env_state.clone(),
             en: energy_state.clone(),
             extras: extra_state.clone(),
-            tick_counter: 0,
-            world_dim: 0,
-            active_region: ActiveRegion {
-                top_left: (279, 279),
-                bottom_right: (0, 0),
-                spatial_index: 0,
-            },
-            vent: Rc::new(RefCell::new(Vent::new())),
-            road_tiles: HashSet::new(),
-            dynamo: Dynamo {},
-            weather_predictor: WeatherPredictionTool::new(),
-            tom_tom: TomTom {},
-            charting_tools: ChartingTools,
-            missions: VecDeque::new(),
+            snapshots: session.snapshots.clone(),
+            world_counter: world_state.counter,
+            last_time: String::new(),
+            last_energy: None,
+            last_backpack: HashMap::new(),
+            last_score: score_tracker.score(),
+            score_tracker,
+            overlay: overlay_data.clone(),
+            overlay_wanted: overlay_state.wanted.clone(),
+            last_overlay: AiOverlay::default(),
+            report: report_state.clone(),
+            telemetry: telemetry_state.clone(),
+            missions: mission_state.clone(),
+            inspect_request: inspector.request.clone(),
+            last_report: None,
+            last_missions: Vec::new(),
         };
+        let sink = ReplaySink::new(Box::new(sink), replay_state.replay.clone());
+        let scheduler = settings.scheduler.build();
+        if session.resume.is_none() {
+            session.control.set_tick_time(settings.tick_time);
+            session.control.set_tick_limit(settings.tick_limit);
+        }
 
-        let mut generator = WorldgeneratorUnwrap::init(false, Some(PathBuf::from("world.bin")));
-        let run = Rc::new(RefCell::new(Runner::new(Box::new(r), &mut generator)));
+        let runner = match &session.resume {
+            Some(snapshot) => {
+                *session.snapshots.world.borrow_mut() = Some(snapshot.world.clone());
+                let r = Jerry::restore(Box::new(sink), scheduler, &snapshot.robot);
+                Runner::new(Box::new(r), &mut SnapshotGenerator::new((**snapshot).clone()))
+            }
+            None => {
+                let r = Jerry::new(Box::new(sink), scheduler, settings.seed);
+                match settings.world_kind {
+                    WorldKind::File => {
+                        let mut generator = match &uploaded_world.bytes {
+                            Some(bytes) => WorldgeneratorUnwrap::from_bytes(bytes.to_vec()),
+                            None => WorldgeneratorUnwrap::init(false, None),
+                        };
+                        if let Some(score_map) = score_map {
+                            generator.set_score_hashmap(score_map);
+                        }
+                        *session.snapshots.world.borrow_mut() = generator.saved_world().ok();
+                        Runner::new(Box::new(r), &mut generator)
+                    }
+                    WorldKind::Procedural => {
+                        let mut generator = ProceduralGenerator::new(ProceduralConfig {
+                            seed: settings.seed,
+                            size: settings.world_size,
+                            ..Default::default()
+                        });
+                        if let Some(score_map) = score_map {
+                            generator.set_score_hashmap(score_map);
+                        }
+                        *session.snapshots.world.borrow_mut() = Some(generator.saved_world());
+                        Runner::new(Box::new(r), &mut generator)
+                    }
+                }
+            }
+        };
+        let run = Rc::new(RefCell::new(runner));
+
+        info!(target: logging::RUNNER, "Starting game");
+        let generation = *generation;
+        session.live.set(generation);
+        wasm_bindgen_futures::spawn_local(async move {
+            let _done = run_game(run, session.control.clone(), replay_state.paused.clone(), session.live.clone(), generation).await;
+        });
+        || ()
+    });
 
-        if world_state.counter == 0 {
-            info!("STARTING GAME...");
-            wasm_bindgen_futures::spawn_local(async move {
-                let _done = run_game(run, settings.clone()).await;
-            });
-        }
-    }
     html! {
         <></>
     }
@@ -779,12 +2243,33 @@ pub fn timo_ai() -> Html {
 
 async fn run_game(
     run: Rc<RefCell<Result<Runner, LibError>>>,
-    settings: UseAtomHandle<StartingSettings>,
+    control: Rc<RunControl>,
+    paused: Rc<Cell<bool>>,
+    live: Rc<Cell<usize>>,
+    generation: usize,
 ) -> () {
-    let tick_time = settings.tick_time.clone();
-    // let mut counter = 0;
+    let mut counter = 0;
     sleep(1000).await;
-    for _ in 0..100000 {
+    loop {
+        // a newer game was started from a snapshot
+        if live.get() != generation {
+            return;
+        }
+        if paused.get() {
+            sleep(WAIT_TIME).await;
+            continue;
+        }
+        match control.next(counter) {
+            RunAction::Stop => {
+                info!(target: logging::RUNNER, "Stopped after {} ticks", counter);
+                return;
+            }
+            RunAction::Wait => {
+                sleep(WAIT_TIME).await;
+                continue;
+            }
+            RunAction::Tick => (),
+        }
         // info!("[ RUNNER ] Tick {:?}", tick_time);
         // Get a mutable reference to the Result<Runner>
         let mut runner_result = run.borrow_mut();
@@ -792,20 +2277,47 @@ async fn run_game(
         runner_result
             .as_mut()
             .map(|runner| {
-                let _ = runner.game_tick();
-            })
-            .map_err(|e| {
-                info!("[ RUNNER ] ERROR WITH RUN: {:?}", e);
+                if let Err(e) = runner.game_tick() {
+                    error!(target: logging::RUNNER, "Error with run: {:?}", e);
+                }
             })
-            .unwrap_or_else(|_| {
-                info!("[ RUNNER ] ERROR WITH RUN. ");
+            .unwrap_or_else(|e| {
+                error!(target: logging::RUNNER, "Error with run: {:?}", e);
             });
-        sleep(tick_time).await;
+        drop(runner_result);
+        // read every tick, so the delay can be changed from the Menu
+        sleep(control.tick_time()).await;
 
-        // counter = counter +1;
+        counter = counter + 1;
     }
 }
 
+// milliseconds between two checks while the game is paused
+const WAIT_TIME: u32 = 100;
+
+// Reads a file chosen in a file input
+async fn read_file(file: &web_sys::File) -> Result<Vec<u8>, String> {
+    let buffer = JsFuture::from(file.array_buffer())
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    Ok(js_sys::Uint8Array::new(&buffer).to_vec())
+}
+
+// Lets the browser download the bytes as a file
+fn download(bytes: &[u8], file_name: &str) -> Result<(), wasm_bindgen::JsValue> {
+    let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes));
+    let blob = web_sys::Blob::new_with_u8_array_sequence(&parts)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let document = window()
+        .and_then(|win| win.document())
+        .ok_or(wasm_bindgen::JsValue::NULL)?;
+    let anchor: web_sys::HtmlAnchorElement = document.create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)
+}
+
 // Custom sleep function to support the web
 async fn sleep(duration: u32) {
     let promise = js_sys::Promise::new(&mut |resolve, _| {
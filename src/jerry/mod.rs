@@ -0,0 +1,330 @@
+use charting_tools::charted_coordinate::ChartedCoordinate;
+use charting_tools::ChartingTools;
+use ohcrab_weather::weather_tool::WeatherPredictionTool;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use robotics_lib::energy::Energy;
+use robotics_lib::event::events::Event;
+use robotics_lib::interface::{get_score, look_at_sky, robot_map, where_am_i};
+use robotics_lib::runner::backpack::BackPack;
+use robotics_lib::runner::{Robot, Runnable};
+use robotics_lib::world::coordinates::Coordinate;
+use robotics_lib::world::World;
+use rust_and_furious_dynamo::dynamo::Dynamo;
+use rust_eze_tomtom::TomTom;
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::rc::Rc;
+use vent_tool_ascii_crab::Vent;
+
+// tools like the charting ones can discover tiles far from the robot, a full scan every so often picks them up
+const MAP_RESYNC_TICKS: usize = 100;
+
+use crate::explorer::new_explorer;
+use crate::fast_paths::{Path, PathCache};
+use crate::inspector::inspect_tile;
+use crate::logging;
+use crate::map_tracker::MapTracker;
+use crate::mission_control::{apply_command, assign_ids, summarize};
+use crate::overlay::collect_overlay;
+use crate::scheduler::Scheduler;
+use crate::sink::StateSink;
+use crate::snapshot::{rediscover_known_tiles, take_snapshot, RobotSnapshot};
+use crate::telemetry::take_sample;
+use crate::utils::{
+    calculate_spatial_index, execute_mission, get_world_dimension, ActiveRegion, Mission,
+};
+
+// TIMO CODE
+pub(crate) struct Jerry {
+    pub(crate) robot: Robot,
+    pub(crate) sink: Box<dyn StateSink>,
+    pub(crate) tick_counter: usize,
+    pub(crate) world_dim: usize,
+    pub(crate) active_region: ActiveRegion,
+    pub(crate) road_tiles: HashSet<ChartedCoordinate>,
+    pub(crate) vent: Rc<RefCell<Vent>>,
+    pub(crate) dynamo: Dynamo,
+    pub(crate) weather_predictor: WeatherPredictionTool,
+    pub(crate) tom_tom: TomTom,
+    pub(crate) charting_tools: ChartingTools,
+    pub(crate) missions: VecDeque<Mission>,
+    pub(crate) scheduler: Box<dyn Scheduler>,
+    // every random choice of the AI goes through here, so a run can be replayed with the same seed
+    // it is the generator behind StdRng, which cannot be saved in a snapshot
+    pub(crate) seed: u64,
+    pub(crate) rng: ChaCha12Rng,
+    pub(crate) map_tracker: MapTracker,
+    pub(crate) path_cache: PathCache,
+    // only kept to show them in the frontend
+    pub(crate) sector_nodes: Vec<(usize, usize)>,
+    pub(crate) current_path: Option<Path>,
+    // totals of the energy events, for the telemetry
+    pub(crate) energy_consumed: usize,
+    pub(crate) energy_recharged: usize,
+    pub(crate) next_mission_id: usize,
+    // tiles known when the snapshot was taken, discovered again on the first tick after a resume
+    pub(crate) rediscover: Vec<(usize, usize)>,
+}
+
+impl Jerry {
+    pub(crate) fn new(sink: Box<dyn StateSink>, scheduler: Box<dyn Scheduler>, seed: u64) -> Jerry {
+        Jerry {
+            robot: Robot::new(),
+            sink,
+            tick_counter: 0,
+            world_dim: 0,
+            active_region: ActiveRegion {
+                top_left: (279, 279),
+                bottom_right: (0, 0),
+                spatial_index: 0,
+            },
+            vent: Rc::new(RefCell::new(Vent::new())),
+            road_tiles: HashSet::new(),
+            dynamo: Dynamo {},
+            weather_predictor: WeatherPredictionTool::new(),
+            tom_tom: TomTom {},
+            charting_tools: ChartingTools,
+            missions: VecDeque::new(),
+            scheduler,
+            seed,
+            rng: ChaCha12Rng::seed_from_u64(seed),
+            map_tracker: MapTracker::new(),
+            path_cache: PathCache::new(),
+            sector_nodes: Vec::new(),
+            current_path: None,
+            energy_consumed: 0,
+            energy_recharged: 0,
+            next_mission_id: 0,
+            rediscover: Vec::new(),
+        }
+    }
+
+    /*
+        Jerry as it was when the snapshot was taken.
+        The position comes from the world generator, the rng goes on from where it was.
+     */
+    pub(crate) fn restore(sink: Box<dyn StateSink>, scheduler: Box<dyn Scheduler>, snapshot: &RobotSnapshot) -> Jerry {
+        let mut jerry = Jerry::new(sink, scheduler, snapshot.seed);
+        jerry.rng = snapshot.rng.clone();
+        jerry.tick_counter = snapshot.tick_counter;
+        jerry.world_dim = snapshot.world_dim;
+        jerry.active_region = snapshot.active_region;
+        jerry.road_tiles = snapshot.road_tiles.clone();
+        jerry.missions = snapshot.missions.clone();
+        jerry.next_mission_id = jerry.missions.iter().filter_map(|mission| mission.id).max().map_or(0, |id| id + 1);
+        jerry.robot.energy = Energy::new(snapshot.energy);
+        jerry.energy_consumed = snapshot.energy_consumed;
+        jerry.energy_recharged = snapshot.energy_recharged;
+        jerry.rediscover = snapshot.known_map.iter().enumerate()
+            .flat_map(|(row, tiles)| tiles.iter().enumerate()
+                .filter(|(_, tile)| tile.is_some())
+                .map(move |(col, _)| (row, col)))
+            .collect();
+        let contents = jerry.get_backpack_mut().get_contents_mut();
+        contents.clear();
+        contents.extend(snapshot.backpack.iter().map(|(content, amount)| (content.clone(), *amount)));
+        jerry
+    }
+}
+
+impl Runnable for Jerry {
+    fn process_tick(&mut self, world: &mut World) {
+        if self.tick_counter == 0 {
+            first_tick(self, world);
+        }
+        if !self.rediscover.is_empty() {
+            rediscover_known_tiles(self, world);
+        }
+        for command in self.sink.mission_commands() {
+            if !apply_command(&mut self.missions, command) {
+                log::warn!(target: logging::JERRY, "Mission command {:?} ignored", command);
+            }
+        }
+        //only the path walked during this tick is shown
+        self.current_path = None;
+        execute_mission(self, world);
+        log::debug!(target: logging::JERRY, "Tick {}, energy {}, path cache {} hits {} misses", self.tick_counter,
+            self.robot.energy.get_energy_level(), self.path_cache.hits(), self.path_cache.misses());
+        let tick = self.tick_counter;
+        self.tick_counter += 1;
+
+        // Update UI State
+        if tick % MAP_RESYNC_TICKS == 0 {
+            self.map_tracker.resync();
+        }
+        if self.map_tracker.has_moved() {
+            let (view, position) = where_am_i(self, world);
+            self.map_tracker.seen_around(position, &view);
+        }
+        if self.map_tracker.has_changes() {
+            //the whole map is only read on a full scan
+            let diff = if self.map_tracker.needs_full_scan() {
+                self.map_tracker.diff(&robot_map(&world).unwrap_or_default())
+            } else {
+                self.map_tracker.diff_seen()
+            };
+            //the paths through a tile that changed may not be the shortest anymore, and it may be a teleport
+            for (row, col, tile) in diff.iter() {
+                self.path_cache.tile_changed((*row, *col), tile.as_ref());
+            }
+            if !diff.is_empty() {
+                self.sink.map_changed(self.map_tracker.size(), &diff);
+            }
+        }
+        if self.sink.wants_overlay() {
+            let overlay = collect_overlay(self);
+            self.sink.overlay_changed(overlay);
+        }
+        if let Some(position) = self.sink.inspected_tile() {
+            let tmp_map = robot_map(&world).unwrap_or_default();
+            let report = inspect_tile(self, world, &tmp_map, position);
+            self.sink.tile_inspected(report);
+        }
+        let tmp_conditions = look_at_sky(&world);
+        self.sink.conditions_changed(&tmp_conditions);
+        self.sink.score_changed(get_score(&world));
+        self.next_mission_id = assign_ids(&mut self.missions, self.next_mission_id);
+        self.sink.missions_changed(&summarize(&self.missions));
+        let sample = take_sample(self, tick);
+        self.sink.telemetry_sampled(&sample);
+        self.sink.tick_finished(tick);
+        if self.sink.wants_snapshot() {
+            let snapshot = take_snapshot(self, world);
+            self.sink.snapshot_taken(snapshot);
+        }
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        log::trace!(target: logging::JERRY, "{:?}", event);
+        self.sink.event_received(&event);
+        match event {
+            Event::AddedToBackpack(_, _) | Event::RemovedFromBackpack(_, _) => {
+                let backpack = self.get_backpack();
+                let (size, content) = (backpack.get_size(), backpack.get_contents().clone());
+                self.sink.backpack_changed(size, &content);
+            }
+            Event::Moved(tile, position) => {
+                if position.0 >= self.active_region.bottom_right.0 {
+                    self.active_region.bottom_right.0 = if position.0 == self.world_dim - 1
+                    {
+                        self.world_dim - 1
+                    } else {
+                        position.0 + 1
+                    };
+                }
+                if position.1 >= self.active_region.bottom_right.1 {
+                    self.active_region.bottom_right.1 = if position.1 == self.world_dim - 1
+                    {
+                        self.world_dim - 1
+                    } else {
+                        position.1 + 1
+                    };
+                }
+                if position.0 <= self.active_region.top_left.0 {
+                    self.active_region.top_left.0 =
+                        if position.0 == 0 { 0 } else { position.0 - 1 };
+                }
+                if position.1 <= self.active_region.top_left.1 {
+                    self.active_region.top_left.1 =
+                        if position.1 == 0 { 0 } else { position.1 - 1 };
+                }
+                self.map_tracker.moved(position, &tile);
+                self.path_cache.touch_around(position);
+                let tmp_coords = (self.get_coordinate().get_row(), self.get_coordinate().get_col());
+                self.sink.robot_moved(tmp_coords);
+            }
+            Event::TileContentUpdated(tile, position) => {
+                self.map_tracker.seen(position, Some(tile));
+                self.path_cache.touch(position);
+            }
+            Event::EnergyRecharged(amount) => {
+                self.energy_recharged += amount;
+                let tmp_energy = self.get_energy().get_energy_level();
+                self.sink.energy_changed(tmp_energy);
+            }
+            Event::EnergyConsumed(amount) => {
+                self.energy_consumed += amount;
+                let tmp_energy = self.get_energy().get_energy_level();
+                self.sink.energy_changed(tmp_energy);
+            }
+            _ => (),
+        };
+    }
+
+    fn get_energy(&self) -> &Energy {
+        &self.robot.energy
+    }
+    fn get_energy_mut(&mut self) -> &mut Energy {
+        &mut self.robot.energy
+    }
+
+    fn get_coordinate(&self) -> &Coordinate {
+        &self.robot.coordinate
+    }
+    fn get_coordinate_mut(&mut self) -> &mut Coordinate {
+        &mut self.robot.coordinate
+    }
+
+    fn get_backpack(&self) -> &BackPack {
+        &self.robot.backpack
+    }
+    fn get_backpack_mut(&mut self) -> &mut BackPack {
+        &mut self.robot.backpack
+    }
+}
+
+fn first_tick(jerry: &mut Jerry, world: &mut World) {
+    let size = get_world_dimension(world);
+    jerry.world_dim = size;
+    jerry.active_region.spatial_index = calculate_spatial_index(
+        jerry.get_coordinate().get_row(),
+        jerry.get_coordinate().get_col(),
+        size,
+    );
+    let explorer = new_explorer(jerry, world, jerry.active_region.spatial_index);
+    jerry.missions.push_back(explorer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::SchedulerPolicy;
+    use crate::sink::{RecordingSink, StateUpdate};
+    use crate::telemetry::TelemetryLog;
+    use crate::worldloader::WorldgeneratorUnwrap;
+    use robotics_lib::runner::Runner;
+
+    #[test]
+    fn test_jerry_reports_to_sink(){
+        let recorder = RecordingSink::new();
+        let jerry = Jerry::new(Box::new(recorder.clone()), SchedulerPolicy::Alternating.build(), 0);
+        let mut generator = WorldgeneratorUnwrap::init(false, None);
+        let mut runner = Runner::new(Box::new(jerry), &mut generator).unwrap();
+        for _ in 0..5 {
+            let _ = runner.game_tick();
+        }
+
+        let updates = recorder.updates();
+        //the first tick sends what the robot sees around itself
+        assert!(updates.iter().any(|update| matches!(update, StateUpdate::Map(diff) if !diff.is_empty())));
+        assert!(updates.iter().any(|update| matches!(update, StateUpdate::Conditions { .. })));
+        assert!(updates.iter().any(|update| matches!(update, StateUpdate::Score(_))));
+    }
+    #[test]
+    fn test_robot_recovers_from_low_energy(){
+        //idling gives no energy back, the missions that use the dynamo have to keep running
+        for policy in [SchedulerPolicy::Priority, SchedulerPolicy::Cautious] {
+            let log = TelemetryLog::new();
+            let mut jerry = Jerry::new(Box::new(log.clone()), policy.build(), 0);
+            jerry.robot.energy = Energy::new(5);
+            let mut generator = WorldgeneratorUnwrap::init(false, None);
+            let mut runner = Runner::new(Box::new(jerry), &mut generator).unwrap();
+            for _ in 0..20 {
+                let _ = runner.game_tick();
+            }
+            let samples = log.samples();
+            assert!(samples.iter().any(|sample| sample.energy >= 50), "{} stayed without energy", policy);
+        }
+    }
+}
@@ -1,9 +1,13 @@
 // Frontend
+#[cfg(feature = "frontend")]
 use yew::prelude::*;
+#[cfg(feature = "frontend")]
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "frontend")]
 use bounce::BounceRoot;
 
 
+#[cfg(feature = "frontend")]
 pub mod interface;
 pub mod biomes;
 pub mod explorer;
@@ -13,12 +17,30 @@ pub mod sector_analyzer;
 pub mod road_builder;
 pub mod resources;
 pub mod fast_paths;
+pub mod scheduler;
+pub mod mission_control;
+pub mod control;
+pub mod jerry;
+pub mod sink;
+pub mod score;
+pub mod telemetry;
+pub mod map_tracker;
+pub mod inspector;
+pub mod overlay;
+pub mod replay;
+pub mod snapshot;
+pub mod worldloader;
+pub mod procedural;
+pub mod headless;
+pub mod logging;
 
+#[cfg(feature = "frontend")]
 use interface::{Main};
 
+#[cfg(feature = "frontend")]
 #[function_component(App)]
 fn app() -> Html {
-    wasm_logger::init(wasm_logger::Config::default());
+    logging::init(log::LevelFilter::Info);
 
     html! {
         <>
@@ -30,6 +52,7 @@ fn app() -> Html {
     }
 }
 
+#[cfg(feature = "frontend")]
 #[wasm_bindgen(start)]
 fn run_app() {
     // RunGame::registrar().register();
@@ -0,0 +1,177 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+// log targets of the AI modules, every level can be changed from the Menu
+pub(crate) const JERRY: &'static str = "jerry";
+pub(crate) const SCHEDULER: &'static str = "scheduler";
+pub(crate) const EXPLORER: &'static str = "explorer";
+pub(crate) const ROAD_BUILDER: &'static str = "road_builder";
+pub(crate) const RESOURCES: &'static str = "resources";
+pub(crate) const SECTOR_ANALYZER: &'static str = "sector_analyzer";
+pub(crate) const FAST_PATHS: &'static str = "fast_paths";
+pub(crate) const RUNNER: &'static str = "runner";
+
+pub(crate) const TARGETS: [&'static str; 8] = [JERRY, SCHEDULER, EXPLORER, ROAD_BUILDER, RESOURCES, SECTOR_ANALYZER, FAST_PATHS, RUNNER];
+
+// the console only keeps the last lines
+const LOG_CAPACITY: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LogEntry {
+    // grows by one with every entry, the console asks for the ones after the last it has seen
+    pub(crate) id: usize,
+    pub(crate) level: Level,
+    pub(crate) target: String,
+    pub(crate) message: String,
+}
+
+/*
+    Last log lines and the level of every target.
+    Targets without a level of their own, like the ones of the other crates, use the default one.
+ */
+#[derive(Debug)]
+pub(crate) struct LogBook {
+    entries: VecDeque<LogEntry>,
+    levels: Vec<(String, LevelFilter)>,
+    default_level: LevelFilter,
+    next_id: usize,
+}
+
+impl LogBook {
+    pub(crate) const fn new(default_level: LevelFilter) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            levels: Vec::new(),
+            default_level,
+            next_id: 0,
+        }
+    }
+    pub(crate) fn level(&self, target: &str) -> LevelFilter {
+        self.levels
+            .iter()
+            .find(|(name, _)| name == target)
+            .map_or(self.default_level, |(_, level)| *level)
+    }
+    pub(crate) fn set_level(&mut self, target: &str, level: LevelFilter) {
+        match self.levels.iter_mut().find(|(name, _)| name == target) {
+            Some((_, old)) => *old = level,
+            None => self.levels.push((target.to_string(), level)),
+        }
+    }
+    pub(crate) fn enabled(&self, level: Level, target: &str) -> bool {
+        level <= self.level(target)
+    }
+    pub(crate) fn push(&mut self, level: Level, target: &str, message: String) {
+        if self.entries.len() == LOG_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry { id: self.next_id, level, target: target.to_string(), message });
+        self.next_id += 1;
+    }
+    pub(crate) fn entries_after(&self, id: Option<usize>) -> Vec<LogEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| id.map_or(true, |id| entry.id > id))
+            .cloned()
+            .collect()
+    }
+}
+
+// writes the enabled records to the console and keeps them for the log viewer
+struct BookLogger {
+    book: Mutex<LogBook>,
+}
+
+impl Log for BookLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.book.lock().map_or(false, |book| book.enabled(metadata.level(), metadata.target()))
+    }
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        echo(record.level(), record.target(), &message);
+        if let Ok(mut book) = self.book.lock() {
+            book.push(record.level(), record.target(), message);
+        }
+    }
+    fn flush(&self) {}
+}
+
+#[cfg(all(target_arch = "wasm32", feature = "frontend"))]
+fn echo(level: Level, target: &str, message: &str) {
+    let line = wasm_bindgen::JsValue::from_str(&format!("{} [{}] {}", level, target, message));
+    match level {
+        Level::Error => web_sys::console::error_1(&line),
+        Level::Warn => web_sys::console::warn_1(&line),
+        _ => web_sys::console::log_1(&line),
+    }
+}
+
+#[cfg(not(all(target_arch = "wasm32", feature = "frontend")))]
+fn echo(level: Level, target: &str, message: &str) {
+    eprintln!("{} [{}] {}", level, target, message);
+}
+
+static LOGGER: BookLogger = BookLogger {
+    book: Mutex::new(LogBook::new(LevelFilter::Info)),
+};
+
+//installs the logger, calling it again only changes the default level
+pub fn init(default_level: LevelFilter) {
+    if let Ok(mut book) = LOGGER.book.lock() {
+        book.default_level = default_level;
+    }
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Trace);
+    }
+}
+
+pub(crate) fn level(target: &str) -> LevelFilter {
+    LOGGER.book.lock().map_or(LevelFilter::Off, |book| book.level(target))
+}
+
+pub(crate) fn set_level(target: &str, level: LevelFilter) {
+    if let Ok(mut book) = LOGGER.book.lock() {
+        book.set_level(target, level);
+    }
+}
+
+pub(crate) fn entries_after(id: Option<usize>) -> Vec<LogEntry> {
+    LOGGER.book.lock().map_or(Vec::new(), |book| book.entries_after(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_per_target(){
+        let mut book = LogBook::new(LevelFilter::Info);
+        book.set_level(EXPLORER, LevelFilter::Trace);
+        book.set_level(RESOURCES, LevelFilter::Off);
+        assert!(book.enabled(Level::Trace, EXPLORER));
+        assert!(!book.enabled(Level::Error, RESOURCES));
+        assert!(book.enabled(Level::Info, ROAD_BUILDER));
+        assert!(!book.enabled(Level::Debug, "yew"));
+        book.set_level(EXPLORER, LevelFilter::Warn);
+        assert_eq!(book.level(EXPLORER), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn test_entries_after(){
+        let mut book = LogBook::new(LevelFilter::Info);
+        for i in 0..LOG_CAPACITY + 5 {
+            book.push(Level::Info, JERRY, i.to_string());
+        }
+        let entries = book.entries_after(None);
+        assert_eq!(entries.len(), LOG_CAPACITY);
+        assert_eq!(entries[0].message, "5");
+        let last = entries.last().unwrap().id;
+        assert!(book.entries_after(Some(last)).is_empty());
+        book.push(Level::Warn, EXPLORER, "new".to_string());
+        assert_eq!(book.entries_after(Some(last))[0].target, EXPLORER);
+    }
+}
@@ -0,0 +1,188 @@
+use robotics_lib::world::tile::Tile;
+
+// tiles of the robot map that changed, with their new value
+pub(crate) type MapDiff = Vec<(usize, usize, Option<Tile>)>;
+
+//tiles that are different between the two maps, a missing row or column counts as None
+pub(crate) fn map_diff(old: &Vec<Vec<Option<Tile>>>, new: &Vec<Vec<Option<Tile>>>) -> MapDiff {
+    let mut diff = Vec::new();
+    for (i, row) in new.iter().enumerate() {
+        for (j, tile) in row.iter().enumerate() {
+            let old_tile = old.get(i).and_then(|old_row| old_row.get(j)).unwrap_or(&None);
+            if tile != old_tile {
+                diff.push((i, j, tile.clone()));
+            }
+        }
+    }
+    diff
+}
+
+/*
+    Keeps track of what the robot knows of the world, to only report the tiles that changed.
+    The robot only learns about the tiles around it when it moves, and only changes the tiles
+    it interacts with. The events carry those tiles and the robot view gives the square around
+    the robot at the end of the tick, so the whole map is only read on a full scan: at the start
+    and whenever it is asked for, in case something else discovered tiles.
+ */
+pub(crate) struct MapTracker {
+    known: Vec<Vec<Option<Tile>>>,
+    // tiles seen since the last diff, with their new value
+    seen: Vec<(usize, usize, Option<Tile>)>,
+    moved: bool,
+    full_scan: bool,
+    discovered: usize,
+}
+
+impl MapTracker {
+    pub(crate) fn new() -> Self {
+        Self {
+            known: Vec::new(),
+            seen: Vec::new(),
+            moved: false,
+            full_scan: true,
+            discovered: 0,
+        }
+    }
+
+    //the tile comes with the event, the ones around it are read from the robot view after the tick
+    pub(crate) fn moved(&mut self, position: (usize, usize), tile: &Tile) {
+        self.seen(position, Some(tile.clone()));
+        self.moved = true;
+    }
+    pub(crate) fn has_moved(&self) -> bool {
+        self.moved
+    }
+    pub(crate) fn seen(&mut self, position: (usize, usize), tile: Option<Tile>) {
+        self.seen.push((position.0, position.1, tile));
+    }
+    //the 3x3 square of the robot view, centered on the robot
+    pub(crate) fn seen_around(&mut self, position: (usize, usize), view: &Vec<Vec<Option<Tile>>>) {
+        for (i, row) in view.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if let (Some(row), Some(col)) = ((position.0 + i).checked_sub(1), (position.1 + j).checked_sub(1)) {
+                    self.seen((row, col), tile.clone());
+                }
+            }
+        }
+        self.moved = false;
+    }
+    pub(crate) fn resync(&mut self) {
+        self.full_scan = true;
+    }
+    //the robot map is needed for the next diff
+    pub(crate) fn needs_full_scan(&self) -> bool {
+        self.full_scan
+    }
+    pub(crate) fn has_changes(&self) -> bool {
+        self.full_scan || !self.seen.is_empty()
+    }
+    //side of the known map
+    pub(crate) fn size(&self) -> usize {
+        self.known.len()
+    }
+    //tiles known after the last diff
+    pub(crate) fn discovered(&self) -> usize {
+        self.discovered
+    }
+
+    //compares the whole map with the known one
+    pub(crate) fn diff(&mut self, map: &Vec<Vec<Option<Tile>>>) -> MapDiff {
+        let diff = map_diff(&self.known, map);
+        self.known = map.clone();
+        self.discovered = map.iter().flatten().filter(|tile| tile.is_some()).count();
+        self.seen.clear();
+        self.moved = false;
+        self.full_scan = false;
+        diff
+    }
+    //only the tiles seen since the last diff, the unknown tiles are left as None
+    pub(crate) fn diff_seen(&mut self) -> MapDiff {
+        let mut changed = Vec::new();
+        for (row, col, tile) in std::mem::take(&mut self.seen) {
+            let Some(known) = self.known.get_mut(row).and_then(|known_row| known_row.get_mut(col)) else {
+                continue;
+            };
+            if *known != tile {
+                match (known.is_some(), tile.is_some()) {
+                    (false, true) => self.discovered += 1,
+                    (true, false) => self.discovered -= 1,
+                    _ => (),
+                }
+                *known = tile;
+                changed.push((row, col));
+            }
+        }
+        changed.sort_unstable();
+        changed.dedup();
+        changed.into_iter().map(|(row, col)| (row, col, self.known[row][col].clone())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use robotics_lib::world::tile::{Content, TileType};
+
+    fn tile(content: Content) -> Option<Tile> {
+        Some(Tile { tile_type: TileType::Grass, content, elevation: 0 })
+    }
+
+    #[test]
+    fn test_map_diff(){
+        let old = vec![vec![None, None]];
+        let new = vec![vec![tile(Content::None), None], vec![None, tile(Content::Fire)]];
+        assert_eq!(map_diff(&old, &new), vec![(0, 0, tile(Content::None)), (1, 1, tile(Content::Fire))]);
+    }
+
+    #[test]
+    fn test_tracker_full_scan_first(){
+        let mut tracker = MapTracker::new();
+        assert!(tracker.has_changes());
+        let map = vec![vec![tile(Content::None), None], vec![None, None]];
+        assert_eq!(tracker.diff(&map), vec![(0, 0, tile(Content::None))]);
+        assert!(!tracker.has_changes());
+        assert!(tracker.diff(&map).is_empty());
+    }
+
+    #[test]
+    fn test_tracker_only_checks_seen_tiles(){
+        let mut tracker = MapTracker::new();
+        let mut map = vec![vec![None; 5]; 5];
+        tracker.diff(&map);
+
+        //the robot moves to (1, 1) and sees the square around it
+        let mut view = vec![vec![None; 3]; 3];
+        view[0][0] = tile(Content::Rock(1));
+        view[1][1] = tile(Content::None);
+        map[4][4] = tile(Content::None);
+        tracker.moved((1, 1), &Tile { tile_type: TileType::Grass, content: Content::None, elevation: 0 });
+        assert!(tracker.has_moved());
+        tracker.seen_around((1, 1), &view);
+        assert!(!tracker.has_moved());
+        assert_eq!(tracker.diff_seen(), vec![(0, 0, tile(Content::Rock(1))), (1, 1, tile(Content::None))]);
+        assert_eq!(tracker.discovered(), 2);
+
+        //the content changed by the robot comes with the event
+        tracker.seen((0, 0), tile(Content::None));
+        assert_eq!(tracker.diff_seen(), vec![(0, 0, tile(Content::None))]);
+        assert_eq!(tracker.discovered(), 2);
+
+        //found on the next full scan
+        map[0][0] = tile(Content::None);
+        map[1][1] = tile(Content::None);
+        tracker.resync();
+        assert!(tracker.needs_full_scan());
+        assert_eq!(tracker.diff(&map), vec![(4, 4, tile(Content::None))]);
+        assert_eq!(tracker.discovered(), 3);
+    }
+
+    #[test]
+    fn test_seen_out_of_the_map(){
+        let mut tracker = MapTracker::new();
+        let map = vec![vec![None; 2]; 2];
+        tracker.diff(&map);
+        tracker.seen_around((0, 0), &vec![vec![tile(Content::None); 3]; 3]);
+        tracker.seen((7, 0), tile(Content::None));
+        assert_eq!(tracker.diff_seen(), vec![(0, 0, tile(Content::None)), (0, 1, tile(Content::None)), (1, 0, tile(Content::None)), (1, 1, tile(Content::None))]);
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::VecDeque;
+
+use crate::utils::{Mission, MissionStatus};
+
+//what the frontend shows of a mission
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MissionSummary {
+    pub(crate) id: usize,
+    pub(crate) kind: &'static str,
+    pub(crate) status: MissionStatus,
+    pub(crate) held: bool,
+    pub(crate) priority: u8,
+    pub(crate) spatial_index: Option<usize>,
+    //frontier tiles of an explorer, tiles still to pave of a road builder
+    pub(crate) remaining: Option<usize>,
+}
+
+//changes to the queue asked by the user, applied by Jerry before the next mission runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MissionCommand {
+    Pause(usize),
+    Resume(usize),
+    Cancel(usize),
+    SetPriority(usize, u8),
+    MoveToFront(usize),
+}
+
+impl MissionCommand {
+    fn id(&self) -> usize {
+        match *self {
+            MissionCommand::Pause(id)
+            | MissionCommand::Resume(id)
+            | MissionCommand::Cancel(id)
+            | MissionCommand::SetPriority(id, _)
+            | MissionCommand::MoveToFront(id) => id,
+        }
+    }
+}
+
+//gives an id to the missions that were added since the last call, returns the next free id
+pub(crate) fn assign_ids(missions: &mut VecDeque<Mission>, mut next_id: usize) -> usize {
+    for mission in missions.iter_mut().filter(|mission| mission.id.is_none()) {
+        mission.id = Some(next_id);
+        next_id += 1;
+    }
+    next_id
+}
+
+pub(crate) fn summarize(missions: &VecDeque<Mission>) -> Vec<MissionSummary> {
+    missions
+        .iter()
+        .filter_map(|mission| {
+            let (spatial_index, remaining) = match (mission.explorer(), mission.road_builder(), mission.region()) {
+                (Some(explorer), _, _) => (Some(explorer.spatial_index), Some(explorer.frontier.len())),
+                (_, Some(road_builder), _) => (None, Some(road_builder.to_pave().len())),
+                (_, _, Some(region)) => (Some(region.spatial_index), None),
+                _ => (None, None),
+            };
+            Some(MissionSummary {
+                id: mission.id?,
+                kind: mission.kind().name(),
+                status: mission.status,
+                held: mission.is_held(),
+                priority: mission.priority,
+                spatial_index,
+                remaining,
+            })
+        })
+        .collect()
+}
+
+//false if the mission is not in the queue anymore or the command makes no sense for it
+pub(crate) fn apply_command(missions: &mut VecDeque<Mission>, command: MissionCommand) -> bool {
+    let Some(index) = missions.iter().position(|mission| mission.id == Some(command.id())) else {
+        return false;
+    };
+    match command {
+        MissionCommand::Pause(_) => {
+            let mission = &mut missions[index];
+            if mission.is_held() || mission.status.is_finished() {
+                return false;
+            }
+            mission.held = Some(mission.status);
+            mission.status = MissionStatus::Paused;
+        }
+        MissionCommand::Resume(_) => {
+            let mission = &mut missions[index];
+            let Some(status) = mission.held.take() else {
+                return false;
+            };
+            mission.status = status;
+        }
+        MissionCommand::Cancel(_) => {
+            missions.remove(index);
+        }
+        MissionCommand::SetPriority(_, priority) => {
+            missions[index].priority = priority;
+        }
+        MissionCommand::MoveToFront(_) => {
+            if let Some(mission) = missions.remove(index) {
+                missions.push_front(mission);
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::road_builder::new_road_builder;
+    use crate::sector_analyzer::new_sector_analyzer;
+    use charting_tools::charted_coordinate::ChartedCoordinate;
+
+    fn queue() -> VecDeque<Mission> {
+        let mut missions = VecDeque::from([
+            new_sector_analyzer(0, 50),
+            new_road_builder(&vec![ChartedCoordinate(1, 1), ChartedCoordinate(1, 2)]),
+        ]);
+        assert_eq!(assign_ids(&mut missions, 1), 3);
+        missions
+    }
+
+    #[test]
+    fn test_summarize(){
+        let missions = queue();
+        let summaries = summarize(&missions);
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].id, 1);
+        assert_eq!(summaries[0].spatial_index, Some(0));
+        assert_eq!(summaries[1].kind, missions[1].kind().name());
+        assert_eq!(summaries[1].remaining, Some(2));
+    }
+
+    #[test]
+    fn test_pause_and_resume(){
+        let mut missions = queue();
+        assert!(apply_command(&mut missions, MissionCommand::Pause(1)));
+        assert_eq!(missions[0].status, MissionStatus::Paused);
+        assert!(missions[0].is_held());
+        assert!(!apply_command(&mut missions, MissionCommand::Pause(1)));
+
+        assert!(apply_command(&mut missions, MissionCommand::Resume(1)));
+        assert_eq!(missions[0].status, MissionStatus::Active);
+        assert!(!apply_command(&mut missions, MissionCommand::Resume(1)));
+    }
+
+    #[test]
+    fn test_cancel_and_reorder(){
+        let mut missions = queue();
+        assert!(apply_command(&mut missions, MissionCommand::MoveToFront(2)));
+        assert_eq!(missions[0].id, Some(2));
+        assert!(apply_command(&mut missions, MissionCommand::SetPriority(2, 9)));
+        assert_eq!(missions[0].priority, 9);
+        assert!(apply_command(&mut missions, MissionCommand::Cancel(2)));
+        assert_eq!(missions.len(), 1);
+        //the id is not reused
+        assert!(!apply_command(&mut missions, MissionCommand::Cancel(2)));
+        assert_eq!(assign_ids(&mut missions, 3), 3);
+    }
+}
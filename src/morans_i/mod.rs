@@ -1,6 +1,7 @@
 use robotics_lib::world::tile::Tile;
 use robotics_lib::world::tile::{Content};
 use rand::Rng;
+use crate::logging;
 
 /*
     Moran's I is a measure of spatial autocorrelation
@@ -15,7 +16,7 @@ use rand::Rng;
                     and the mean value of the spatial units
 
     Values for content types (only the ones that the robot can destroy) are as follows:
-    None (and those that cannot be destroyed) -> random value between 1 and 9
+    None (and those that cannot be destroyed) -> random value between 1 and 9, taken from the given rng
     Rock -> 1
     Tree -> 2
     Water -> 3
@@ -26,7 +27,7 @@ use rand::Rng;
     Bush -> 8
     JollyBlock -> 9
  */
-pub fn morans_i(sector: &Vec<Vec<Option<Tile>>>) -> f64{
+pub fn morans_i(sector: &Vec<Vec<Option<Tile>>>, rng: &mut impl Rng) -> f64{
     let n = (sector.len()*sector.len()) as f64;
     let w = get_w(sector.len()) as f64;
     let mean = 4.5;
@@ -35,30 +36,30 @@ pub fn morans_i(sector: &Vec<Vec<Option<Tile>>>) -> f64{
     for (i, row) in sector.iter().enumerate(){
         for (j, tile) in row.iter().enumerate(){
 
-            let value = get_content_value_morans(tile);
+            let value = get_content_value_morans(tile, rng);
             if i > 0{
-                enumerator_sum += (value - mean)*(get_content_value_morans(&sector[i-1][j]) - mean);
+                enumerator_sum += (value - mean)*(get_content_value_morans(&sector[i-1][j], rng) - mean);
             }
             if j > 0{
-                enumerator_sum += (value - mean)*(get_content_value_morans(&sector[i][j-1]) - mean);
+                enumerator_sum += (value - mean)*(get_content_value_morans(&sector[i][j-1], rng) - mean);
             }
             if i < sector.len() - 1{
-                enumerator_sum += (value - mean)*(get_content_value_morans(&sector[i + 1][j]) - mean);
+                enumerator_sum += (value - mean)*(get_content_value_morans(&sector[i + 1][j], rng) - mean);
             }
             if j < sector[0].len() - 1{
-                enumerator_sum += (value - mean)*(get_content_value_morans(&sector[i][j + 1]) - mean);
+                enumerator_sum += (value - mean)*(get_content_value_morans(&sector[i][j + 1], rng) - mean);
             }
 
             denominator_sum += (value - mean) * (value - mean);
         }
     }
     let i = n/(w*denominator_sum)*enumerator_sum;
-    println!("{}", i);
+    log::trace!(target: logging::SECTOR_ANALYZER, "Moran's I {}", i);
     i
 }
-pub fn get_content_value_morans(tile: &Option<Tile>) -> f64{
+pub fn get_content_value_morans(tile: &Option<Tile>, rng: &mut impl Rng) -> f64{
     if tile.is_none(){
-        return rand::thread_rng().gen_range(1..10) as f64;
+        return rng.gen_range(1..10) as f64;
     }
     let content = tile.as_ref().unwrap().content.to_default();
     match content{
@@ -71,7 +72,7 @@ pub fn get_content_value_morans(tile: &Option<Tile>) -> f64{
         Content::Fish(_) => 7.,
         Content::Bush(_) => 8.,
         Content::JollyBlock(_) => 9.,
-        _ => rand::thread_rng().gen_range(1..10) as f64,
+        _ => rng.gen_range(1..10) as f64,
     }
 }
 pub fn get_w(n:usize) -> usize{
@@ -82,6 +83,8 @@ pub fn get_w(n:usize) -> usize{
 }
 #[cfg(test)]
 mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
     use robotics_lib::world::tile::TileType;
 
     use super::*;
@@ -145,7 +148,18 @@ mod tests {
                 Some(Tile{tile_type: TileType::Grass, content: Content::Garbage(1), elevation: 0}),
             ],
         ];
-        println!("{}", morans_i(&sector));
+        println!("{}", morans_i(&sector, &mut StdRng::seed_from_u64(0)));
+    }
+    #[test]
+    fn test_morans_i_same_seed(){
+        let sector: Vec<Vec<Option<Tile>>> = vec![
+            vec![None, Some(Tile{tile_type: TileType::Grass, content: Content::Rock(1), elevation: 0}), None],
+            vec![None, None, Some(Tile{tile_type: TileType::Grass, content: Content::None, elevation: 0})],
+            vec![Some(Tile{tile_type: TileType::Grass, content: Content::Tree(1), elevation: 0}), None, None],
+        ];
+        let first = morans_i(&sector, &mut StdRng::seed_from_u64(7));
+        let second = morans_i(&sector, &mut StdRng::seed_from_u64(7));
+        assert_eq!(first, second);
     }
 }
 
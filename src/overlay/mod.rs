@@ -0,0 +1,135 @@
+use crate::fast_paths::path_to_coordinates;
+use crate::jerry::Jerry;
+use crate::utils::get_tl_and_br_from_spatial_index;
+use crate::utils::SECTOR_DIMENSION;
+
+//the internal state of the AI that can be drawn on top of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum OverlayLayer {
+    Frontier,
+    Sectors,
+    Roads,
+    PlannedRoads,
+    PavedRoads,
+    SectorNodes,
+    Path,
+}
+
+impl OverlayLayer {
+    pub(crate) const ALL: [OverlayLayer; 7] = [
+        OverlayLayer::Frontier,
+        OverlayLayer::Sectors,
+        OverlayLayer::Roads,
+        OverlayLayer::PlannedRoads,
+        OverlayLayer::PavedRoads,
+        OverlayLayer::SectorNodes,
+        OverlayLayer::Path,
+    ];
+
+    pub(crate) fn label(&self) -> &'static str {
+        match self {
+            OverlayLayer::Frontier => "Frontier",
+            OverlayLayer::Sectors => "Sectors",
+            OverlayLayer::Roads => "Roads",
+            OverlayLayer::PlannedRoads => "Planned roads",
+            OverlayLayer::PavedRoads => "Paved roads",
+            OverlayLayer::SectorNodes => "Sector nodes",
+            OverlayLayer::Path => "Path",
+        }
+    }
+}
+
+/*
+    What the AI is working with in a given tick, as plain coordinates.
+    Frontier and roads come from every mission of that kind, the sector nodes from the
+    last analyzed sector and the path is the last one the robot followed.
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct AiOverlay {
+    pub(crate) frontier: Vec<(usize, usize)>,
+    pub(crate) active_sector: usize,
+    pub(crate) road_tiles: Vec<(usize, usize)>,
+    pub(crate) to_pave: Vec<(usize, usize)>,
+    pub(crate) paved: Vec<(usize, usize)>,
+    pub(crate) sector_nodes: Vec<(usize, usize)>,
+    pub(crate) path: Vec<(usize, usize)>,
+}
+
+pub(crate) fn collect_overlay(jerry: &Jerry) -> AiOverlay {
+    let mut overlay = AiOverlay {
+        active_sector: jerry.active_region.spatial_index,
+        road_tiles: jerry.road_tiles.iter().map(|tile| (tile.0, tile.1)).collect(),
+        sector_nodes: jerry.sector_nodes.clone(),
+        ..AiOverlay::default()
+    };
+    for mission in jerry.missions.iter() {
+        if let Some(explorer) = mission.explorer() {
+            overlay.frontier.extend(explorer.frontier.iter().map(|tile| (tile.0, tile.1)));
+        }
+        if let Some(road_builder) = mission.road_builder() {
+            overlay.to_pave.extend(road_builder.to_pave().iter().map(|tile| (tile.0, tile.1)));
+            overlay.paved.extend(road_builder.paved().iter().map(|tile| (tile.0, tile.1)));
+        }
+    }
+    if let Some(path) = &jerry.current_path {
+        overlay.path.push(path.source);
+        overlay.path.extend(path_to_coordinates(path).iter().map(|tile| (tile.0, tile.1)));
+    }
+    overlay
+}
+
+//top left and bottom right corner of every sector of a world of the given size
+pub(crate) fn sector_bounds(size: usize) -> Vec<((usize, usize), (usize, usize))> {
+    if size == 0 {
+        return Vec::new();
+    }
+    let per_side = (size as f64 / SECTOR_DIMENSION as f64).ceil() as usize;
+    (0..per_side * per_side)
+        .map(|spatial_index| get_tl_and_br_from_spatial_index(spatial_index, size))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explorer::ExplorerData;
+    use crate::fast_paths::{Action, Path};
+    use crate::road_builder::new_road_builder;
+    use crate::scheduler::SchedulerPolicy;
+    use crate::sink::NoopSink;
+    use crate::utils::{Mission, MissionData, MissionStatus};
+    use charting_tools::charted_coordinate::ChartedCoordinate;
+    use robotics_lib::interface::Direction;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_collect_overlay(){
+        let mut jerry = Jerry::new(Box::new(NoopSink), SchedulerPolicy::Alternating.build(), 0);
+        jerry.missions.push_back(Mission::new(MissionStatus::Active, MissionData::Explorer(ExplorerData {
+            frontier: vec![ChartedCoordinate(1, 2)],
+            frontier_hs: HashSet::from([ChartedCoordinate(1, 2)]),
+            spatial_index: 0,
+            robot_moved: false,
+        })));
+        jerry.missions.push_back(new_road_builder(&vec![ChartedCoordinate(3, 3)]));
+        jerry.road_tiles.insert(ChartedCoordinate(4, 4));
+        let mut path = Path::new((0, 0), (0, 1), 3);
+        path.actions.push_back(Action::Go(Direction::Right));
+        jerry.current_path = Some(path);
+
+        let overlay = collect_overlay(&jerry);
+        assert_eq!(overlay.frontier, vec![(1, 2)]);
+        assert_eq!(overlay.to_pave, vec![(3, 3)]);
+        assert!(overlay.paved.is_empty());
+        assert_eq!(overlay.road_tiles, vec![(4, 4)]);
+        assert_eq!(overlay.path, vec![(0, 0), (0, 1)]);
+    }
+
+    #[test]
+    fn test_sector_bounds(){
+        assert_eq!(sector_bounds(50), vec![((0, 0), (49, 49))]);
+        let bounds = sector_bounds(80);
+        assert_eq!(bounds.len(), 4);
+        assert_eq!(bounds[3], ((70, 70), (79, 79)));
+    }
+}
@@ -0,0 +1,309 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
+use robotics_lib::world::tile::TileType::{
+    DeepWater, Grass, Hill, Mountain, Sand, ShallowWater, Snow, Teleport,
+};
+use robotics_lib::world::tile::{Content, Tile, TileType};
+use robotics_lib::world::world_generator::Generator;
+use std::collections::HashMap;
+
+use crate::snapshot::SavedWorld;
+use crate::worldloader::GeneratedWorld;
+
+/*
+    Procedural world generator.
+    Elevation comes from seeded value noise and decides the tile type, then every tile type
+    gets the content that makes sense for it. The same config always generates the same world.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProceduralConfig {
+    pub seed: u64,
+    pub size: usize,
+    pub weather: Vec<WeatherType>,
+    pub teleports: usize,
+    pub markets: usize,
+    pub banks: usize,
+    pub max_score: f32,
+}
+
+impl Default for ProceduralConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            size: 100,
+            weather: vec![
+                WeatherType::Sunny,
+                WeatherType::Sunny,
+                WeatherType::Foggy,
+                WeatherType::Rainy,
+                WeatherType::Sunny,
+                WeatherType::TrentinoSnow,
+            ],
+            teleports: 4,
+            markets: 3,
+            banks: 3,
+            max_score: 100.0,
+        }
+    }
+}
+
+pub struct ProceduralGenerator {
+    config: ProceduralConfig,
+    score_map: Option<HashMap<Content, f32>>,
+}
+
+// distance between the noise lattice points, bigger means larger lakes and mountains
+const NOISE_SCALE: f64 = 16.0;
+const NOISE_OCTAVES: u32 = 3;
+const MAX_ELEVATION: f64 = 20.0;
+
+impl ProceduralGenerator {
+    pub fn new(config: ProceduralConfig) -> Self {
+        Self { config, score_map: None }
+    }
+    pub fn set_score_hashmap(&mut self, score_map: HashMap<Content, f32>) {
+        self.score_map = Some(score_map);
+    }
+
+    // the world as it is at the start, to be saved in a snapshot
+    pub(crate) fn saved_world(&self) -> SavedWorld {
+        let (tiles, _, _, max_score, score_map) = self.generate();
+        SavedWorld {
+            tiles,
+            max_score,
+            score_map,
+        }
+    }
+
+    fn weather(&self) -> Vec<WeatherType> {
+        if self.config.weather.is_empty() {
+            vec![WeatherType::Sunny]
+        } else {
+            self.config.weather.clone()
+        }
+    }
+
+    fn generate(&self) -> GeneratedWorld {
+        let size = self.config.size.max(2);
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+
+        let elevation = fractal_noise(&mut rng, size);
+        let mut world: Vec<Vec<Tile>> = elevation
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|height| {
+                        let tile_type = tile_type_for(*height);
+                        Tile {
+                            tile_type,
+                            elevation: match tile_type {
+                                DeepWater | ShallowWater => 0,
+                                _ => (height * MAX_ELEVATION) as usize,
+                            },
+                            content: Content::None,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for row in world.iter_mut() {
+            for tile in row.iter_mut() {
+                tile.content = biome_content(&mut rng, tile.tile_type);
+            }
+        }
+
+        place_special(&mut rng, &mut world, self.config.markets, |_| Content::Market(20));
+        place_special(&mut rng, &mut world, self.config.banks, |rng| Content::Bank(0..rng.gen_range(20..50)));
+        for _ in 0..self.config.teleports {
+            if let Some((row, col)) = free_land_tile(&mut rng, &world, &Content::None) {
+                world[row][col].tile_type = Teleport(false);
+            }
+        }
+        let robot_pos = free_land_tile(&mut rng, &world, &Content::None).unwrap_or((0, 0));
+
+        let conditions = EnvironmentalConditions::new(&self.weather(), 15, 12).unwrap();
+
+        (world, robot_pos, conditions, self.config.max_score, self.score_map.clone())
+    }
+}
+
+impl Generator for ProceduralGenerator {
+    fn gen(&mut self) -> GeneratedWorld {
+        self.generate()
+    }
+}
+
+//value noise on a lattice, summed over a few octaves, normalised in 0..1
+fn fractal_noise(rng: &mut StdRng, size: usize) -> Vec<Vec<f64>> {
+    let mut out = vec![vec![0.0; size]; size];
+    let mut amplitude = 1.0;
+    let mut total_amplitude = 0.0;
+    for octave in 0..NOISE_OCTAVES {
+        let scale = NOISE_SCALE / 2f64.powi(octave as i32);
+        let lattice_size = (size as f64 / scale).ceil() as usize + 2;
+        let lattice: Vec<Vec<f64>> = (0..lattice_size)
+            .map(|_| (0..lattice_size).map(|_| rng.gen::<f64>()).collect())
+            .collect();
+        for (i, row) in out.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value += amplitude * sample(&lattice, i as f64 / scale, j as f64 / scale);
+            }
+        }
+        total_amplitude += amplitude;
+        amplitude /= 2.0;
+    }
+    for value in out.iter_mut().flatten() {
+        *value /= total_amplitude;
+    }
+    out
+}
+
+fn sample(lattice: &Vec<Vec<f64>>, x: f64, y: f64) -> f64 {
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (tx, ty) = (smoothstep(x - x0 as f64), smoothstep(y - y0 as f64));
+    let top = lerp(lattice[x0][y0], lattice[x0][y0 + 1], ty);
+    let bottom = lerp(lattice[x0 + 1][y0], lattice[x0 + 1][y0 + 1], ty);
+    lerp(top, bottom, tx)
+}
+
+fn smoothstep(t: f64) -> f64 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+fn tile_type_for(height: f64) -> TileType {
+    match height {
+        h if h < 0.30 => DeepWater,
+        h if h < 0.38 => ShallowWater,
+        h if h < 0.42 => Sand,
+        h if h < 0.58 => Grass,
+        h if h < 0.66 => Hill,
+        h if h < 0.74 => Mountain,
+        _ => Snow,
+    }
+}
+
+//content that fits the tile type, with the chance of finding it
+fn biome_content(rng: &mut StdRng, tile_type: TileType) -> Content {
+    let candidates: &[(Content, f64)] = match tile_type {
+        DeepWater => &[(Content::Fish(0), 0.04)],
+        ShallowWater => &[(Content::Fish(0), 0.03), (Content::Garbage(0), 0.01)],
+        Sand => &[(Content::Rock(0), 0.03), (Content::Garbage(0), 0.03), (Content::Bin(0..0), 0.01)],
+        Grass => &[
+            (Content::Tree(0), 0.08),
+            (Content::Bush(0), 0.05),
+            (Content::Rock(0), 0.02),
+            (Content::Garbage(0), 0.02),
+            (Content::Coin(0), 0.01),
+            (Content::Crate(0..0), 0.005),
+            (Content::Bin(0..0), 0.005),
+        ],
+        Hill => &[(Content::Tree(0), 0.05), (Content::Rock(0), 0.06), (Content::Coin(0), 0.01)],
+        Mountain => &[(Content::Rock(0), 0.10)],
+        Snow => &[(Content::Rock(0), 0.02)],
+        _ => &[],
+    };
+    let roll = rng.gen::<f64>();
+    let mut threshold = 0.0;
+    for (content, chance) in candidates {
+        threshold += chance;
+        if roll < threshold {
+            let content = with_amount(rng, content);
+            if tile_type.properties().can_hold(&content) {
+                return content;
+            }
+            break;
+        }
+    }
+    Content::None
+}
+
+fn with_amount(rng: &mut StdRng, content: &Content) -> Content {
+    match content {
+        Content::Rock(_) => Content::Rock(rng.gen_range(1..=3)),
+        Content::Tree(_) => Content::Tree(rng.gen_range(1..=4)),
+        Content::Garbage(_) => Content::Garbage(rng.gen_range(1..=2)),
+        Content::Coin(_) => Content::Coin(rng.gen_range(1..=5)),
+        Content::Fish(_) => Content::Fish(rng.gen_range(1..=3)),
+        Content::Bush(_) => Content::Bush(rng.gen_range(1..=2)),
+        Content::Bin(_) => Content::Bin(0..rng.gen_range(5..15)),
+        Content::Crate(_) => Content::Crate(0..rng.gen_range(5..15)),
+        other => other.clone(),
+    }
+}
+
+fn place_special(
+    rng: &mut StdRng,
+    world: &mut Vec<Vec<Tile>>,
+    amount: usize,
+    content: impl Fn(&mut StdRng) -> Content,
+) {
+    for _ in 0..amount {
+        let content = content(rng);
+        if let Some((row, col)) = free_land_tile(rng, world, &content) {
+            world[row][col].content = content;
+        }
+    }
+}
+
+//random land tile without content that can hold the given one, gives up after a while on worlds made of water
+fn free_land_tile(rng: &mut StdRng, world: &Vec<Vec<Tile>>, content: &Content) -> Option<(usize, usize)> {
+    let size = world.len();
+    for _ in 0..size * size {
+        let (row, col) = (rng.gen_range(0..size), rng.gen_range(0..size));
+        let tile = &world[row][col];
+        if matches!(tile.tile_type, Sand | Grass | Hill)
+            && tile.content == Content::None
+            && tile.tile_type.properties().can_hold(content)
+        {
+            return Some((row, col));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate(config: ProceduralConfig) -> GeneratedWorld {
+        ProceduralGenerator::new(config).gen()
+    }
+
+    #[test]
+    fn test_same_seed_same_world(){
+        let config = ProceduralConfig { seed: 42, size: 40, ..Default::default() };
+        let first = generate(config.clone());
+        let second = generate(config);
+        assert_eq!(first.0, second.0);
+        assert_eq!(first.1, second.1);
+    }
+
+    #[test]
+    fn test_different_seed_different_world(){
+        let first = generate(ProceduralConfig { seed: 1, size: 40, ..Default::default() });
+        let second = generate(ProceduralConfig { seed: 2, size: 40, ..Default::default() });
+        assert_ne!(first.0, second.0);
+    }
+
+    #[test]
+    fn test_world_layout(){
+        let (world, robot_pos, _, _, _) = generate(ProceduralConfig { seed: 7, size: 60, ..Default::default() });
+        assert_eq!(world.len(), 60);
+        assert!(world.iter().all(|row| row.len() == 60));
+        assert!(world[robot_pos.0][robot_pos.1].tile_type.properties().walk());
+
+        let teleports = world.iter().flatten().filter(|tile| tile.tile_type == Teleport(false)).count();
+        let markets = world.iter().flatten().filter(|tile| matches!(tile.content, Content::Market(_))).count();
+        assert_eq!(teleports, 4);
+        assert_eq!(markets, 3);
+        for tile in world.iter().flatten() {
+            assert!(tile.tile_type.properties().can_hold(&tile.content));
+        }
+    }
+}
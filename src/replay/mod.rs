@@ -0,0 +1,391 @@
+use robotics_lib::event::events::Event;
+use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
+use robotics_lib::world::tile::{Content, Tile};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::inspector::TileReport;
+use crate::map_tracker::MapDiff;
+use crate::mission_control::{MissionCommand, MissionSummary};
+use crate::overlay::AiOverlay;
+use crate::sink::StateSink;
+use crate::snapshot::RobotSnapshot;
+use crate::telemetry::TelemetrySample;
+
+/*
+    Replay of a simulation.
+    Every tick becomes a frame with the events received during the tick, the tiles of the
+    robot map that changed and the state updates. Playing the frames back in order rebuilds
+    what the frontend showed, without running the AI again.
+ */
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub(crate) struct ReplayFrame {
+    pub(crate) tick: usize,
+    pub(crate) events: Vec<ReplayEvent>,
+    pub(crate) map_diff: MapDiff,
+    pub(crate) robot: Option<(usize, usize)>,
+    pub(crate) energy: Option<usize>,
+    pub(crate) conditions: Option<(WeatherType, String)>,
+    pub(crate) backpack: Option<(usize, HashMap<Content, usize>)>,
+    pub(crate) score: Option<f32>,
+}
+
+//copy of the robot events that can be saved, the one of the library cannot be serialized
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) enum ReplayEvent {
+    Ready,
+    Terminated,
+    TimeChanged(WeatherType, String),
+    DayChanged(WeatherType, String),
+    EnergyRecharged(usize),
+    EnergyConsumed(usize),
+    Moved(Tile, (usize, usize)),
+    TileContentUpdated(Tile, (usize, usize)),
+    AddedToBackpack(Content, usize),
+    RemovedFromBackpack(Content, usize),
+}
+
+impl From<&Event> for ReplayEvent {
+    fn from(event: &Event) -> Self {
+        match event {
+            Event::Ready => ReplayEvent::Ready,
+            Event::Terminated => ReplayEvent::Terminated,
+            Event::TimeChanged(conditions) => ReplayEvent::TimeChanged(conditions.get_weather_condition(), conditions.get_time_of_day_string()),
+            Event::DayChanged(conditions) => ReplayEvent::DayChanged(conditions.get_weather_condition(), conditions.get_time_of_day_string()),
+            Event::EnergyRecharged(amount) => ReplayEvent::EnergyRecharged(*amount),
+            Event::EnergyConsumed(amount) => ReplayEvent::EnergyConsumed(*amount),
+            Event::Moved(tile, position) => ReplayEvent::Moved(tile.clone(), *position),
+            Event::TileContentUpdated(tile, position) => ReplayEvent::TileContentUpdated(tile.clone(), *position),
+            Event::AddedToBackpack(content, amount) => ReplayEvent::AddedToBackpack(content.clone(), *amount),
+            Event::RemovedFromBackpack(content, amount) => ReplayEvent::RemovedFromBackpack(content.clone(), *amount),
+        }
+    }
+}
+
+// frames kept in memory, a long run folds the oldest ones into the keyframe
+const REPLAY_CAPACITY: usize = 20000;
+// frames folded at once, so the frames are not moved at every tick
+const REPLAY_FOLD: usize = 2000;
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub(crate) struct Replay {
+    pub(crate) size: usize,
+    // state after the frames that were folded, None if the replay starts at the first tick
+    pub(crate) keyframe: Option<ReplayCursor>,
+    pub(crate) frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    //frames recorded, the folded ones included
+    pub(crate) fn len(&self) -> usize {
+        self.first() + self.frames.len()
+    }
+    //the first position the replay can still go back to
+    pub(crate) fn first(&self) -> usize {
+        self.keyframe.as_ref().map_or(0, |keyframe| keyframe.position)
+    }
+    pub(crate) fn push(&mut self, frame: ReplayFrame) {
+        self.push_with_capacity(frame, REPLAY_CAPACITY, REPLAY_FOLD);
+    }
+    fn push_with_capacity(&mut self, frame: ReplayFrame, capacity: usize, fold: usize) {
+        self.frames.push(frame);
+        if self.frames.len() <= capacity {
+            return;
+        }
+        let mut keyframe = self.keyframe.take().unwrap_or_else(|| ReplayCursor::new(self.size));
+        for frame in self.frames.drain(..fold.clamp(1, self.frames.len())) {
+            keyframe.apply(&frame);
+        }
+        self.keyframe = Some(keyframe);
+    }
+    fn frame(&self, position: usize) -> Option<&ReplayFrame> {
+        self.frames.get(position.checked_sub(self.first())?)
+    }
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        bincode::serialize(self).map_err(|e| e.to_string())
+    }
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Replay, String> {
+        bincode::deserialize(bytes).map_err(|e| e.to_string())
+    }
+}
+
+/*
+    Sink that records a replay and forwards everything to another sink.
+    The replay is shared so the frontend can read it while the robot is running.
+ */
+pub(crate) struct ReplaySink {
+    inner: Box<dyn StateSink>,
+    replay: Rc<RefCell<Replay>>,
+    frame: ReplayFrame,
+    // last values recorded, the frames only keep the ones that changed
+    conditions: Option<(WeatherType, String)>,
+    score: Option<f32>,
+}
+
+impl ReplaySink {
+    pub(crate) fn new(inner: Box<dyn StateSink>, replay: Rc<RefCell<Replay>>) -> Self {
+        Self {
+            inner,
+            replay,
+            frame: ReplayFrame::default(),
+            conditions: None,
+            score: None,
+        }
+    }
+}
+
+impl StateSink for ReplaySink {
+    fn map_changed(&mut self, size: usize, diff: &MapDiff) {
+        self.frame.map_diff.extend(diff.iter().cloned());
+        self.replay.borrow_mut().size = size;
+        self.inner.map_changed(size, diff);
+    }
+    fn robot_moved(&mut self, coord: (usize, usize)) {
+        self.frame.robot = Some(coord);
+        self.inner.robot_moved(coord);
+    }
+    fn energy_changed(&mut self, energy: usize) {
+        self.frame.energy = Some(energy);
+        self.inner.energy_changed(energy);
+    }
+    fn backpack_changed(&mut self, size: usize, content: &HashMap<Content, usize>) {
+        self.frame.backpack = Some((size, content.clone()));
+        self.inner.backpack_changed(size, content);
+    }
+    fn conditions_changed(&mut self, conditions: &EnvironmentalConditions) {
+        let recorded = Some((conditions.get_weather_condition(), conditions.get_time_of_day_string()));
+        if recorded != self.conditions {
+            self.frame.conditions = recorded.clone();
+            self.conditions = recorded;
+        }
+        self.inner.conditions_changed(conditions);
+    }
+    fn score_changed(&mut self, score: f32) {
+        if self.score != Some(score) {
+            self.frame.score = Some(score);
+            self.score = Some(score);
+        }
+        self.inner.score_changed(score);
+    }
+    fn event_received(&mut self, event: &Event) {
+        self.frame.events.push(ReplayEvent::from(event));
+        self.inner.event_received(event);
+    }
+    fn telemetry_sampled(&mut self, sample: &TelemetrySample) {
+        self.inner.telemetry_sampled(sample);
+    }
+    fn missions_changed(&mut self, missions: &[MissionSummary]) {
+        self.inner.missions_changed(missions);
+    }
+    fn mission_commands(&mut self) -> Vec<MissionCommand> {
+        self.inner.mission_commands()
+    }
+    fn tick_finished(&mut self, tick: usize) {
+        let mut frame = std::mem::take(&mut self.frame);
+        frame.tick = tick;
+        self.replay.borrow_mut().push(frame);
+        self.inner.tick_finished(tick);
+    }
+    fn wants_snapshot(&mut self) -> bool {
+        self.inner.wants_snapshot()
+    }
+    fn snapshot_taken(&mut self, snapshot: RobotSnapshot) {
+        self.inner.snapshot_taken(snapshot);
+    }
+    fn wants_overlay(&mut self) -> bool {
+        self.inner.wants_overlay()
+    }
+    fn overlay_changed(&mut self, overlay: AiOverlay) {
+        self.inner.overlay_changed(overlay);
+    }
+    fn inspected_tile(&mut self) -> Option<(usize, usize)> {
+        self.inner.inspected_tile()
+    }
+    fn tile_inspected(&mut self, report: TileReport) {
+        self.inner.tile_inspected(report);
+    }
+}
+
+/*
+    State rebuilt from the first `position` frames of a replay.
+    Going forward applies the next frames, going back starts over from the keyframe.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct ReplayCursor {
+    pub(crate) position: usize,
+    pub(crate) map: Vec<Vec<Option<Tile>>>,
+    pub(crate) robot: (usize, usize),
+    pub(crate) energy: usize,
+    pub(crate) conditions: Option<(WeatherType, String)>,
+    pub(crate) backpack: (usize, HashMap<Content, usize>),
+    pub(crate) score: f32,
+    pub(crate) events: Vec<ReplayEvent>,
+}
+
+impl ReplayCursor {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            position: 0,
+            map: vec![vec![None; size]; size],
+            robot: (0, 0),
+            energy: 0,
+            conditions: None,
+            backpack: (0, HashMap::new()),
+            score: 0.0,
+            events: Vec::new(),
+        }
+    }
+    fn apply(&mut self, frame: &ReplayFrame) {
+        for (row, col, tile) in frame.map_diff.iter() {
+            if let Some(cell) = self.map.get_mut(*row).and_then(|map_row| map_row.get_mut(*col)) {
+                *cell = tile.clone();
+            }
+        }
+        if let Some(robot) = frame.robot {
+            self.robot = robot;
+        }
+        if let Some(energy) = frame.energy {
+            self.energy = energy;
+        }
+        if let Some(conditions) = &frame.conditions {
+            self.conditions = Some(conditions.clone());
+        }
+        if let Some(backpack) = &frame.backpack {
+            self.backpack = backpack.clone();
+        }
+        if let Some(score) = frame.score {
+            self.score = score;
+        }
+        self.events = frame.events.clone();
+        self.position += 1;
+    }
+    pub(crate) fn step(&mut self, replay: &Replay) -> bool {
+        match replay.frame(self.position) {
+            Some(frame) => {
+                self.apply(frame);
+                true
+            }
+            None => false,
+        }
+    }
+    pub(crate) fn seek(&mut self, replay: &Replay, position: usize) {
+        if position < self.position || self.position < replay.first() || self.map.len() != replay.size {
+            *self = replay.keyframe.clone().unwrap_or_else(|| ReplayCursor::new(replay.size));
+        }
+        while self.position < position && self.step(replay) {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::NoopSink;
+    use robotics_lib::world::tile::TileType;
+
+    fn tile(content: Content) -> Option<Tile> {
+        Some(Tile { tile_type: TileType::Grass, content, elevation: 0 })
+    }
+
+    fn record() -> Replay {
+        let replay = Rc::new(RefCell::new(Replay::default()));
+        let mut sink = ReplaySink::new(Box::new(NoopSink), replay.clone());
+        sink.map_changed(2, &vec![(0, 0, tile(Content::None))]);
+        sink.robot_moved((0, 0));
+        sink.tick_finished(0);
+        sink.map_changed(2, &vec![(0, 1, tile(Content::Rock(1)))]);
+        sink.robot_moved((0, 1));
+        sink.energy_changed(990);
+        sink.event_received(&Event::EnergyConsumed(10));
+        sink.score_changed(2.0);
+        sink.tick_finished(1);
+        sink.score_changed(2.0);
+        sink.map_changed(2, &vec![(0, 1, tile(Content::None))]);
+        sink.tick_finished(2);
+        let recorded = replay.borrow().clone();
+        recorded
+    }
+
+    #[test]
+    fn test_recorded_frames(){
+        let replay = record();
+        assert_eq!(replay.size, 2);
+        assert_eq!(replay.len(), 3);
+        assert_eq!(replay.frames[1].map_diff, vec![(0, 1, tile(Content::Rock(1)))]);
+        assert_eq!(replay.frames[2].robot, None);
+        assert_eq!(replay.frames[1].events, vec![ReplayEvent::EnergyConsumed(10)]);
+        //the same score is only recorded once
+        assert_eq!(replay.frames[1].score, Some(2.0));
+        assert_eq!(replay.frames[2].score, None);
+    }
+
+    #[test]
+    fn test_conditions_recorded_when_they_change(){
+        let replay = Rc::new(RefCell::new(Replay::default()));
+        let mut sink = ReplaySink::new(Box::new(NoopSink), replay.clone());
+        let morning = EnvironmentalConditions::new(&[WeatherType::Sunny], 15, 8).unwrap();
+        let evening = EnvironmentalConditions::new(&[WeatherType::Sunny], 15, 20).unwrap();
+        for conditions in [&morning, &morning, &evening] {
+            sink.conditions_changed(conditions);
+            sink.tick_finished(0);
+        }
+        let frames = replay.borrow().frames.clone();
+        assert!(frames[0].conditions.is_some());
+        assert_eq!(frames[1].conditions, None);
+        assert_eq!(frames[2].conditions, Some((WeatherType::Sunny, evening.get_time_of_day_string())));
+    }
+
+    #[test]
+    fn test_cursor_seek(){
+        let replay = record();
+        let mut cursor = ReplayCursor::new(replay.size);
+        cursor.seek(&replay, 2);
+        assert_eq!(cursor.robot, (0, 1));
+        assert_eq!(cursor.energy, 990);
+        assert_eq!(cursor.map[0][1], tile(Content::Rock(1)));
+
+        cursor.seek(&replay, 3);
+        assert_eq!(cursor.map[0][1], tile(Content::None));
+
+        //going back rebuilds the state from the start
+        cursor.seek(&replay, 1);
+        assert_eq!(cursor.map[0][1], None);
+        assert_eq!(cursor.robot, (0, 0));
+        assert!(cursor.step(&replay));
+        assert_eq!(cursor.position, 2);
+    }
+
+    #[test]
+    fn test_old_frames_are_folded(){
+        let mut replay = Replay { size: 2, ..Default::default() };
+        for tick in 0..10 {
+            let frame = ReplayFrame { tick, robot: Some((0, tick % 2)), map_diff: vec![(0, tick % 2, tile(Content::Rock(tick)))], ..Default::default() };
+            replay.push_with_capacity(frame, 4, 3);
+        }
+        assert_eq!(replay.len(), 10);
+        assert!(replay.frames.len() <= 4);
+        assert_eq!(replay.first(), 10 - replay.frames.len());
+
+        //the state at the end is the same as without folding
+        let mut cursor = ReplayCursor::new(2);
+        cursor.seek(&replay, 10);
+        assert_eq!(cursor.position, 10);
+        assert_eq!(cursor.map[0][0], tile(Content::Rock(8)));
+        assert_eq!(cursor.map[0][1], tile(Content::Rock(9)));
+        assert_eq!(cursor.robot, (0, 1));
+
+        //the folded ticks cannot be reached anymore, the cursor stops at the keyframe
+        cursor.seek(&replay, 0);
+        assert_eq!(cursor.position, replay.first());
+        let bytes = replay.to_bytes().unwrap();
+        assert_eq!(Replay::from_bytes(&bytes).unwrap(), replay);
+    }
+
+    #[test]
+    fn test_replay_bytes(){
+        let replay = record();
+        assert_eq!(Replay::from_bytes(&replay.to_bytes().unwrap()).unwrap(), replay);
+    }
+}
@@ -1,6 +1,7 @@
 
 use std::collections::HashSet;
-use crate::interface::Jerry;
+use crate::jerry::Jerry;
+use crate::logging;
 use charting_tools::charted_coordinate::ChartedCoordinate;
 use robotics_lib::interface::{destroy, put, where_am_i, Direction};
 use robotics_lib::runner::Runnable;
@@ -12,17 +13,17 @@ use crate::resources::ResourceCollectorError::*;
 //collects a certain resource until the backpack is full
 pub fn get_content(jerry: &mut Jerry, world: &mut World, content: Content,
     planned_road: Option<&HashSet<ChartedCoordinate>>, desired_amount: usize) -> Result<usize, ResourceCollectorError> {
-   println!("Getting content");
+   log::debug!(target: logging::RESOURCES, "Getting content {:?}", content);
    if desired_amount > jerry.get_backpack().get_size(){
-       panic!("You want too fucking much");
+       return Err(TooMuchRequested);
    }
    //if there is not enough space in the backpack
    let occupied_space = jerry.get_backpack().get_contents().values().sum::<usize>();
    if desired_amount > jerry.get_backpack().get_size() - occupied_space{
-       println!("desired amount is {}, and occupied space is {}", desired_amount, occupied_space);
+       log::debug!(target: logging::RESOURCES, "desired amount is {}, and occupied space is {}", desired_amount, occupied_space);
        let space_needed = desired_amount - occupied_space;
        let _ = empty_the_backpack(jerry, world, planned_road, space_needed)?;
-       println!("{:?}", jerry.get_backpack().get_contents().values().sum::<usize>());
+       log::debug!(target: logging::RESOURCES, "Backpack holds {} after emptying", jerry.get_backpack().get_contents().values().sum::<usize>());
    }
    let amount_collected = go_get_content(jerry, world, content)?;
    Ok(amount_collected)
@@ -31,7 +32,7 @@ pub fn get_content(jerry: &mut Jerry, world: &mut World, content: Content,
 //disposes the content till the moment there's enough space in the backpack
 pub fn empty_the_backpack(jerry: &mut Jerry, world: &mut World,
    planned_road: Option<&HashSet<ChartedCoordinate>>, space_needed: usize) -> Result<(), ResourceCollectorError> {
-       println!("Emptying backpack, i inserted {}", space_needed);
+       log::info!(target: logging::RESOURCES, "Emptying backpack, {} free slots needed", space_needed);
        let mut contents: HashSet<Content> = HashSet::new();
        for (content, amount) in jerry.get_backpack_mut().get_contents().iter()
        {
@@ -45,40 +46,40 @@ pub fn empty_the_backpack(jerry: &mut Jerry, world: &mut World,
            let mut skipped_all = true;
            for content in contents.iter(){
                match go_dispose_content(jerry, world, content.to_default().clone(), planned_road){
-                   Ok(amount) => {println!("Disposed!"); disposed_content += amount; skipped_all = false},
-                   Err(NotEnoughEnergy) => {println!("NEE"); return Err(NotEnoughEnergy);},
+                   Ok(amount) => {log::debug!(target: logging::RESOURCES, "Disposed {} {:?}", amount, content); disposed_content += amount; skipped_all = false},
+                   Err(NotEnoughEnergy) => {log::warn!(target: logging::RESOURCES, "Not enough energy to dispose {:?}", content); return Err(NotEnoughEnergy);},
                    Err(NoWayToDispose) | Err(NoContentToDispose) | Err(PathNotFound) => continue,
-                   _ => panic!("Unexpected error"),
+                   Err(error) => return Err(error),
                }
            }
            if skipped_all{
                break;
            }
        }
-       println!("Backpack: {:?}", jerry.get_backpack());
+       log::trace!(target: logging::RESOURCES, "Backpack: {:?}", jerry.get_backpack());
        Ok(())
 }
 
 //goes to a tile with a certain content and collects it (not on the tile directly, but on adjacent tiles)
 //returns the amount of collected content
 pub fn go_get_content(jerry: &mut Jerry, world: &mut World, content: Content) -> Result<usize, ResourceCollectorError> {
-   println!("Going to get content");
+   log::debug!(target: logging::RESOURCES, "Going to get content {:?}", content);
    //this is for converting the content to a plain content
-   let plain_content = match_to_plain_content(content.clone());
+   let plain_content = match_to_plain_content(content.clone()).ok_or(NotCollectable)?;
    //check if can reach the resource
    //if not it's a pizdec and we panic (should not happen if i check the amount of rocks on the map) - return a special error which
    //will be handled by the caller
    if let Err(error) = 
        TomTom::go_to_tile(jerry, world, true, None, Some(plain_content)){
            if error == "Path not found!"{
-               println!("No path found to the resource {:?}", content);
+               log::warn!(target: logging::RESOURCES, "No path found to the resource {:?}", content);
                return Err(PathNotFound);
            }
            else if error == "Not enough energy!"{
                return Err(NotEnoughEnergy);
            }
            else{
-               panic!("Unexpected error: {}", error);
+               return Err(Navigation(error));
            }
        }
    //if the content is rock, we can also search for the mountain tile
@@ -86,14 +87,14 @@ pub fn go_get_content(jerry: &mut Jerry, world: &mut World, content: Content) ->
        if let Err(error) = 
        TomTom::go_to_tile(jerry, world, true, Some(PlainTileType::Mountain), None){
            if error == "Path not found!"{
-               println!("No path found to the resource {:?}", content);
+               log::warn!(target: logging::RESOURCES, "No path found to the resource {:?}", content);
                return Err(PathNotFound);
            }
            else if error == "Not enough energy!"{
                return Err(NotEnoughEnergy);
            }
            else{
-               panic!("Unexpected error: {}", error);
+               return Err(Navigation(error));
            }
        }
    }
@@ -113,7 +114,7 @@ pub fn go_get_content(jerry: &mut Jerry, world: &mut World, content: Content) ->
 //navigates to a closest suitable tile for disposing the resource (again not on the tile directly, but on adjacent tiles)
 //and tries to put it there
 pub fn go_dispose_content(jerry: &mut Jerry, world: &mut World, content: Content, planned_road: Option<&HashSet<ChartedCoordinate>>) -> Result<usize, ResourceCollectorError> {
-   println!("Going to dispose content");
+   log::debug!(target: logging::RESOURCES, "Going to dispose content {:?}", content);
    //get the tile types and contents that can hold the content
    let (tile_types, contents) = ways_to_dispose(content.clone());
    for tile_type in &tile_types{
@@ -122,40 +123,42 @@ pub fn go_dispose_content(jerry: &mut Jerry, world: &mut World, content: Content
        if let Err(error) = TomTom::go_to_tile
        (jerry, world, true, Some(plain_tile_type), Some(PlainContent::None)){
            if error == "Path not found!"{
-               println!("No path found to the resource");
+               log::warn!(target: logging::RESOURCES, "No path found to a place to dispose {:?}", content);
                return Err(PathNotFound);
            }
            else if error == "Not enough energy!"{
                return Err(NotEnoughEnergy);
            }
            else{
-               panic!("Unexpected error: {}", error);
+               return Err(Navigation(error));
            }
        }
        //if we have arrived to a tile of a certain type
        //we can dispose the resource
        let robot_view = where_am_i(jerry, world).0;
        let res =  dispose_around(jerry, world, &robot_view, planned_road, content.clone());
-       println!("{:?}", res);
+       log::debug!(target: logging::RESOURCES, "Disposing around: {:?}", res);
        return res;
    }
    for content in &contents{
-       let plain_content = match_to_plain_content(content.clone());
+       let Some(plain_content) = match_to_plain_content(content.clone()) else {
+           continue;
+       };
        if let Err(error) = TomTom::go_to_tile(jerry, world, true, None, Some(plain_content)){
            if error == "Path not found!"{
-               println!("No path found to the resource");
+               log::warn!(target: logging::RESOURCES, "No path found to a {:?} to dispose into", content);
                return Err(PathNotFound);
            }
            else if error == "Not enough energy!"{
                return Err(NotEnoughEnergy);
            }
            else{
-               panic!("Unexpected error: {}", error);
+               return Err(Navigation(error));
            }
        }
        let robot_view = where_am_i(jerry, world).0;
        let res =  dispose_around(jerry, world, &robot_view, planned_road, content.clone());
-       println!("{:?}", res);
+       log::debug!(target: logging::RESOURCES, "Disposing around: {:?}", res);
        return res;
    }
    //if we didn't find a suitable tile to dispose the content
@@ -163,7 +166,7 @@ pub fn go_dispose_content(jerry: &mut Jerry, world: &mut World, content: Content
 }
 
 pub fn get_content_around(jerry: &mut Jerry, view: &Vec<Vec<Option<Tile>>>, world: &mut World, content: Content) -> Result<usize, ResourceCollectorError>{
-   println!("Trying to get content around");
+   log::trace!(target: logging::RESOURCES, "Trying to get content around");
    if jerry.get_backpack().get_size() == jerry.get_backpack().get_contents().values().sum::<usize>(){
        return Err(BackPackIsFull);
    }
@@ -253,17 +256,16 @@ pub fn get_content_around(jerry: &mut Jerry, view: &Vec<Vec<Option<Tile>>>, worl
 */
 pub(crate) fn dispose_around(jerry: &mut Jerry, world: &mut World, robot_view: &Vec<Vec<Option<Tile>>>, 
    planned_road: Option<&HashSet<ChartedCoordinate>>, content: Content) -> Result<usize, ResourceCollectorError>{
-       println!("Trying to dispose content around");
+       log::trace!(target: logging::RESOURCES, "Trying to dispose content around");
    
    //total amount of disposed content
    let mut total_disposed = 0;
 
-   //check if the robot has the content to dispose
-   if jerry.get_backpack().get_contents().get(&content.to_default()).is_none(){
+   //amount of content in a backpack, if the robot has any
+   let Some(&amount) = jerry.get_backpack().get_contents().get(&content.to_default()) else {
        return Err(NoContentToDispose);
-   }
-   //amount of content in a backpack
-   let mut amount_to_dispose = *jerry.get_backpack().get_contents().get(&content.to_default()).unwrap();
+   };
+   let mut amount_to_dispose = amount;
    if amount_to_dispose == 0{
        return Err(NoContentToDispose);
    }
@@ -497,8 +499,9 @@ fn ways_to_dispose(content: Content) -> (HashSet<TileType>, HashSet<Content>){
    }
    (tile_types, contents)
 }
-fn match_to_plain_content(content: Content) -> PlainContent{
-   match content{
+//None if the TomTom tool cannot look for the content
+fn match_to_plain_content(content: Content) -> Option<PlainContent>{
+   let plain_content = match content{
        Content::Rock(_) => PlainContent::Rock,
        Content::Tree(_) => PlainContent::Tree,
        Content::Bush(_) => PlainContent::Bush,
@@ -508,8 +511,9 @@ fn match_to_plain_content(content: Content) -> PlainContent{
        Content::JollyBlock(_) => PlainContent::JollyBlock,
        Content::Fire => PlainContent::Fire,
        Content::Garbage(_) => PlainContent::Garbage,
-       _ => panic!("This content is not collectable")
-   }
+       _ => return None,
+   };
+   Some(plain_content)
 }
 fn match_to_plain_tile_type(tile_type: TileType) -> PlainTileType{
    match tile_type{
@@ -526,7 +530,7 @@ fn match_to_plain_tile_type(tile_type: TileType) -> PlainTileType{
        TileType::Wall => PlainTileType::Wall,
    }
 }
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ResourceCollectorError{
    BackPackIsFull,
    NotEnoughEnergy,
@@ -534,6 +538,11 @@ pub enum ResourceCollectorError{
    NoContentFound,
    NoWayToDispose,
    NoContentToDispose,
+   //more content than the backpack can ever hold
+   TooMuchRequested,
+   NotCollectable,
+   //any other error of the TomTom tool
+   Navigation(String),
 }
 
 #[cfg(test)]
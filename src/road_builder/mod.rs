@@ -1,61 +1,88 @@
-use core::panic;
 use std::collections::HashSet;
 use bessie::bessie::{road_paving_machine, RpmError, State};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::snapshot::charted;
 use robotics_lib::interface::{destroy, put, robot_map,  Direction};
 use robotics_lib::runner::Runnable;
 use robotics_lib::utils::LibError;
 use robotics_lib::world::tile::{Content, Tile, TileType};
 use robotics_lib::world::World;
 use rust_and_furious_dynamo::dynamo::Dynamo;
-use rust_eze_tomtom::TomTom;
 use crate::explorer::{coordinate_to_direction, is_adjacent};
-use crate::fast_paths::{dijkstra, path_to_coordinates};
+use crate::fast_paths::{dijkstra, get_adjacent_tiles, path_to_coordinates, robot_field, walk_path, Path, PathCost};
 use crate::resources::{empty_the_backpack, get_content, ResourceCollectorError};
-use crate::interface::Jerry;
+use crate::jerry::Jerry;
+use crate::logging;
 use crate::sector_analyzer::SectorData;
 use crate::utils::JerryStatus;
 use charting_tools::charted_coordinate::ChartedCoordinate;
-use charting_tools::charted_paths::ChartedPaths;
-use charting_tools::ChartingTools;
-use crate::utils::Mission;
+use crate::utils::{Mission, MissionData, MissionKind, MissionOutcome};
 use crate::utils::MissionStatus::Paused;
 
 use crate::utils::MissionStatus::Completed;
 use crate::road_builder::RoadBuilderError::RoadNonAccessible;
 
 const TO_REMOVE_FROM_BP: usize = 7;
+//tiles further than this are not worth the energy, the mission fails instead
+const MAX_PAVE_COST: u32 = 1000;
+
+pub(crate) struct RoadBuilder;
+
+impl MissionKind for RoadBuilder {
+    fn name(&self) -> &'static str {
+        "Road Builder"
+    }
+    fn default_priority(&self) -> u8 {
+        2
+    }
+    fn recharges(&self) -> bool {
+        true
+    }
+    fn execute(&self, jerry: &mut Jerry, world: &mut World, mission_index: usize) -> Result<MissionOutcome, JerryStatus> {
+        jerry.active_region.top_left = (jerry.world_dim - 1, jerry.world_dim - 1);
+        jerry.active_region.bottom_right = (0, 0);
+        road_builder_execute(jerry, world, mission_index)
+    }
+}
+
 pub fn plan_node_2_node(jerry: &mut Jerry, world: &mut World, node1: (usize, usize), node2: (usize, usize)) -> Vec<ChartedCoordinate> {
     let target = HashSet::from([node2]);
     let road = HashSet::from([ChartedCoordinate(node2.0, node2.1)]);
-    let map = robot_map(world).unwrap();
-    if let Ok(path) = dijkstra(jerry, world, &map, node1, target, Some(Some(&road))){
+    let Some(map) = robot_map(world) else {
+        log::warn!(target: logging::ROAD_BUILDER, "No robot map to plan node to node");
+        return Vec::new();
+    };
+    if let Ok(path) = dijkstra(jerry, world, &map, node1, target, Some(Some(&road)), PathCost::Road){
         return path_to_coordinates(&path)
     }
-    if let Err(error) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(Some(&road))){
-        println!("{:?}", error);
+    if let Err(error) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(Some(&road)), PathCost::Road){
+        log::debug!(target: logging::ROAD_BUILDER, "Dijkstra failed: {:?}", error);
     }
-    println!("Failed to plan node to node");
+    log::warn!(target: logging::ROAD_BUILDER, "Failed to plan node to node");
     Vec::new()
 }
 pub fn plan_node_2_road(jerry: &mut Jerry, world: &mut World, node1: (usize, usize), local_road: Option<&HashSet<ChartedCoordinate>>) -> Vec<ChartedCoordinate> {
-    let map = robot_map(world).unwrap();
+    let Some(map) = robot_map(world) else {
+        log::warn!(target: logging::ROAD_BUILDER, "No robot map to plan node_to_road");
+        return Vec::new();
+    };
     if let Some(road) = local_road{
-        if let Ok(path) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(Some(&road))){
+        if let Ok(path) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(Some(&road)), PathCost::Road){
             return path_to_coordinates(&path)
         }
-        if let Err(error) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(Some(&road))){
-            println!("{:?}", error);
+        if let Err(error) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(Some(&road)), PathCost::Road){
+            log::debug!(target: logging::ROAD_BUILDER, "Dijkstra failed: {:?}", error);
         }
     }
     //else plan the road to the global road
-    if let Ok(path) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(None)){
+    if let Ok(path) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(None), PathCost::Road){
         return path_to_coordinates(&path)
     }
-    if let Err(error) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(None)){
-        println!("{:?}", error);
+    if let Err(error) = dijkstra(jerry, world, &map, node1, HashSet::new(), Some(None), PathCost::Road){
+        log::debug!(target: logging::ROAD_BUILDER, "Dijkstra failed: {:?}", error);
     }
-    println!("Failed to plan node_to_road");
+    log::warn!(target: logging::ROAD_BUILDER, "Failed to plan node_to_road");
     Vec::new()
 }
 pub fn plan_road_2_global(jerry: &mut Jerry, world: &mut World, road: &HashSet<ChartedCoordinate>) -> Vec<ChartedCoordinate> {
@@ -63,11 +90,11 @@ pub fn plan_road_2_global(jerry: &mut Jerry, world: &mut World, road: &HashSet<C
     for node in road.iter(){
         let path1 = plan_node_2_road(jerry, world, (node.0, node.1), None);
         if (path1.len() < ret.len() && path1.len() > 0) || ret.is_empty(){
-            println!("Path1 len {}", path1.len());
+            log::trace!(target: logging::ROAD_BUILDER, "Path1 len {}", path1.len());
             ret = path1;
         }
     }
-    if ret.len() == 0 {println!("Failed to plan road_to_global");}
+    if ret.len() == 0 {log::warn!(target: logging::ROAD_BUILDER, "Failed to plan road_to_global");}
     ret
 }
 fn shrink_path(path: &mut Vec<ChartedCoordinate>, map: &Vec<Vec<Option<Tile>>>){
@@ -99,7 +126,10 @@ fn shrink_path(path: &mut Vec<ChartedCoordinate>, map: &Vec<Vec<Option<Tile>>>){
 pub fn generate_road_builders(jerry: &mut Jerry, world: &mut World, sector_data: SectorData){
     let nodes = sector_data.nodes;
     let mut missions = 0;
-    let map = robot_map(world).unwrap();
+    let Some(map) = robot_map(world) else {
+        log::warn!(target: logging::ROAD_BUILDER, "No robot map to plan the road builders");
+        return;
+    };
     let first_road_to_pave = jerry.road_tiles.is_empty();
      //if there is just one node
      if nodes.len() == 1{
@@ -113,7 +143,7 @@ pub fn generate_road_builders(jerry: &mut Jerry, world: &mut World, sector_data:
                 return;
             }
 
-            println!("1 node, the global road exists. Connecting {:?} to {:?}, path len is {:?}", nodes[0], path[path.len() - 1], path.len());
+            log::debug!(target: logging::ROAD_BUILDER, "1 node, the global road exists. Connecting {:?} to {:?}, path len is {:?}", nodes[0], path[path.len() - 1], path.len());
             shrink_path(&mut path, &map);
             for tile in &path{
                 jerry.road_tiles.insert(tile.clone());
@@ -131,7 +161,7 @@ pub fn generate_road_builders(jerry: &mut Jerry, world: &mut World, sector_data:
             let mut path = plan_node_2_node(jerry, world, nodes[0], nodes[1]);
             shrink_path(&mut path, &map);
 
-            println!("2 nodes, Connecting in sector {:?} to {:?}, path len is {:?}", nodes[0], nodes[1], path.len());
+            log::debug!(target: logging::ROAD_BUILDER, "2 nodes, Connecting in sector {:?} to {:?}, path len is {:?}", nodes[0], nodes[1], path.len());
             //add the tiles to the sector road
             let mut to_pave = HashSet::new();
             for tile in &path {
@@ -146,7 +176,7 @@ pub fn generate_road_builders(jerry: &mut Jerry, world: &mut World, sector_data:
                 //connecting the road to the global road if it exists
                 //planning the road between the roads
                 let mut path = plan_road_2_global(jerry, world, &to_pave);
-                println!("2 nodes, road exists, connecting to global, path len is {:?}", path.len());
+                log::debug!(target: logging::ROAD_BUILDER, "2 nodes, road exists, connecting to global, path len is {:?}", path.len());
                 shrink_path(&mut path, &map);
                 for tile in &path{
                     jerry.road_tiles.insert(tile.clone());
@@ -168,7 +198,7 @@ pub fn generate_road_builders(jerry: &mut Jerry, world: &mut World, sector_data:
             let (node1, node2) = get_2_furthest_nodes(&nodes);
             let mut path = plan_node_2_node(jerry, world, (node1.0, node1.1), (node2.0, node2.1));
             shrink_path(&mut path, &map);
-            println!("More than 2 nodes, Connecting in sector the two most distant {:?} to {:?}, path len {:?}", node1, node2, path.len());
+            log::debug!(target: logging::ROAD_BUILDER, "More than 2 nodes, Connecting in sector the two most distant {:?} to {:?}, path len {:?}", node1, node2, path.len());
 
             //add the tiles to the sector road
             let mut to_pave = HashSet::new();
@@ -200,7 +230,7 @@ pub fn generate_road_builders(jerry: &mut Jerry, world: &mut World, sector_data:
             if !first_road_to_pave{
             //connecting the local road network to the global road
             let path = plan_road_2_global(jerry, world, &to_pave);
-            println!("More than 2 nodes, road exists, connecting to global");
+            log::debug!(target: logging::ROAD_BUILDER, "More than 2 nodes, road exists, connecting to global");
             let mission = new_road_builder(&path);
             for tile in &path{
                 jerry.road_tiles.insert(tile.clone());
@@ -216,7 +246,7 @@ pub fn generate_road_builders(jerry: &mut Jerry, world: &mut World, sector_data:
         for node in nodes.iter(){
             jerry.road_tiles.insert(ChartedCoordinate(node.0, node.1));
         }
-    println!("Missions {}", missions);
+    log::info!(target: logging::ROAD_BUILDER, "Planned {} road builders", missions);
 }
 
 //sector paver struct
@@ -229,10 +259,24 @@ pub fn new_road_builder(path: &Vec<ChartedCoordinate>) -> Mission{
     for tile in path {
         to_pave.insert(tile.clone());
     }
-    Mission {
-        name: "Road Builder".to_string(),
-        status: Paused,
-        additional_data: Some(Box::new(RoadBuilderData{to_pave: to_pave, paved: HashSet::new()})),
+    Mission::new(Paused, MissionData::RoadBuilder(RoadBuilderData{to_pave: to_pave, paved: HashSet::new()}))
+}
+//the road builder state of the mission at the given index
+fn road_builder_data(jerry: &Jerry, mission_index: usize) -> Result<&RoadBuilderData, JerryStatus>{
+    jerry.missions.get(mission_index).and_then(Mission::road_builder).ok_or(JerryStatus::MissionExecutionError)
+}
+fn road_builder_data_mut(jerry: &mut Jerry, mission_index: usize) -> Result<&mut RoadBuilderData, JerryStatus>{
+    jerry.missions.get_mut(mission_index).and_then(Mission::road_builder_mut).ok_or(JerryStatus::MissionExecutionError)
+}
+//the tiles that are still to be paved, so that nothing gets disposed on them
+fn planned_road(jerry: &Jerry, mission_index: usize) -> HashSet<ChartedCoordinate>{
+    road_builder_data(jerry, mission_index).map(|data| data.to_pave.clone()).unwrap_or_default()
+}
+//moves the tile from the to_pave set to the paved one
+fn mark_paved(jerry: &mut Jerry, mission_index: usize, tile: ChartedCoordinate){
+    if let Ok(road_builder_data) = road_builder_data_mut(jerry, mission_index){
+        road_builder_data.to_pave.remove(&tile);
+        road_builder_data.paved.insert(tile);
     }
 }
 //executes the road builder mission
@@ -240,7 +284,7 @@ pub fn new_road_builder(path: &Vec<ChartedCoordinate>) -> Mission{
 //ideally, path should not contain crates
 //if it does, the function will skip a tile
 //if it contains a teleport, the function will teleport to that tile
-pub fn road_builder_execute(jerry: &mut Jerry, world: &mut World, mission_index: usize) -> Result<(), JerryStatus> {
+pub fn road_builder_execute(jerry: &mut Jerry, world: &mut World, mission_index: usize) -> Result<MissionOutcome, JerryStatus> {
     let mut new_tick = true;
     
     /*
@@ -255,8 +299,7 @@ pub fn road_builder_execute(jerry: &mut Jerry, world: &mut World, mission_index:
     for _ in 0..1{
         //if the robot has less than 100 energy on the new tick, use the dynamo tool with the probability of 0.8
         if new_tick && jerry.get_energy().get_energy_level() < 100{
-            let mut rng = rand::thread_rng();
-            let probability = rng.gen_range(0.0..1.0);
+            let probability = jerry.rng.gen_range(0.0..1.0);
             if probability < 0.7{
                 *jerry.get_energy_mut() = Dynamo::update_energy();
             }
@@ -264,64 +307,54 @@ pub fn road_builder_execute(jerry: &mut Jerry, world: &mut World, mission_index:
         
         //initializing the necessary tools and data
 
-        let map = robot_map(world).unwrap();
-        let mut charted_paths  = ChartingTools::tool::<ChartedPaths>()
-            .expect("too many tools used!");
-        charted_paths.init(&map, world);
-        let mission = jerry.missions.get_mut(mission_index).unwrap();
-        let road_builder_data = mission.additional_data.as_mut().unwrap().downcast_mut::<RoadBuilderData>().unwrap();
+        let map = robot_map(world).ok_or(JerryStatus::RoadBuilderFailed(RoadBuilderError::MapUnavailable))?;
+        let data = road_builder_data(jerry, mission_index)?;
         
         //debugging
         //update web page
         //update_web_page(jerry, &map);
 
         //completion condition
-        if road_builder_data.to_pave.is_empty(){
-            println!("I built the road!");
-            mission.status = Completed;
-            return Ok(());
-        }
-        let selected_tile = choose_tile_to_pave(jerry, charted_paths, mission_index);
-
-        //if the selected tile to pave is not accessible, panic, because this should not happen
-        if selected_tile.is_err(){
-            panic!("Selected tile to pave is not accessible");
+        if data.to_pave.is_empty(){
+            log::info!(target: logging::ROAD_BUILDER, "I built the road!");
+            if let Some(mission) = jerry.missions.get_mut(mission_index){
+                mission.status = Completed;
+            }
+            return Ok(MissionOutcome::Completed);
         }
+        //if no tile left to pave is accessible the mission cannot go on
+        let (selected_tile, selected_tile_cost, selected_path) = choose_tile_to_pave(jerry, world, &map, mission_index)
+            .map_err(JerryStatus::RoadBuilderFailed)?;
 
         //try to reach the tiles adjacent to the selected tile or throw an error if it is too expensive
-        let selected_tile = selected_tile.unwrap().0;
-        let selected_tile_cost = selected_tile.1;
-        //panic if the selected tile is too expensive
-        if selected_tile_cost > 1000{
-            panic!("Too Expensive bro")
+        if selected_tile_cost > MAX_PAVE_COST{
+            return Err(JerryStatus::RoadBuilderFailed(RoadBuilderError::TooExpensive(selected_tile_cost)));
         }
+        let selected_tile_cost = selected_tile_cost as usize;
         if selected_tile_cost > jerry.get_energy().get_energy_level(){
             return Err(JerryStatus::LowEnergyWarning);
         }
 
-        if let Err(error) =  go_and_pave(jerry, &map, world, selected_tile, mission_index){
+        if let Err(error) =  go_and_pave(jerry, &map, world, selected_tile, selected_path, mission_index){
 
-            let mission = jerry.missions.get_mut(mission_index).unwrap();
-            let road_builder_data = mission.additional_data.as_mut().unwrap().downcast_mut::<RoadBuilderData>().unwrap();
             match error{
                 //if not enough energy, return the error and with the prob of cheating update it or wait
                 | RoadBuilderError::NotEnoughEnergy => {
                     return Err(JerryStatus::LowEnergyWarning);
                 }
-                //if cannot get the material, stop executing the mission
+                //if cannot get the material, the road cannot be finished and the mission fails
                 | RoadBuilderError::CannotGetMaterial => {
-                    println!("Cannot get the material");
-                    mission.status = Completed;
-                    return Ok(());
+                    log::warn!(target: logging::ROAD_BUILDER, "Cannot get the material");
+                    return Err(JerryStatus::RoadBuilderFailed(error));
                 }
                 //if cannot pave the tile, skip it, remove it from the to_pave set and continue
                 | RoadBuilderError::CannotPaveTile => {
-                    println!("Skipping tile {:?}", map[selected_tile.0][selected_tile.1]);
-                    road_builder_data.to_pave.remove(&selected_tile);
+                    log::debug!(target: logging::ROAD_BUILDER, "Skipping tile {:?}", map[selected_tile.0][selected_tile.1]);
+                    road_builder_data_mut(jerry, mission_index)?.to_pave.remove(&selected_tile);
                     continue;
                 }
-                //other errors should not be propagated
-                | _ => {panic!("{:?}", error)}
+                //anything else means the mission cannot go on
+                | _ => return Err(JerryStatus::RoadBuilderFailed(error)),
             }
         }
         //successfully paved the tile and go_and_pave has deleted it from the to_pave set
@@ -329,9 +362,9 @@ pub fn road_builder_execute(jerry: &mut Jerry, world: &mut World, mission_index:
             continue;
         }
     }
-    Err(JerryStatus::CallingNextTick)
+    Ok(MissionOutcome::InProgress)
 }
-fn go_and_pave(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mut World, tile: ChartedCoordinate, mission_index: usize) -> Result<(), RoadBuilderError>{
+fn go_and_pave(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mut World, tile: ChartedCoordinate, path: Path, mission_index: usize) -> Result<(), RoadBuilderError>{
     
     let jerry_coordinate = ChartedCoordinate(jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col());
     //if the destination is adjacent to the robot, try to pave it
@@ -343,22 +376,19 @@ fn go_and_pave(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mut Worl
         }
         //else modify the mission data and return Ok
         else{
-            let mission = jerry.missions.get_mut(mission_index).unwrap();
-            let road_builder_data = mission.additional_data.as_mut().unwrap().downcast_mut::<RoadBuilderData>().unwrap();
-            road_builder_data.to_pave.remove(&tile);
-            road_builder_data.paved.insert(tile);
+            mark_paved(jerry, mission_index, tile);
             return Ok(());
         }
     }
-    //else try to reach the tile adjacent to the target and try bessie there
+    //else walk to the tile adjacent to the target and try bessie there
     else{
-        if let Err(error) = TomTom::go_to_coordinates(jerry, world, true, (tile.0, tile.1)){
+        if let Err(error) = walk_path(jerry, world, path){
             if error.eq("Not enough energy!"){
                 return Err(RoadBuilderError::NotEnoughEnergy);
             }
             else{
                 //Should not fail to go to the tile
-                panic!("{:?}", error);
+                return Err(RoadBuilderError::Navigation(error));
             }
         }
         else {
@@ -370,10 +400,7 @@ fn go_and_pave(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mut Worl
             }
             //else modify the mission data and return Ok
             else{
-                let mission = jerry.missions.get_mut(mission_index).unwrap();
-                let road_builder_data = mission.additional_data.as_mut().unwrap().downcast_mut::<RoadBuilderData>().unwrap();
-                road_builder_data.to_pave.remove(&tile);
-                road_builder_data.paved.insert(tile);
+                mark_paved(jerry, mission_index, tile);
                 return Ok(());
             } 
         }
@@ -389,17 +416,19 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
             //The error occurs when trying to pave the street
             | RpmError::CannotPlaceHere => {
                 let coord = direction_to_coordinate((jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col()),direction.clone());
-                if map[coord.0][coord.1].as_ref().unwrap().tile_type == TileType::Street{
-                    println!("There's a street already");
+                if matches!(&map[coord.0][coord.1], Some(tile) if tile.tile_type == TileType::Street){
+                    log::debug!(target: logging::ROAD_BUILDER, "There's a street already");
                 }
-                println!("Cannot place here {:?}", map[coord.0][coord.1]);
+                log::debug!(target: logging::ROAD_BUILDER, "Cannot place here {:?}", map[coord.0][coord.1]);
                 return Err(RoadBuilderError::CannotPaveTile);
             }
             //try to destroy the content and pave again, if the tile does contain a crate, skip it and do not pave returning a specific error
             | RpmError::MustDestroyContentFirst => {
                 let tile_coord = direction_to_coordinate((jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col()), direction.clone());
-                //force to unwrap because should be known
-                let tile = map[tile_coord.0][tile_coord.1].as_ref().unwrap();
+                //the robot is next to it, so it should be known, if not it is skipped
+                let Some(tile) = map[tile_coord.0][tile_coord.1].as_ref() else {
+                    return Err(RoadBuilderError::CannotPaveTile);
+                };
                 if Content::Crate(0..0) == tile.content.to_default(){
                     return Err(RoadBuilderError::CannotPaveTile);
                 }
@@ -409,15 +438,13 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
                         //if not enough space in the backpack to destroy the content
                         | LibError::NotEnoughSpace(added) => {
                             vent_tool1.borrow_mut().create_waypoint(jerry, 1000);
-                            let mission = jerry.missions.get_mut(mission_index).unwrap();
-                            let road_builder_data = mission.additional_data.as_mut().unwrap().downcast_mut::<RoadBuilderData>().unwrap();
-                            let planned_road = &road_builder_data.to_pave.clone();
-                            println!("Not enough space in the backpack, i have added {}", added);
+                            let planned_road = &planned_road(jerry, mission_index);
+                            log::debug!(target: logging::ROAD_BUILDER, "Not enough space in the backpack, i have added {}", added);
                             if let Err(error) = empty_the_backpack(jerry, world, Some(planned_road), TO_REMOVE_FROM_BP - added){
                                 match error{
                                     //if not enough energy, return the error
                                     | ResourceCollectorError::NotEnoughEnergy => return Err(RoadBuilderError::NotEnoughEnergy),
-                                    | _ => {panic!("unexpected {:?}", error)}
+                                    | _ => return Err(RoadBuilderError::Resources(error)),
                                 }
                             }
                             //if disposed the content, return to the waypoint and try to pave again
@@ -427,7 +454,7 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
                                         //pretty much the only error that can happen
                                         | vent_tool_ascii_crab::VentError::NotEnoughEnergy => return Err(RoadBuilderError::NotEnoughEnergy),   
                                         | vent_tool_ascii_crab::VentError::CommonCrateError(_) => return Err(RoadBuilderError::NotEnoughEnergy),
-                                        | _ => panic!("{:?}", error),
+                                        | _ => return Err(RoadBuilderError::Vent(format!("{:?}", error))),
                                     }
                                 }
                                 drop(vent_tool1);
@@ -437,9 +464,7 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
                             }
                         }
                         //if undefined error occurs
-                        | _ => {
-                            panic!("{:?}", error);
-                        }
+                        | _ => return Err(RoadBuilderError::Interface(format!("{:?}", error))),
                     }
                 }
                 //if the content is successfully destroyed, try to pave again
@@ -452,18 +477,15 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
                 //need to search for rocks
                 //remember the current position to return back after the search using the vent tool waypoint
                 vent_tool2.borrow_mut().create_waypoint(jerry, 1000);
-                let mission = jerry.missions.get_mut(mission_index).unwrap();
-                let road_builder_data = mission.additional_data.as_mut().unwrap().downcast_mut::<RoadBuilderData>().unwrap();
-                let planned_road = &road_builder_data.to_pave.clone();
+                let planned_road = &planned_road(jerry, mission_index);
                 if let Err(error) = get_content(jerry, world, Content::Rock(0), Some(&planned_road), 20){
                     match error{
                         //if not enough energy, just propagate the error
                         | ResourceCollectorError::NotEnoughEnergy => return Err(RoadBuilderError::NotEnoughEnergy),
-                        //Normally, should not happen
-                        | ResourceCollectorError::BackPackIsFull => panic!("Backpack is full"),
                         //if the path to the content is not found
                         |ResourceCollectorError::PathNotFound | ResourceCollectorError::NoContentFound => return Err(RoadBuilderError::CannotGetMaterial),
-                        _ => panic!("Unexpected {:?}", error),
+                        //Normally, should not happen, like a full backpack
+                        _ => return Err(RoadBuilderError::Resources(error)),
                     }
                 }
                 //if found the content, return to the waypoint and try to pave again
@@ -473,7 +495,7 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
                             //pretty much the only error that can happen
                             | vent_tool_ascii_crab::VentError::NotEnoughEnergy => return Err(RoadBuilderError::NotEnoughEnergy),
                             | vent_tool_ascii_crab::VentError::CommonCrateError(_) => return Err(RoadBuilderError::NotEnoughEnergy),
-                            | _ => panic!("{:?}", error),
+                            | _ => return Err(RoadBuilderError::Vent(format!("{:?}", error))),
                         }
                     }
                     //if the robot has returned, try to pave again
@@ -486,7 +508,7 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
             //Normally doesn't happen, usually on the mountain tile
             | _ => {
                 let coord = direction_to_coordinate((jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col()),direction.clone());
-                if map[coord.0][coord.1].as_ref().unwrap().tile_type == TileType::Mountain{
+                if matches!(&map[coord.0][coord.1], Some(tile) if tile.tile_type == TileType::Mountain){
                     //Try to pave the mountain tile
                     //If it fails, empty the backpack and try again
                     if let Err(error) = put(jerry, world, Content::None, 0, direction.clone()){
@@ -494,13 +516,11 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
                             | LibError::NotEnoughEnergy => return Err(RoadBuilderError::NotEnoughEnergy),
                             | LibError::NotEnoughSpace(added) => {
                                 vent_tool2.borrow_mut().create_waypoint(jerry, 1000);
-                                let mission = jerry.missions.get_mut(mission_index).unwrap();
-                                let road_builder_data = mission.additional_data.as_mut().unwrap().downcast_mut::<RoadBuilderData>().unwrap();
-                                let planned_road = &road_builder_data.to_pave.clone();
+                                let planned_road = &planned_road(jerry, mission_index);
                                 if let Err(error) = empty_the_backpack(jerry, world, Some(planned_road), TO_REMOVE_FROM_BP - added){
                                     match error{
                                         | ResourceCollectorError::NotEnoughEnergy => return Err(RoadBuilderError::NotEnoughEnergy),
-                                        | _ => panic!("{:?}", error),
+                                        | _ => return Err(RoadBuilderError::Resources(error)),
                                     }
                                 }
                                 else{
@@ -508,60 +528,45 @@ fn bessie_controller(jerry: &mut Jerry, map: &Vec<Vec<Option<Tile>>>, world: &mu
                                         match error{
                                             | vent_tool_ascii_crab::VentError::NotEnoughEnergy => return Err(RoadBuilderError::NotEnoughEnergy),
                                             | vent_tool_ascii_crab::VentError::CommonCrateError(_) => return Err(RoadBuilderError::NotEnoughEnergy),
-                                            | _ => panic!("{:?}", error),
+                                            | _ => return Err(RoadBuilderError::Vent(format!("{:?}", error))),
                                         }
                                     }
                                     drop(vent_tool2);
                                     return bessie_controller(jerry, map, world, direction, mission_index);
                                 }
                             }
-                            | _ => panic!("{:?}", error),
+                            | _ => return Err(RoadBuilderError::Interface(format!("{:?}", error))),
                         }
                     }
                 }
                 else{
-                    panic!("{:?}", error);
+                    return Err(RoadBuilderError::Paving(format!("{:?}", error)));
                 }
             }
         }
     }
-    println!("Paved successfully!");
+    log::trace!(target: logging::ROAD_BUILDER, "Paved successfully!");
     Ok(())   
 }
-//choose the tile to pave with the cheapest cost of going to
-fn choose_tile_to_pave(jerry: &mut Jerry, tool: ChartedPaths, mission_index: usize) -> Result<(ChartedCoordinate, u32), RoadBuilderError>{
-
-    let mission = jerry.missions.get(mission_index);
-    let robot_coord = (jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col());
-    let robot_charted_coord = ChartedCoordinate(robot_coord.0, robot_coord.1);
-    let data: &RoadBuilderData = mission.as_ref().unwrap().additional_data.as_ref().unwrap().downcast_ref().unwrap();
+//choose the tile to pave with the cheapest cost of going to, with the way to the tile next to it the robot paves from
+fn choose_tile_to_pave(jerry: &mut Jerry, world: &World, map: &Vec<Vec<Option<Tile>>>, mission_index: usize) -> Result<(ChartedCoordinate, u32, Path), RoadBuilderError>{
 
-    let search_depth = if data.to_pave.len() > 10 { 10 } else { data.to_pave.len() };
-    let mut road_accessible = false;
-    let mut start = data.to_pave.len();
-    let mut candidate_cost = 0;
-    //iterate first over the last 10 added frontier tiles then 20 then 30 and so on
-    while start > 0{
-        let end = start;
-        start = start.saturating_sub(search_depth);
+    let data = road_builder_data(jerry, mission_index).map_err(|_| RoadNonAccessible)?;
+    let to_pave: Vec<(usize, usize)> = data.to_pave.iter().map(|coord| (coord.0, coord.1)).collect();
+    //one search from the robot gives the cost of every tile left to pave, and the way to the chosen one
+    let field = robot_field(jerry, world, map, PathCost::Energy);
 
-        //candidate is the tile with the minimum cost to go to
-        //if the cost is u32::MAX, the tile is not accessible
-        let candidate = data.to_pave.iter()
-        .min_by_key(|coord|{
-            let cost = tool.shortest_path_cost(robot_charted_coord, **coord).unwrap_or(u32::MAX);
-            if cost != u32::MAX{
-                road_accessible = true;
-            }
-            candidate_cost = cost;
-            cost
-        });
-        //return the accessible tile with the minimum cost
-        if road_accessible{
-            return Ok((ChartedCoordinate(candidate.unwrap().0, candidate.unwrap().1), candidate_cost));
-        }
-    }
-    return Err(RoadNonAccessible);
+    //candidate is the accessible tile with the minimum cost to go to, together with that cost
+    //the set has no order, ties go to the smallest coordinate so a run can be replayed
+    let candidate = to_pave.iter()
+        .filter_map(|coord| field.cost(*coord).map(|cost| (coord, cost)))
+        .min_by_key(|(coord, cost)| (*cost, **coord));
+    let Some((coord, cost)) = candidate else {
+        return Err(RoadNonAccessible);
+    };
+    let next_to: HashSet<(usize, usize)> = get_adjacent_tiles(map, *coord).into_iter().collect();
+    let path = field.closest(&next_to).map_err(RoadBuilderError::Navigation)?;
+    Ok((ChartedCoordinate(coord.0, coord.1), u32::try_from(cost).unwrap_or(u32::MAX), path))
 }
 
 fn get_2_furthest_nodes(nodes: &Vec<(usize, usize)>) -> (ChartedCoordinate, ChartedCoordinate){
@@ -589,15 +594,37 @@ fn direction_to_coordinate(coord: (usize, usize), direction: Direction) -> (usiz
         | Direction::Right => (coord.0, coord.1 + 1),
     }
 }
-#[derive(Debug, Clone, PartialEq, Copy)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RoadBuilderError{
     NotEnoughEnergy,
     NotEnoughMaterial,
     CannotGetMaterial,
     RoadNonAccessible,
     CannotPaveTile,
+    //the cheapest tile left to pave costs more than MAX_PAVE_COST to reach
+    TooExpensive(u32),
+    //errors of the tools and of the interface that the road builder cannot recover from
+    Resources(ResourceCollectorError),
+    Navigation(String),
+    Paving(String),
+    Interface(String),
+    Vent(String),
+    //the library has no map of what the robot has seen
+    MapUnavailable,
 }
+#[derive(Clone, Serialize, Deserialize)]
 pub struct RoadBuilderData{
+    #[serde(with = "charted")]
     to_pave: HashSet<ChartedCoordinate>,
+    #[serde(with = "charted")]
     paved: HashSet<ChartedCoordinate>,
+}
+
+impl RoadBuilderData {
+    pub(crate) fn to_pave(&self) -> &HashSet<ChartedCoordinate> {
+        &self.to_pave
+    }
+    pub(crate) fn paved(&self) -> &HashSet<ChartedCoordinate> {
+        &self.paved
+    }
 }
\ No newline at end of file
@@ -0,0 +1,329 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::utils::{Mission, MissionStatus};
+use crate::logging;
+
+/*
+    The scheduler decides which mission the robot works on during a tick.
+    It is allowed to change the statuses of the missions (pause the explorers, resume the road builders, ...)
+    and returns the index of the mission to execute, or None if the robot should stay idle.
+    Preemption rules are checked in order before the mission is picked.
+ */
+pub(crate) trait Scheduler {
+    fn name(&self) -> &'static str;
+    fn schedule(&mut self, missions: &mut VecDeque<Mission>, context: &ScheduleContext) -> Option<usize>;
+}
+
+//what the scheduler knows about the robot
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ScheduleContext {
+    pub(crate) energy: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Preemption {
+    //below the threshold only the missions that recharge with the dynamo are run
+    //the robot gets no energy back while idle, so it never stops
+    LowEnergy { threshold: usize },
+    //after every n completed explorers the road builders take over until they are done
+    RoadsAfterSectors { sectors: usize },
+}
+
+//result of a preemption rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Preempted {
+    Nothing,
+    Recharging,
+}
+
+impl Preemption {
+    fn apply(&self, missions: &mut VecDeque<Mission>, context: &ScheduleContext) -> Preempted {
+        match *self {
+            Preemption::LowEnergy { threshold } => {
+                if context.energy < threshold {
+                    log::info!(target: logging::SCHEDULER, "Low energy ({}), running the missions that recharge", context.energy);
+                    return Preempted::Recharging;
+                }
+                Preempted::Nothing
+            }
+            Preemption::RoadsAfterSectors { sectors } => {
+                roads_after_sectors(missions, sectors.max(1));
+                Preempted::Nothing
+            }
+        }
+    }
+}
+
+//applies every rule, true if only the missions that recharge may run
+fn apply_rules(rules: &[Preemption], missions: &mut VecDeque<Mission>, context: &ScheduleContext) -> bool {
+    let mut recharging = false;
+    for rule in rules.iter(){
+        if rule.apply(missions, context) == Preempted::Recharging{
+            recharging = true;
+        }
+    }
+    recharging
+}
+
+fn roads_after_sectors(missions: &mut VecDeque<Mission>, sectors: usize) {
+    let count = MissionCount::of(missions);
+    log::debug!(target: logging::SCHEDULER, "Act Exp: {} Wait Exp: {} Comp Exp: {} Act RB: {} Wait RB: {} Comp RB {}",
+    count.active_explorers, count.waiting_explorers, count.completed_explorers,
+    count.active_road_builders, count.waiting_road_builders, count.completed_road_builders);
+    //after every n completed explorers, set road builders to active and pause the active explorers
+    if (count.completed_explorers > 0 && count.completed_explorers % sectors == 0) && count.waiting_road_builders > 0{
+        for mission in missions.iter_mut().filter(|mission| !mission.is_held()){
+            if mission.is_explorer() && (mission.status == MissionStatus::Active || mission.status == MissionStatus::New){
+                mission.status = MissionStatus::Paused;
+            }
+            if mission.is_road_builder() && mission.status == MissionStatus::Paused{
+                mission.status = MissionStatus::Active;
+            }
+        }
+    }
+    //when the builders are done set explorers to active and pause the active builders
+    if count.waiting_explorers > 0 && count.active_road_builders == 0{
+        for mission in missions.iter_mut().filter(|mission| !mission.is_held()){
+            if mission.is_explorer() && mission.status == MissionStatus::Paused{
+                mission.status = MissionStatus::Active;
+            }
+            if mission.is_road_builder() && mission.status == MissionStatus::Active{
+                mission.status = MissionStatus::Paused;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MissionCount {
+    pub(crate) active_explorers: usize,
+    pub(crate) waiting_explorers: usize,
+    pub(crate) completed_explorers: usize,
+    pub(crate) active_road_builders: usize,
+    pub(crate) waiting_road_builders: usize,
+    pub(crate) completed_road_builders: usize,
+}
+
+impl MissionCount {
+    pub(crate) fn of(missions: &VecDeque<Mission>) -> MissionCount {
+        let mut count = MissionCount::default();
+        //missions held by the user are out of the scheduler's hands, failed ones count as completed
+        for mission in missions.iter().filter(|mission| !mission.is_held()){
+            if mission.is_explorer(){
+                match mission.status{
+                    MissionStatus::Completed | MissionStatus::Failed => count.completed_explorers += 1,
+                    MissionStatus::Paused => count.waiting_explorers += 1,
+                    MissionStatus::Active | MissionStatus::New => count.active_explorers += 1,
+                }
+            }
+            else if mission.is_road_builder(){
+                match mission.status{
+                    MissionStatus::Completed | MissionStatus::Failed => count.completed_road_builders += 1,
+                    MissionStatus::Paused => count.waiting_road_builders += 1,
+                    MissionStatus::Active | MissionStatus::New => count.active_road_builders += 1,
+                }
+            }
+        }
+        count
+    }
+}
+
+//runs the first active mission of the queue
+pub(crate) struct QueueScheduler {
+    pub(crate) rules: Vec<Preemption>,
+}
+
+impl Scheduler for QueueScheduler {
+    fn name(&self) -> &'static str {
+        "Queue"
+    }
+    fn schedule(&mut self, missions: &mut VecDeque<Mission>, context: &ScheduleContext) -> Option<usize> {
+        let recharging = apply_rules(&self.rules, missions, context);
+        missions.iter().position(|mission| !mission.is_held() && (!recharging || mission.kind().recharges()) &&
+            (mission.status == MissionStatus::Active || mission.status == MissionStatus::New))
+    }
+}
+
+//runs the unfinished mission with the highest priority, the oldest one on ties
+//the mission that gets the robot pauses the other active ones
+pub(crate) struct PriorityScheduler {
+    pub(crate) rules: Vec<Preemption>,
+}
+
+impl Scheduler for PriorityScheduler {
+    fn name(&self) -> &'static str {
+        "Priority"
+    }
+    fn schedule(&mut self, missions: &mut VecDeque<Mission>, context: &ScheduleContext) -> Option<usize> {
+        let recharging = apply_rules(&self.rules, missions, context);
+        let mut selected: Option<usize> = None;
+        for (index, mission) in missions.iter().enumerate(){
+            if mission.status.is_finished() || mission.is_held() || (recharging && !mission.kind().recharges()){
+                continue;
+            }
+            match selected{
+                Some(best) if missions[best].priority >= mission.priority => {}
+                _ => selected = Some(index),
+            }
+        }
+        let selected = selected?;
+        for (index, mission) in missions.iter_mut().enumerate(){
+            if index != selected && mission.status == MissionStatus::Active && !mission.is_held(){
+                mission.status = MissionStatus::Paused;
+            }
+        }
+        if missions[selected].status == MissionStatus::Paused{
+            missions[selected].status = MissionStatus::Active;
+        }
+        Some(selected)
+    }
+}
+
+//below it the cautious robot only runs the missions that recharge
+const CAUTIOUS_ENERGY: usize = 20;
+
+//the scheduling policies that can be chosen on the start screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SchedulerPolicy {
+    //explore two sectors, then build their roads (the original behaviour)
+    Alternating,
+    //road builders first, explorers after, only the missions that recharge when the energy is low
+    Priority,
+    //build the roads after every sector and only recharge when the energy is almost gone
+    Cautious,
+}
+
+impl SchedulerPolicy {
+    pub(crate) const ALL: [SchedulerPolicy; 3] = [SchedulerPolicy::Alternating, SchedulerPolicy::Priority, SchedulerPolicy::Cautious];
+
+    pub(crate) fn build(&self) -> Box<dyn Scheduler> {
+        match self {
+            SchedulerPolicy::Alternating => Box::new(QueueScheduler {
+                rules: vec![Preemption::RoadsAfterSectors { sectors: 2 }],
+            }),
+            SchedulerPolicy::Priority => Box::new(PriorityScheduler {
+                rules: vec![Preemption::LowEnergy { threshold: 50 }],
+            }),
+            SchedulerPolicy::Cautious => Box::new(QueueScheduler {
+                rules: vec![Preemption::LowEnergy { threshold: CAUTIOUS_ENERGY }, Preemption::RoadsAfterSectors { sectors: 1 }],
+            }),
+        }
+    }
+    pub(crate) fn from_label(label: &str) -> Option<SchedulerPolicy> {
+        SchedulerPolicy::ALL.iter().copied().find(|policy| policy.to_string() == label)
+    }
+}
+
+impl fmt::Display for SchedulerPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchedulerPolicy::Alternating => write!(f, "Alternating"),
+            SchedulerPolicy::Priority => write!(f, "Priority"),
+            SchedulerPolicy::Cautious => write!(f, "Cautious"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use charting_tools::charted_coordinate::ChartedCoordinate;
+    use crate::explorer::ExplorerData;
+    use crate::road_builder::new_road_builder;
+    use crate::sector_analyzer::new_sector_analyzer;
+    use crate::utils::MissionData;
+    use super::*;
+
+    fn explorer(status: MissionStatus) -> Mission {
+        Mission::new(status, MissionData::Explorer(ExplorerData{
+            frontier: vec![ChartedCoordinate(0, 1)],
+            frontier_hs: HashSet::from([ChartedCoordinate(0, 1)]),
+            spatial_index: 0,
+            robot_moved: false,
+        }))
+    }
+    fn road_builder() -> Mission {
+        new_road_builder(&vec![ChartedCoordinate(1, 1), ChartedCoordinate(1, 2)])
+    }
+    const FULL: ScheduleContext = ScheduleContext { energy: 1000 };
+
+    #[test]
+    fn test_alternating_switches_to_road_builders(){
+        let mut missions = VecDeque::from([
+            explorer(MissionStatus::Completed),
+            explorer(MissionStatus::Completed),
+            explorer(MissionStatus::New),
+            road_builder(),
+        ]);
+        let mut scheduler = SchedulerPolicy::Alternating.build();
+        assert_eq!(scheduler.schedule(&mut missions, &FULL), Some(3));
+        assert_eq!(missions[2].status, MissionStatus::Paused);
+        assert_eq!(missions[3].status, MissionStatus::Active);
+
+        //once the road is built, the explorers get the robot back
+        missions[3].status = MissionStatus::Completed;
+        assert_eq!(scheduler.schedule(&mut missions, &FULL), Some(2));
+        assert_eq!(missions[2].status, MissionStatus::Active);
+    }
+    #[test]
+    fn test_low_energy_preempts_everything(){
+        let mut missions = VecDeque::from([new_sector_analyzer(0, 70)]);
+        let mut scheduler = SchedulerPolicy::Cautious.build();
+        assert_eq!(scheduler.schedule(&mut missions, &ScheduleContext { energy: 10 }), None);
+        assert_eq!(scheduler.schedule(&mut missions, &FULL), Some(0));
+
+        //the explorer recharges, so it runs in its place
+        missions.push_back(explorer(MissionStatus::Active));
+        for policy in [SchedulerPolicy::Cautious, SchedulerPolicy::Priority]{
+            assert_eq!(policy.build().schedule(&mut missions, &ScheduleContext { energy: 10 }), Some(1));
+        }
+    }
+    #[test]
+    fn test_cautious_runs_the_missions_that_recharge(){
+        //the explorer and the road builder only use the dynamo below 100 energy
+        let mut missions = VecDeque::from([explorer(MissionStatus::Active), road_builder()]);
+        let mut scheduler = SchedulerPolicy::Cautious.build();
+        assert_eq!(scheduler.schedule(&mut missions, &ScheduleContext { energy: 99 }), Some(0));
+        assert_eq!(scheduler.schedule(&mut missions, &ScheduleContext { energy: CAUTIOUS_ENERGY }), Some(0));
+        assert_eq!(scheduler.schedule(&mut missions, &ScheduleContext { energy: CAUTIOUS_ENERGY - 1 }), Some(0));
+    }
+    #[test]
+    fn test_priority_prefers_road_builders(){
+        let mut missions = VecDeque::from([explorer(MissionStatus::Active), road_builder()]);
+        let mut scheduler = SchedulerPolicy::Priority.build();
+        assert_eq!(scheduler.schedule(&mut missions, &FULL), Some(1));
+        assert_eq!(missions[0].status, MissionStatus::Paused);
+        assert_eq!(missions[1].status, MissionStatus::Active);
+
+        missions[1].status = MissionStatus::Completed;
+        assert_eq!(scheduler.schedule(&mut missions, &FULL), Some(0));
+        assert_eq!(missions[0].status, MissionStatus::Active);
+    }
+    #[test]
+    fn test_held_missions_are_skipped(){
+        let mut missions = VecDeque::from([explorer(MissionStatus::Active), explorer(MissionStatus::New)]);
+        missions[0].held = Some(MissionStatus::Active);
+        missions[0].status = MissionStatus::Paused;
+        for policy in SchedulerPolicy::ALL{
+            assert_eq!(policy.build().schedule(&mut missions, &FULL), Some(1));
+            assert_eq!(missions[0].status, MissionStatus::Paused);
+        }
+    }
+    #[test]
+    fn test_failed_missions_are_skipped(){
+        let mut missions = VecDeque::from([road_builder(), explorer(MissionStatus::Paused)]);
+        missions[0].status = MissionStatus::Failed;
+        assert_eq!(MissionCount::of(&missions).completed_road_builders, 1);
+        for policy in SchedulerPolicy::ALL{
+            assert_ne!(policy.build().schedule(&mut missions, &FULL), Some(0));
+            assert_eq!(missions[0].status, MissionStatus::Failed);
+        }
+    }
+    #[test]
+    fn test_policy_labels(){
+        for policy in SchedulerPolicy::ALL{
+            assert_eq!(SchedulerPolicy::from_label(&policy.to_string()), Some(policy));
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use robotics_lib::event::events::Event;
+use robotics_lib::world::tile::Content;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/*
+    Follows the score of the world tick by tick.
+    The library only gives the total, so the points gained in a tick are split between the
+    contents that left the backpack in that same tick, which is how points are made, each one
+    weighted by what it is worth in the score map of the world.
+    Points gained without anything worth points leaving the backpack go under Content::None.
+    It is saved with the snapshots, so a resumed game keeps its history.
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct ScoreTracker {
+    // points of one unit of every content
+    score_map: HashMap<Content, f32>,
+    score: f32,
+    // the ticks where the score changed, with the new score
+    history: Vec<(usize, f32)>,
+    breakdown: HashMap<Content, f32>,
+    removed: Vec<(Content, usize)>,
+}
+
+impl ScoreTracker {
+    pub(crate) fn new(score_map: HashMap<Content, f32>) -> Self {
+        Self { score_map, ..Self::default() }
+    }
+    pub(crate) fn event(&mut self, event: &Event) {
+        if let Event::RemovedFromBackpack(content, amount) = event {
+            if *amount > 0 {
+                self.removed.push((content.to_default(), *amount));
+            }
+        }
+    }
+    //called at the end of every tick with the score of the world, true if it changed
+    pub(crate) fn tick(&mut self, tick: usize, score: f32) -> bool {
+        let removed = std::mem::take(&mut self.removed);
+        let gained = score - self.score;
+        if gained == 0.0 {
+            return false;
+        }
+        let worth: Vec<(Content, f32)> = removed
+            .into_iter()
+            .map(|(content, amount)| {
+                let points = self.score_map.get(&content).copied().unwrap_or(0.0);
+                (content, amount as f32 * points)
+            })
+            .filter(|(_, worth)| *worth > 0.0)
+            .collect();
+        let total: f32 = worth.iter().map(|(_, worth)| worth).sum();
+        if total == 0.0 {
+            *self.breakdown.entry(Content::None).or_insert(0.0) += gained;
+        }
+        for (content, worth) in worth {
+            *self.breakdown.entry(content).or_insert(0.0) += gained * worth / total;
+        }
+        self.score = score;
+        self.history.push((tick, score));
+        true
+    }
+    pub(crate) fn score(&self) -> f32 {
+        self.score
+    }
+    pub(crate) fn history(&self) -> &Vec<(usize, f32)> {
+        &self.history
+    }
+    //highest score first
+    pub(crate) fn breakdown(&self) -> Vec<(Content, f32)> {
+        let mut breakdown: Vec<(Content, f32)> = self.breakdown.iter().map(|(content, score)| (content.clone(), *score)).collect();
+        breakdown.sort_by(|a, b| b.1.total_cmp(&a.1));
+        breakdown
+    }
+}
+
+const SCORE_CONTENT: [Content; 15] = [
+    Content::Rock(0),
+    Content::Tree(0),
+    Content::Garbage(0),
+    Content::Fire,
+    Content::Coin(0),
+    Content::Bin(0..0),
+    Content::Crate(0..0),
+    Content::Bank(0..0),
+    Content::Water(0),
+    Content::Market(0),
+    Content::Fish(0),
+    Content::Building,
+    Content::Bush(0),
+    Content::JollyBlock(0),
+    Content::Scarecrow,
+];
+
+// the library does not expose the scores it uses without a custom map, there every content has the same weight
+const DEFAULT_POINTS: f32 = 1.0;
+
+//the score map the world uses, the custom scores over the default ones
+pub(crate) fn effective_score_map(custom: Option<&HashMap<Content, f32>>) -> HashMap<Content, f32> {
+    let mut score_map: HashMap<Content, f32> = SCORE_CONTENT.iter().map(|content| (content.clone(), DEFAULT_POINTS)).collect();
+    if let Some(custom) = custom {
+        score_map.extend(custom.iter().map(|(content, points)| (content.clone(), *points)));
+    }
+    score_map
+}
+
+//name of the content as written in a score map, like "Rock" or "JollyBlock"
+fn content_name(content: &Content) -> String {
+    let name = format!("{:?}", content);
+    name.split('(').next().unwrap_or_default().to_string()
+}
+
+/*
+    Reads a custom score map written as "Rock=2, Tree=0.5".
+    An empty string is an empty map, which means the default scores of the world.
+ */
+pub(crate) fn parse_score_map(text: &str) -> Result<HashMap<Content, f32>, String> {
+    let mut score_map = HashMap::new();
+    for entry in text.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+        let Some((name, value)) = entry.split_once('=') else {
+            return Err(format!("'{}' should look like Rock=2", entry));
+        };
+        let content = SCORE_CONTENT
+            .iter()
+            .find(|content| content_name(content).eq_ignore_ascii_case(name.trim()))
+            .ok_or(format!("unknown content '{}'", name.trim()))?;
+        let value = value.trim().parse::<f32>().map_err(|_| format!("'{}' is not a number", value.trim()))?;
+        score_map.insert(content.clone(), value);
+    }
+    Ok(score_map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_split_by_removed_content(){
+        let mut tracker = ScoreTracker::new(effective_score_map(None));
+        tracker.event(&Event::RemovedFromBackpack(Content::Rock(0), 3));
+        tracker.event(&Event::RemovedFromBackpack(Content::Coin(0), 1));
+        assert!(tracker.tick(4, 8.0));
+        assert!(!tracker.tick(5, 8.0));
+        //nothing left the backpack
+        assert!(tracker.tick(6, 9.0));
+
+        assert_eq!(tracker.score(), 9.0);
+        assert_eq!(tracker.history(), &vec![(4, 8.0), (6, 9.0)]);
+        assert_eq!(
+            tracker.breakdown(),
+            vec![(Content::Rock(0), 6.0), (Content::Coin(0), 2.0), (Content::None, 1.0)]
+        );
+    }
+
+    #[test]
+    fn test_score_split_by_worth(){
+        let custom = parse_score_map("Coin=5, Tree=0").unwrap();
+        let mut tracker = ScoreTracker::new(effective_score_map(Some(&custom)));
+        tracker.event(&Event::RemovedFromBackpack(Content::Rock(0), 3));
+        tracker.event(&Event::RemovedFromBackpack(Content::Coin(0), 1));
+        tracker.event(&Event::RemovedFromBackpack(Content::Tree(0), 4));
+        assert!(tracker.tick(1, 16.0));
+        assert_eq!(tracker.breakdown(), vec![(Content::Coin(0), 10.0), (Content::Rock(0), 6.0)]);
+
+        //worth nothing, the points come from somewhere else
+        tracker.event(&Event::RemovedFromBackpack(Content::Tree(0), 1));
+        assert!(tracker.tick(2, 17.0));
+        assert_eq!(tracker.breakdown().last(), Some(&(Content::None, 1.0)));
+
+        //the history goes on after a snapshot
+        let restored: ScoreTracker = bincode::deserialize(&bincode::serialize(&tracker).unwrap()).unwrap();
+        assert_eq!(restored.history(), tracker.history());
+        assert_eq!(restored.breakdown(), tracker.breakdown());
+    }
+
+    #[test]
+    fn test_parse_score_map(){
+        let score_map = parse_score_map("Rock=2, jollyblock = 0.5").unwrap();
+        assert_eq!(score_map.get(&Content::Rock(0)), Some(&2.0));
+        assert_eq!(score_map.get(&Content::JollyBlock(0)), Some(&0.5));
+        assert!(parse_score_map("").unwrap().is_empty());
+        assert!(parse_score_map("Rock").is_err());
+        assert!(parse_score_map("Lava=1").is_err());
+        assert!(parse_score_map("Rock=a lot").is_err());
+    }
+}
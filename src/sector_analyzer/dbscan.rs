@@ -1,3 +1,4 @@
+use rand::Rng;
 use robotics_lib::world::tile::Tile;
 use Classification::{Core, Edge, Noise};
 
@@ -117,11 +118,11 @@ impl Model{
     }
 
 }
-pub(crate) fn map_into_db_input(input: &Vec<Vec<Option<Tile>>>) -> Vec<(usize, usize, usize)>{
+pub(crate) fn map_into_db_input(input: &Vec<Vec<Option<Tile>>>, rng: &mut impl Rng) -> Vec<(usize, usize, usize)>{
     let mut output = Vec::new();
     for (i, row) in input.iter().enumerate(){
         for (j, tile) in row.iter().enumerate(){
-            output.push((i, j, get_content_value_morans(tile) as usize));
+            output.push((i, j, get_content_value_morans(tile, rng) as usize));
         }
     }
     output
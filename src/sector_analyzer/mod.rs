@@ -1,13 +1,18 @@
 use std::collections::{HashMap, HashSet};
 
+use charting_tools::charted_coordinate::ChartedCoordinate;
+use rand::Rng;
 use robotics_lib::interface::robot_map;
 use robotics_lib::world::tile::TileType::*;
 use robotics_lib::world::{World};
 use robotics_lib::world::tile::{Content, Tile};
 
+use crate::jerry::Jerry;
+use crate::logging;
+use crate::road_builder::generate_road_builders;
 use crate::utils::{get_tl_and_br_from_spatial_index, robot_map_slice};
-use crate::utils::{ActiveRegion, Mission};
-use crate::utils::MissionStatus::Active;
+use crate::utils::{ActiveRegion, JerryStatus, Mission, MissionData, MissionKind, MissionOutcome};
+use crate::utils::MissionStatus::{Active, Completed};
 use crate::morans_i::{morans_i};
 
 use self::dbscan::{Classification, Model};
@@ -15,17 +20,52 @@ use self::dbscan::map_into_db_input;
 
 
 
+pub(crate) struct SectorAnalyzer;
+
+impl MissionKind for SectorAnalyzer {
+    fn name(&self) -> &'static str {
+        "Sector Analyzer"
+    }
+    fn default_priority(&self) -> u8 {
+        3
+    }
+    fn execute(&self, jerry: &mut Jerry, world: &mut World, mission_index: usize) -> Result<MissionOutcome, JerryStatus> {
+        let region = *jerry.missions.get(mission_index)
+            .and_then(Mission::region)
+            .ok_or(JerryStatus::MissionExecutionError)?;
+        analyze_and_plan(jerry, world, &region)?;
+        if let Some(mission) = jerry.missions.get_mut(mission_index){
+            mission.status = Completed;
+        }
+        Ok(MissionOutcome::Completed)
+    }
+}
+
 pub fn new_sector_analyzer(spatial_index: usize, world_dim: usize) -> Mission {
     let (tl, br) = get_tl_and_br_from_spatial_index(spatial_index, world_dim);
-    Mission {
-        name: "Sector Analyzer".to_string(),
-        status: Active,
-        additional_data: Some(Box::new(ActiveRegion {
-            top_left: tl,
-            bottom_right: br,
-            spatial_index,
-        })),
+    Mission::new(Active, MissionData::SectorAnalyzer(ActiveRegion {
+        top_left: tl,
+        bottom_right: br,
+        spatial_index,
+    }))
+}
+//analyzes the region and plans the road builders for it
+//unless all of its nodes are already on the road
+pub(crate) fn analyze_and_plan(jerry: &mut Jerry, world: &mut World, region: &ActiveRegion) -> Result<(), JerryStatus>{
+    let (tl, br) = (region.top_left, region.bottom_right);
+    let sector_data = analyzer_execute(world, tl, br, &mut jerry.rng)?;
+    log::debug!(target: logging::SECTOR_ANALYZER, "Sector data: {:?}", sector_data);
+    //here we check if the sector is fully explored
+    if let None = sector_data.nodes.iter().find(|node|
+        !jerry.road_tiles.contains(&ChartedCoordinate(node.0, node.1))){
+        log::info!(target: logging::SECTOR_ANALYZER, "Sector {} is fully explored", region.spatial_index);
+        return Ok(());
     }
+    jerry.sector_nodes = sector_data.nodes.clone();
+    generate_road_builders(jerry, world, sector_data);
+    jerry.active_region.top_left = tl;
+    jerry.active_region.bottom_right = br;
+    Ok(())
 }
 /*Goals: to determine if the sector is random or not
     to get the amount of the resources in the sector
@@ -36,12 +76,12 @@ pub fn new_sector_analyzer(spatial_index: usize, world_dim: usize) -> Mission {
         markets and banks
 
 */
-pub fn analyzer_execute(world: &mut World, tl: (usize, usize), br: (usize, usize)) -> SectorData{
-    let robot_map = robot_map(world).unwrap();
-    let sector_map = robot_map_slice(&robot_map,tl, br).unwrap();
+pub fn analyzer_execute(world: &mut World, tl: (usize, usize), br: (usize, usize), rng: &mut impl Rng) -> Result<SectorData, JerryStatus>{
+    let robot_map = robot_map(world).ok_or(JerryStatus::MissionExecutionError)?;
+    let sector_map = robot_map_slice(&robot_map,tl, br).ok_or(JerryStatus::MissionExecutionError)?;
     let sector_resources = sector_collectable(&sector_map, tl);
     let mountain_tiles = count_mountain_tiles(&sector_map);
-    let is_random = is_content_random(&sector_map);
+    let is_random = is_content_random(&sector_map, rng);
     let mut zone = find_largest_connected_subset(&sector_map);
     //turn the relative coordinates into absolute
     for coord in zone.iter_mut(){
@@ -54,16 +94,16 @@ pub fn analyzer_execute(world: &mut World, tl: (usize, usize), br: (usize, usize
             let amount = sector_resources.2;
             nodes.push(get_weighted_centroid(&hs.iter().map(|(i, j, _)| (*i, *j, 1)).collect(), amount));
         }
-        return SectorData {
+        return Ok(SectorData {
             resources: sector_resources.0,
             mountain_tiles,
             is_random: true,
             nodes: nodes,
-        };
+        });
     }
     let (eps, min_points) = (6.0, 80);
     let model = Model::new(eps, min_points);
-    let model_inputs = map_into_db_input(&sector_map);
+    let model_inputs = map_into_db_input(&sector_map, rng);
     let classification = model.run(&model_inputs);
     let mut clusters =  HashMap::new();
     for el in classification.iter(){
@@ -76,21 +116,21 @@ pub fn analyzer_execute(world: &mut World, tl: (usize, usize), br: (usize, usize
            }
         }
     }
-    println!("Clusters len: {}", clusters.len());
+    log::debug!(target: logging::SECTOR_ANALYZER, "Clusters len: {}", clusters.len());
     for (c, cores) in clusters.iter(){
         let mut centroid = get_centroid(cores);
         //turn the relative coordinates into absolute
         centroid.0 += tl.0;
         centroid.1 += tl.1;
-        println!("Centroid of the cluster is {:?}", centroid);
+        log::trace!(target: logging::SECTOR_ANALYZER, "Centroid of the cluster is {:?}", centroid);
         nodes.push(centroid);
     }
-    SectorData {
+    Ok(SectorData {
         resources: sector_resources.0,
         mountain_tiles,
         is_random: false,
         nodes,
-    }
+    })
 
 
 }
@@ -138,8 +178,8 @@ pub struct SectorData{
     pub nodes: Vec<(usize, usize)>,
 }
 
-pub fn is_content_random(sector: &Vec<Vec<Option<Tile>>>) -> bool{
-    let m = morans_i(sector);
+pub fn is_content_random(sector: &Vec<Vec<Option<Tile>>>, rng: &mut impl Rng) -> bool{
+    let m = morans_i(sector, rng);
     if m < 0.1{
         return true;
     }
@@ -0,0 +1,126 @@
+use robotics_lib::event::events::Event;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::Content;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::inspector::TileReport;
+use crate::map_tracker::MapDiff;
+use crate::mission_control::{MissionCommand, MissionSummary};
+use crate::overlay::AiOverlay;
+use crate::snapshot::RobotSnapshot;
+use crate::telemetry::TelemetrySample;
+
+/*
+    Receives the state updates produced by the robot.
+    The frontend implements it on top of the Bounce atoms, while the headless runner
+    does not need any of them, so every method does nothing by default.
+ */
+pub(crate) trait StateSink {
+    //only the tiles of the robot map that changed since the last call, size is the side of the map
+    fn map_changed(&mut self, _size: usize, _diff: &MapDiff) {}
+    fn robot_moved(&mut self, _coord: (usize, usize)) {}
+    fn energy_changed(&mut self, _energy: usize) {}
+    fn backpack_changed(&mut self, _size: usize, _content: &HashMap<Content, usize>) {}
+    fn conditions_changed(&mut self, _conditions: &EnvironmentalConditions) {}
+    fn score_changed(&mut self, _score: f32) {}
+    fn event_received(&mut self, _event: &Event) {}
+    fn telemetry_sampled(&mut self, _sample: &TelemetrySample) {}
+    fn missions_changed(&mut self, _missions: &[MissionSummary]) {}
+    //asked at the start of every tick, before the scheduler runs
+    fn mission_commands(&mut self) -> Vec<MissionCommand> {
+        Vec::new()
+    }
+    fn tick_finished(&mut self, _tick: usize) {}
+    //asked at the end of every tick, if true the robot sends a snapshot of itself
+    fn wants_snapshot(&mut self) -> bool {
+        false
+    }
+    fn snapshot_taken(&mut self, _snapshot: RobotSnapshot) {}
+    //same for the internal state of the AI, collecting it is not free
+    fn wants_overlay(&mut self) -> bool {
+        false
+    }
+    fn overlay_changed(&mut self, _overlay: AiOverlay) {}
+    //tile picked in the frontend, the robot reports on it at the end of every tick
+    fn inspected_tile(&mut self) -> Option<(usize, usize)> {
+        None
+    }
+    fn tile_inspected(&mut self, _report: TileReport) {}
+}
+
+//drops every update
+pub(crate) struct NoopSink;
+
+impl StateSink for NoopSink {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum StateUpdate {
+    Map(MapDiff),
+    RobotMoved((usize, usize)),
+    Energy(usize),
+    Backpack { size: usize, content: HashMap<Content, usize> },
+    Conditions { time: String },
+    Score(f32),
+}
+
+/*
+    Keeps every update it receives, in order.
+    The updates are shared, so a clone can be given to Jerry and the other one inspected afterwards.
+ */
+#[derive(Clone, Default)]
+pub(crate) struct RecordingSink {
+    updates: Rc<RefCell<Vec<StateUpdate>>>,
+}
+
+impl RecordingSink {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+    pub(crate) fn updates(&self) -> Vec<StateUpdate> {
+        self.updates.borrow().clone()
+    }
+    fn record(&mut self, update: StateUpdate) {
+        self.updates.borrow_mut().push(update);
+    }
+}
+
+impl StateSink for RecordingSink {
+    fn map_changed(&mut self, _size: usize, diff: &MapDiff) {
+        self.record(StateUpdate::Map(diff.clone()));
+    }
+    fn robot_moved(&mut self, coord: (usize, usize)) {
+        self.record(StateUpdate::RobotMoved(coord));
+    }
+    fn energy_changed(&mut self, energy: usize) {
+        self.record(StateUpdate::Energy(energy));
+    }
+    fn backpack_changed(&mut self, size: usize, content: &HashMap<Content, usize>) {
+        self.record(StateUpdate::Backpack { size, content: content.clone() });
+    }
+    fn conditions_changed(&mut self, conditions: &EnvironmentalConditions) {
+        self.record(StateUpdate::Conditions { time: conditions.get_time_of_day_string() });
+    }
+    fn score_changed(&mut self, score: f32) {
+        self.record(StateUpdate::Score(score));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_sink_shares_updates(){
+        let recorder = RecordingSink::new();
+        let mut sink: Box<dyn StateSink> = Box::new(recorder.clone());
+        sink.robot_moved((1, 2));
+        sink.energy_changed(900);
+        sink.score_changed(3.5);
+        assert_eq!(
+            recorder.updates(),
+            vec![StateUpdate::RobotMoved((1, 2)), StateUpdate::Energy(900), StateUpdate::Score(3.5)]
+        );
+    }
+}
@@ -0,0 +1,289 @@
+use charting_tools::charted_coordinate::ChartedCoordinate;
+use robotics_lib::interface::{discover_tiles, look_at_sky, robot_map};
+use robotics_lib::energy::Energy;
+use robotics_lib::runner::Runnable;
+use robotics_lib::world::environmental_conditions::EnvironmentalConditions;
+use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::world_generator::Generator;
+use robotics_lib::world::World;
+use rand_chacha::ChaCha12Rng;
+use rust_and_furious_dynamo::dynamo::Dynamo;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::jerry::Jerry;
+use crate::logging;
+use crate::score::ScoreTracker;
+use crate::utils::{ActiveRegion, Mission};
+use crate::worldloader::GeneratedWorld;
+
+/*
+    Snapshot of a running simulation.
+    The robot state is taken by Jerry at the end of a tick, the world is the one made by the
+    generator at the start, updated with everything the robot knows about it. Since the robot can
+    only change the tiles it has seen, this is the world as it is when the snapshot is taken.
+ */
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct RobotSnapshot {
+    pub(crate) tick_counter: usize,
+    pub(crate) world_dim: usize,
+    pub(crate) active_region: ActiveRegion,
+    #[serde(with = "charted")]
+    pub(crate) road_tiles: HashSet<ChartedCoordinate>,
+    pub(crate) missions: VecDeque<Mission>,
+    pub(crate) energy: usize,
+    pub(crate) energy_consumed: usize,
+    pub(crate) energy_recharged: usize,
+    pub(crate) position: (usize, usize),
+    pub(crate) backpack_size: usize,
+    pub(crate) backpack: HashMap<Content, usize>,
+    pub(crate) seed: u64,
+    pub(crate) rng: ChaCha12Rng,
+    pub(crate) known_map: Vec<Vec<Option<Tile>>>,
+    // the whole clock and the day of the forecast, the weather goes on from there
+    pub(crate) conditions: EnvironmentalConditions,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct SavedWorld {
+    pub(crate) tiles: Vec<Vec<Tile>>,
+    pub(crate) max_score: f32,
+    pub(crate) score_map: Option<HashMap<Content, f32>>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) struct Snapshot {
+    pub(crate) robot: RobotSnapshot,
+    pub(crate) world: SavedWorld,
+    // kept by the frontend, not by the robot
+    pub(crate) score: ScoreTracker,
+}
+
+impl Snapshot {
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        bincode::serialize(self).map_err(|e| e.to_string())
+    }
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Snapshot, String> {
+        bincode::deserialize(bytes).map_err(|e| e.to_string())
+    }
+}
+
+pub(crate) fn take_snapshot(jerry: &Jerry, world: &World) -> RobotSnapshot {
+    let backpack = jerry.get_backpack();
+    RobotSnapshot {
+        tick_counter: jerry.tick_counter,
+        world_dim: jerry.world_dim,
+        active_region: jerry.active_region,
+        road_tiles: jerry.road_tiles.clone(),
+        missions: jerry.missions.clone(),
+        energy: jerry.get_energy().get_energy_level(),
+        energy_consumed: jerry.energy_consumed,
+        energy_recharged: jerry.energy_recharged,
+        position: (jerry.get_coordinate().get_row(), jerry.get_coordinate().get_col()),
+        backpack_size: backpack.get_size(),
+        backpack: backpack.get_contents().clone(),
+        seed: jerry.seed,
+        rng: jerry.rng.clone(),
+        known_map: robot_map(world).unwrap_or_default(),
+        conditions: look_at_sky(world),
+    }
+}
+
+// tiles discovered at once, the robot is given the energy for each call
+const REDISCOVER_CHUNK: usize = 50;
+
+/*
+    The world made from a snapshot starts with every tile hidden, so on its first tick the
+    restored robot discovers again the tiles it knew. Discovering costs energy and sends energy
+    events, the energy and its totals are put back as they were afterwards.
+ */
+pub(crate) fn rediscover_known_tiles(jerry: &mut Jerry, world: &mut World) {
+    let tiles = std::mem::take(&mut jerry.rediscover);
+    let (energy, consumed, recharged) = (jerry.get_energy().get_energy_level(), jerry.energy_consumed, jerry.energy_recharged);
+    for (index, chunk) in tiles.chunks(REDISCOVER_CHUNK).enumerate() {
+        *jerry.get_energy_mut() = Dynamo::update_energy();
+        if let Err(e) = discover_tiles(jerry, world, chunk) {
+            log::warn!(target: logging::JERRY, "{} known tiles not discovered again: {:?}", tiles.len() - index * REDISCOVER_CHUNK, e);
+            break;
+        }
+    }
+    *jerry.get_energy_mut() = Energy::new(energy);
+    jerry.energy_consumed = consumed;
+    jerry.energy_recharged = recharged;
+    jerry.sink.energy_changed(energy);
+    jerry.map_tracker.resync();
+}
+
+// generates the world saved in a snapshot, with the robot where it was
+pub(crate) struct SnapshotGenerator {
+    snapshot: Snapshot,
+}
+
+impl SnapshotGenerator {
+    pub(crate) fn new(snapshot: Snapshot) -> Self {
+        Self { snapshot }
+    }
+}
+
+impl Generator for SnapshotGenerator {
+    fn gen(&mut self) -> GeneratedWorld {
+        let world = &self.snapshot.world;
+        let robot = &self.snapshot.robot;
+        let mut tiles = world.tiles.clone();
+        for (i, row) in robot.known_map.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if let (Some(tile), Some(cell)) = (tile, tiles.get_mut(i).and_then(|r| r.get_mut(j))) {
+                    *cell = tile.clone();
+                }
+            }
+        }
+        //the clock and the forecast go on from where they were
+        (tiles, robot.position, robot.conditions.clone(), world.max_score, world.score_map.clone())
+    }
+}
+
+// serde for collections of ChartedCoordinate, which does not implement it
+pub(crate) mod charted {
+    use charting_tools::charted_coordinate::ChartedCoordinate;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(crate) fn serialize<'a, S, C>(coords: &'a C, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        &'a C: IntoIterator<Item = &'a ChartedCoordinate>,
+    {
+        serializer.collect_seq(coords.into_iter().map(|coord| (coord.0, coord.1)))
+    }
+
+    pub(crate) fn deserialize<'de, D, C>(deserializer: D) -> Result<C, D::Error>
+    where
+        D: Deserializer<'de>,
+        C: FromIterator<ChartedCoordinate>,
+    {
+        let coords: Vec<(usize, usize)> = Vec::deserialize(deserializer)?;
+        Ok(coords.into_iter().map(|(row, col)| ChartedCoordinate(row, col)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scheduler::SchedulerPolicy;
+    use crate::sink::{NoopSink, StateSink};
+    use crate::worldloader::WorldgeneratorUnwrap;
+    use robotics_lib::runner::Runner;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    //asks for a snapshot at every tick and keeps the last one
+    struct SnapshotSink {
+        taken: Rc<RefCell<Option<RobotSnapshot>>>,
+    }
+
+    impl StateSink for SnapshotSink {
+        fn wants_snapshot(&mut self) -> bool {
+            true
+        }
+        fn snapshot_taken(&mut self, snapshot: RobotSnapshot) {
+            *self.taken.borrow_mut() = Some(snapshot);
+        }
+    }
+
+    //runs the ticks and gives the snapshot of the last one
+    fn run(runner: &mut Runner, taken: &Rc<RefCell<Option<RobotSnapshot>>>, ticks: usize) -> RobotSnapshot {
+        for _ in 0..ticks {
+            let _ = runner.game_tick();
+        }
+        taken.borrow_mut().take().unwrap()
+    }
+
+    fn run_and_snapshot(ticks: usize) -> Snapshot {
+        let taken = Rc::new(RefCell::new(None));
+        let sink = SnapshotSink { taken: taken.clone() };
+        let jerry = Jerry::new(Box::new(sink), SchedulerPolicy::Alternating.build(), 5);
+        let generator = WorldgeneratorUnwrap::init(false, None);
+        let world = generator.saved_world().unwrap();
+        let mut runner = Runner::new(Box::new(jerry), &mut WorldgeneratorUnwrap::init(false, None)).unwrap();
+        let robot = run(&mut runner, &taken, ticks);
+        Snapshot { robot, world, score: ScoreTracker::default() }
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip(){
+        let snapshot = run_and_snapshot(10);
+        assert_eq!(snapshot.robot.tick_counter, 10);
+        let restored = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+        assert_eq!(restored.robot.tick_counter, snapshot.robot.tick_counter);
+        assert_eq!(restored.robot.position, snapshot.robot.position);
+        assert_eq!(restored.robot.road_tiles, snapshot.robot.road_tiles);
+        assert_eq!(restored.robot.missions.len(), snapshot.robot.missions.len());
+        assert_eq!(restored.world, snapshot.world);
+    }
+
+    #[test]
+    fn test_resume_from_snapshot(){
+        let snapshot = run_and_snapshot(10);
+        let mut generator = SnapshotGenerator::new(snapshot.clone());
+        let (tiles, robot_pos, _, _, _) = generator.gen();
+        assert_eq!(robot_pos, snapshot.robot.position);
+        for (i, row) in snapshot.robot.known_map.iter().enumerate() {
+            for (j, tile) in row.iter().enumerate() {
+                if let Some(tile) = tile {
+                    assert_eq!(&tiles[i][j], tile);
+                }
+            }
+        }
+
+        let jerry = Jerry::restore(Box::new(NoopSink), SchedulerPolicy::Alternating.build(), &snapshot.robot);
+        assert_eq!(jerry.tick_counter, 10);
+        assert_eq!(jerry.get_energy().get_energy_level(), snapshot.robot.energy);
+        let mut runner = Runner::new(Box::new(jerry), &mut generator).unwrap();
+        for _ in 0..5 {
+            let _ = runner.game_tick();
+        }
+    }
+    #[test]
+    fn test_restored_run_matches_the_original(){
+        let taken = Rc::new(RefCell::new(None));
+        let jerry = Jerry::new(Box::new(SnapshotSink { taken: taken.clone() }), SchedulerPolicy::Alternating.build(), 5);
+        let world = WorldgeneratorUnwrap::init(false, None).saved_world().unwrap();
+        let mut runner = Runner::new(Box::new(jerry), &mut WorldgeneratorUnwrap::init(false, None)).unwrap();
+        let snapshot = Snapshot { robot: run(&mut runner, &taken, 10), world, score: ScoreTracker::default() };
+        let original = run(&mut runner, &taken, 5);
+
+        //the snapshot goes through the bytes, like one saved in the browser
+        let snapshot = Snapshot::from_bytes(&snapshot.to_bytes().unwrap()).unwrap();
+        let restored_taken = Rc::new(RefCell::new(None));
+        let sink = SnapshotSink { taken: restored_taken.clone() };
+        let jerry = Jerry::restore(Box::new(sink), SchedulerPolicy::Alternating.build(), &snapshot.robot);
+        assert_eq!(jerry.get_backpack().get_contents(), &snapshot.robot.backpack);
+        let mut runner = Runner::new(Box::new(jerry), &mut SnapshotGenerator::new(snapshot)).unwrap();
+        let restored = run(&mut runner, &restored_taken, 5);
+
+        assert_eq!(restored.tick_counter, original.tick_counter);
+        assert_eq!(restored.position, original.position);
+        assert_eq!(restored.energy, original.energy);
+        assert_eq!(restored.energy_consumed, original.energy_consumed);
+        assert_eq!(restored.energy_recharged, original.energy_recharged);
+        assert_eq!(restored.conditions.get_time_of_day_string(), original.conditions.get_time_of_day_string());
+        assert_eq!(restored.conditions.get_weather_condition(), original.conditions.get_weather_condition());
+        assert_eq!(restored.backpack, original.backpack);
+        assert_eq!(restored.missions.len(), original.missions.len());
+        //the same random choices were made
+        assert_eq!(restored.rng, original.rng);
+    }
+    #[test]
+    fn test_restored_robot_knows_the_map(){
+        let mut snapshot = run_and_snapshot(10);
+        //without missions the robot stays where it is and only discovers the known tiles again
+        snapshot.robot.missions.clear();
+        let taken = Rc::new(RefCell::new(None));
+        let jerry = Jerry::restore(Box::new(SnapshotSink { taken: taken.clone() }), SchedulerPolicy::Alternating.build(), &snapshot.robot);
+        let mut runner = Runner::new(Box::new(jerry), &mut SnapshotGenerator::new(snapshot.clone())).unwrap();
+        let restored = run(&mut runner, &taken, 1);
+
+        assert_eq!(restored.known_map, snapshot.robot.known_map);
+        assert_eq!(restored.energy, snapshot.robot.energy);
+        assert_eq!(restored.energy_consumed, snapshot.robot.energy_consumed);
+    }
+}
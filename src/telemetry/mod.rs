@@ -0,0 +1,210 @@
+use robotics_lib::runner::Runnable;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::jerry::Jerry;
+use crate::sink::StateSink;
+use crate::utils::MissionStatus;
+
+/*
+    Numbers describing how the run is going, taken at the end of every tick.
+    Two versions of the AI can be compared by running them on the same world and seed
+    and looking at how these change over the ticks.
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct TelemetrySample {
+    pub(crate) tick: usize,
+    pub(crate) energy: usize,
+    // totals since the robot started
+    pub(crate) energy_consumed: usize,
+    pub(crate) energy_recharged: usize,
+    // percent of the world the robot knows
+    pub(crate) discovered: f32,
+    pub(crate) frontier: usize,
+    pub(crate) paved: usize,
+    // missions in each status, in the order of MissionStatus::ALL
+    pub(crate) missions: [usize; 5],
+    // distance fields reused and computed since the robot started
+    pub(crate) path_hits: usize,
+    pub(crate) path_misses: usize,
+}
+
+pub(crate) fn take_sample(jerry: &Jerry, tick: usize) -> TelemetrySample {
+    let mut sample = TelemetrySample {
+        tick,
+        energy: jerry.get_energy().get_energy_level(),
+        energy_consumed: jerry.energy_consumed,
+        energy_recharged: jerry.energy_recharged,
+        path_hits: jerry.path_cache.hits(),
+        path_misses: jerry.path_cache.misses(),
+        ..TelemetrySample::default()
+    };
+    let tiles = jerry.world_dim * jerry.world_dim;
+    if tiles > 0 {
+        sample.discovered = jerry.map_tracker.discovered() as f32 * 100.0 / tiles as f32;
+    }
+    for mission in jerry.missions.iter() {
+        if let Some(explorer) = mission.explorer() {
+            sample.frontier += explorer.frontier.len();
+        }
+        if let Some(road_builder) = mission.road_builder() {
+            sample.paved += road_builder.paved().len();
+        }
+        if let Some(index) = MissionStatus::ALL.iter().position(|status| *status == mission.status) {
+            sample.missions[index] += 1;
+        }
+    }
+    sample
+}
+
+const CSV_HEADER: &'static str =
+    "tick,energy,energy_consumed,energy_recharged,discovered,frontier,paved,missions_new,missions_active,missions_paused,missions_completed,missions_failed,path_hits,path_misses";
+
+pub(crate) fn to_csv(samples: &[TelemetrySample]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for sample in samples {
+        csv.push_str(&format!(
+            "{},{},{},{},{:.2},{},{},{},{},{},{},{},{},{}\n",
+            sample.tick,
+            sample.energy,
+            sample.energy_consumed,
+            sample.energy_recharged,
+            sample.discovered,
+            sample.frontier,
+            sample.paved,
+            sample.missions[0],
+            sample.missions[1],
+            sample.missions[2],
+            sample.missions[3],
+            sample.missions[4],
+            sample.path_hits,
+            sample.path_misses,
+        ));
+    }
+    csv
+}
+
+/*
+    Samples drawn in the charts of the frontend, never more than twice the capacity.
+    When it is full every other sample is dropped and from then on only one tick out of the
+    doubled stride is kept, so a long run is still drawn from its start with few points.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct TelemetrySeries {
+    samples: Vec<TelemetrySample>,
+    capacity: usize,
+    stride: usize,
+    seen: usize,
+}
+
+impl TelemetrySeries {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            samples: Vec::new(),
+            capacity: capacity.max(1),
+            stride: 1,
+            seen: 0,
+        }
+    }
+    pub(crate) fn push(&mut self, sample: &TelemetrySample) {
+        if self.seen % self.stride == 0 {
+            self.samples.push(sample.clone());
+        }
+        self.seen += 1;
+        if self.samples.len() >= 2 * self.capacity {
+            let mut index = 0;
+            self.samples.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.stride *= 2;
+        }
+    }
+    pub(crate) fn samples(&self) -> &Vec<TelemetrySample> {
+        &self.samples
+    }
+    pub(crate) fn clear(&mut self) {
+        *self = Self::new(self.capacity);
+    }
+}
+
+//keeps every sample, the headless runner writes them to a csv file
+#[derive(Clone, Default)]
+pub(crate) struct TelemetryLog {
+    samples: Rc<RefCell<Vec<TelemetrySample>>>,
+}
+
+impl TelemetryLog {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+    pub(crate) fn samples(&self) -> Vec<TelemetrySample> {
+        self.samples.borrow().clone()
+    }
+}
+
+impl StateSink for TelemetryLog {
+    fn telemetry_sampled(&mut self, sample: &TelemetrySample) {
+        self.samples.borrow_mut().push(sample.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explorer::ExplorerData;
+    use crate::road_builder::new_road_builder;
+    use crate::scheduler::SchedulerPolicy;
+    use crate::sink::NoopSink;
+    use crate::utils::{Mission, MissionData};
+    use charting_tools::charted_coordinate::ChartedCoordinate;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_take_sample(){
+        let mut jerry = Jerry::new(Box::new(NoopSink), SchedulerPolicy::Alternating.build(), 0);
+        jerry.missions.push_back(Mission::new(MissionStatus::Active, MissionData::Explorer(ExplorerData {
+            frontier: vec![ChartedCoordinate(0, 1), ChartedCoordinate(1, 0)],
+            frontier_hs: HashSet::new(),
+            spatial_index: 0,
+            robot_moved: false,
+        })));
+        jerry.missions.push_back(new_road_builder(&vec![ChartedCoordinate(3, 3)]));
+        jerry.energy_consumed = 12;
+
+        let sample = take_sample(&jerry, 7);
+        assert_eq!(sample.tick, 7);
+        assert_eq!(sample.energy_consumed, 12);
+        assert_eq!(sample.frontier, 2);
+        assert_eq!(sample.paved, 0);
+        assert_eq!(sample.missions, [0, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_series_downsampled_from_the_start(){
+        let mut series = TelemetrySeries::new(50);
+        for tick in 0..1000 {
+            series.push(&TelemetrySample { tick, ..Default::default() });
+        }
+        let samples = series.samples();
+        assert!(samples.len() >= 50 && samples.len() < 100);
+        assert_eq!(samples[0].tick, 0);
+        //the kept ticks are evenly spaced
+        let stride = samples[1].tick - samples[0].tick;
+        assert!(samples.windows(2).all(|pair| pair[1].tick - pair[0].tick == stride));
+        assert!(samples.last().unwrap().tick + stride >= 1000);
+
+        series.clear();
+        assert!(series.samples().is_empty());
+    }
+
+    #[test]
+    fn test_to_csv(){
+        let sample = TelemetrySample { tick: 3, energy: 900, discovered: 12.5, missions: [1, 0, 0, 2, 0], path_hits: 4, path_misses: 1, ..Default::default() };
+        let csv = to_csv(&[sample]);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines[1], "3,900,0,0,12.50,0,0,1,0,0,2,0,4,1");
+    }
+}
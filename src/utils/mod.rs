@@ -1,29 +1,135 @@
-use std::any::Any;
 use std::hash::Hash;
+use serde::{Deserialize, Serialize};
 use robotics_lib::interface::Direction;
+use robotics_lib::runner::Runnable;
 use robotics_lib::utils::LibError;
 use robotics_lib::world::tile::Tile;
 use robotics_lib::world::World;
-use crate::explorer::explorer_execute;
-use crate::interface::Jerry;
-use crate::road_builder::road_builder_execute;
+use crate::explorer::{Explorer, ExplorerData};
+use crate::jerry::Jerry;
+use crate::road_builder::{RoadBuilder, RoadBuilderData, RoadBuilderError};
+use crate::scheduler::ScheduleContext;
+use crate::sector_analyzer::SectorAnalyzer;
+use crate::logging;
 
 //use crate::road_builder::{build_road, road_builder_execute};
 
 pub(crate) const SECTOR_DIMENSION: usize = 70;
+
+//a type of mission the robot can execute
+//new mission types implement this trait and add their state to MissionData,
+//the scheduler only talks to missions through it
+pub(crate) trait MissionKind {
+    fn name(&self) -> &'static str;
+    //missions with a higher priority are preferred by the priority scheduler
+    fn default_priority(&self) -> u8 {
+        1
+    }
+    //missions that use the dynamo when the energy is low, the only ones run below a low energy threshold
+    fn recharges(&self) -> bool {
+        false
+    }
+    fn execute(&self, jerry: &mut Jerry, world: &mut World, mission_index: usize) -> Result<MissionOutcome, JerryStatus>;
+}
+
+//typed state of every mission kind
+#[derive(Clone, Serialize, Deserialize)]
+pub(crate) enum MissionData {
+    Explorer(ExplorerData),
+    RoadBuilder(RoadBuilderData),
+    SectorAnalyzer(ActiveRegion),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub(crate) struct Mission {
-    pub(crate) name: String,
     pub(crate) status: MissionStatus,
-    pub(crate) additional_data: Option<Box<dyn Any>>,
+    pub(crate) priority: u8,
+    pub(crate) data: MissionData,
+    //given by Jerry once the mission is in the queue, so the frontend can point at it
+    pub(crate) id: Option<usize>,
+    //the status before the user paused the mission, the schedulers leave held missions alone
+    pub(crate) held: Option<MissionStatus>,
+}
+
+impl Mission {
+    pub(crate) fn new(status: MissionStatus, data: MissionData) -> Mission {
+        let mut mission = Mission { status, priority: 0, data, id: None, held: None };
+        mission.priority = mission.kind().default_priority();
+        mission
+    }
+    //the kind is derived from the state, so the two can never disagree
+    pub(crate) fn kind(&self) -> &'static dyn MissionKind {
+        match self.data {
+            MissionData::Explorer(_) => &Explorer,
+            MissionData::RoadBuilder(_) => &RoadBuilder,
+            MissionData::SectorAnalyzer(_) => &SectorAnalyzer,
+        }
+    }
+    pub(crate) fn explorer(&self) -> Option<&ExplorerData> {
+        match &self.data {
+            MissionData::Explorer(data) => Some(data),
+            _ => None,
+        }
+    }
+    pub(crate) fn explorer_mut(&mut self) -> Option<&mut ExplorerData> {
+        match &mut self.data {
+            MissionData::Explorer(data) => Some(data),
+            _ => None,
+        }
+    }
+    pub(crate) fn road_builder(&self) -> Option<&RoadBuilderData> {
+        match &self.data {
+            MissionData::RoadBuilder(data) => Some(data),
+            _ => None,
+        }
+    }
+    pub(crate) fn road_builder_mut(&mut self) -> Option<&mut RoadBuilderData> {
+        match &mut self.data {
+            MissionData::RoadBuilder(data) => Some(data),
+            _ => None,
+        }
+    }
+    pub(crate) fn region(&self) -> Option<&ActiveRegion> {
+        match &self.data {
+            MissionData::SectorAnalyzer(region) => Some(region),
+            _ => None,
+        }
+    }
+    pub(crate) fn is_explorer(&self) -> bool {
+        self.explorer().is_some()
+    }
+    pub(crate) fn is_road_builder(&self) -> bool {
+        self.road_builder().is_some()
+    }
+    pub(crate) fn is_held(&self) -> bool {
+        self.held.is_some()
+    }
+}
+
+//what happened to the mission during one execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MissionOutcome {
+    InProgress,
+    Completed,
 }
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub(crate) enum MissionStatus{
     New,
     Active,
     Paused,
     Completed,
+    //stopped by an error the mission could not recover from, the robot carries on with the others
+    Failed,
 }
-#[derive(Debug)]
+impl MissionStatus {
+    pub(crate) const ALL: [MissionStatus; 5] = [MissionStatus::New, MissionStatus::Active, MissionStatus::Paused, MissionStatus::Completed, MissionStatus::Failed];
+
+    //completed and failed missions are never executed again
+    pub(crate) fn is_finished(&self) -> bool {
+        *self == MissionStatus::Completed || *self == MissionStatus::Failed
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub(crate) struct ActiveRegion{
     pub(crate) top_left: (usize, usize),
     pub(crate) bottom_right: (usize, usize),
@@ -53,91 +159,50 @@ pub(crate) enum JerryStatus{
     LowEnergyWarning,
     Common(LibError),
     ExpectingNiceWeather,
-    CallingNextTick,
+    RoadBuilderFailed(RoadBuilderError),
 }
-pub(crate) fn execute_mission (jerry: &mut Jerry, world: &mut World){
-
-    //after every 2 completed explorers, need to build the roads
-    //so if the # of completed explorers is even, set other active explorers to pause and continue with the road builders
-    let mut completed_explorers = 0;
-    let mut active_explorers = 0;
-    let mut waiting_explorers = 0;
-    let mut completed_road_builders = 0;
-    let mut waiting_road_builders = 0;
-    let mut active_road_builders = 0;
-    for mission in jerry.missions.iter(){
-        match mission.name.as_str(){
-            | "Explore" => {
-                match mission.status{
-                    MissionStatus::Completed => completed_explorers += 1,
-                    MissionStatus::Paused => waiting_explorers += 1,
-                    MissionStatus::Active  | MissionStatus::New => active_explorers += 1,
-                    
-                }
-            }
-            | "Road Builder" => {
-                match  mission.status{
-                    MissionStatus::Completed => completed_road_builders += 1,
-                    MissionStatus::Paused => waiting_road_builders += 1,
-                    MissionStatus::Active  | MissionStatus::New => active_road_builders += 1,
-                    
-                }
-            }
-            | _ => {}
+impl JerryStatus {
+    //a failure ends the mission, the other statuses only stop it for this tick
+    pub(crate) fn is_failure(&self) -> bool {
+        match self {
+            JerryStatus::MissionExecutionError | JerryStatus::Common(_) | JerryStatus::RoadBuilderFailed(_) => true,
+            JerryStatus::LowEnergyWarning | JerryStatus::ExpectingNiceWeather => false,
         }
     }
-    println!("Act Exp: {} Wait Exp: {} Comp Exp: {} Act RB: {} Wait RB: {} Comp RB {}", 
-    active_explorers, waiting_explorers, completed_explorers, active_road_builders, waiting_road_builders, completed_road_builders);
-    //after every 2 completed explorers, set road builders to active and pause the active explorers
-    if (completed_explorers > 0 && completed_explorers % 2 == 0) && waiting_road_builders > 0{
-        for mission in jerry.missions.iter_mut(){
-            if mission.name == "Explore" && (mission.status == MissionStatus::Active || mission.status == MissionStatus::New){
-                mission.status = MissionStatus::Paused;
-            }
-            if mission.name == "Road Builder" && mission.status == MissionStatus::Paused{
-                mission.status = MissionStatus::Active;
-            }
-        }
+}
+pub(crate) fn execute_mission (jerry: &mut Jerry, world: &mut World){
+
+    //the scheduler picks the mission, preempting the others if its policy says so
+    let context = ScheduleContext { energy: jerry.get_energy().get_energy_level() };
+    let selected = jerry.scheduler.schedule(&mut jerry.missions, &context);
+    let Some(index) = selected else {
+        log::debug!(target: logging::SCHEDULER, "I got nothing to do!");
+        return;
+    };
+    let Some(mission) = jerry.missions.get_mut(index) else {
+        return;
+    };
+    if mission.status == MissionStatus::New{
+        mission.status = MissionStatus::Active;
     }
-    //after every 2 completed builders set explorers to active and pause the active builders
-    if waiting_explorers > 0 &&  active_road_builders == 0{
-        for mission in jerry.missions.iter_mut(){
-            if mission.name == "Explore" && mission.status == MissionStatus::Paused{
-                mission.status = MissionStatus::Active;
-            }
-            if mission.name == "Road Builder" && mission.status == MissionStatus::Active{
-                mission.status = MissionStatus::Paused;
+    let kind = mission.kind();
+    let outcome = kind.execute(jerry, world, index);
+    match outcome {
+        Err(status) if status.is_failure() => {
+            log::error!(target: logging::SCHEDULER, "Mission {} failed: {:?}", kind.name(), status);
+            if let Some(mission) = jerry.missions.get_mut(index){
+                mission.status = MissionStatus::Failed;
             }
         }
+        _ => log::debug!(target: logging::SCHEDULER, "Mission {} {:?}", kind.name(), outcome),
     }
-    let mission = jerry.missions.iter_mut().enumerate().find(|(_i, mission)| 
-    (mission.status == MissionStatus::Active) || (mission.status == MissionStatus::New));
-    if mission.is_none() {
-        println!("I got nothing to do!");
-    }
-    if let Some((index, mission)) = mission{
-        match mission.name.as_str(){
-            | "Explore" => {
-                if mission.status == MissionStatus::New{
-                    mission.status = MissionStatus::Active;
-                }
-                println!("Mission Explorer {:?}", explorer_execute(jerry, world, index));
-            }
-            | "Road Builder" => {
-                if mission.status == MissionStatus::New{
-                    mission.status = MissionStatus::Active;
-                }
-                jerry.active_region.top_left = (jerry.world_dim - 1, jerry.world_dim - 1);
-                jerry.active_region.bottom_right = (0, 0);
-                println!("Mission Road Builder {:?}", road_builder_execute(jerry, world, index));
-            }
-
-            | _ => {}
-        }
+    //the path belonged to the mission that just ended
+    if jerry.missions.get(index).map_or(true, |mission| mission.status.is_finished()) {
+        jerry.current_path = None;
     }
 }
 pub(crate) fn get_world_dimension(world: &mut World) -> usize{
-    println!("{:?}", world.get_discoverable());
+    log::debug!(target: logging::JERRY, "Discoverable tiles {}", world.get_discoverable());
     ((world.get_discoverable() as f64 / 3.0 - 1.0) * 10.0).sqrt() as usize
 }
 pub(crate) fn calculate_spatial_index(row: usize, col: usize, size: usize) -> usize {
@@ -163,7 +228,7 @@ pub(crate) fn get_tl_and_br_from_spatial_index(spatial_index: usize, size: usize
     //first calculate the default values
     let top_left = (section_index_row * num_rows_per_section, section_index_col * num_cols_per_section);
     let mut bottom_right = (top_left.0 + num_rows_per_section - 1, top_left.1 + num_cols_per_section - 1);
-    println!("{:?}", (top_left, bottom_right));
+    log::trace!(target: logging::SECTOR_ANALYZER, "Sector {} bounds {:?}", spatial_index, (top_left, bottom_right));
     if size % SECTOR_DIMENSION == 0 {
         return (top_left, bottom_right);
     }
@@ -211,4 +276,45 @@ pub(crate) fn get_direction(from: (usize, usize), to: (usize, usize)) -> Option<
         }
     }
     None
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use super::*;
+    use crate::resources::ResourceCollectorError;
+
+    #[test]
+    fn test_mission_kind_follows_data(){
+        let explorer = Mission::new(MissionStatus::New, MissionData::Explorer(ExplorerData{
+            frontier: Vec::new(),
+            frontier_hs: HashSet::new(),
+            spatial_index: 3,
+            robot_moved: false,
+        }));
+        assert_eq!(explorer.kind().name(), "Explore");
+        assert!(explorer.is_explorer());
+        assert!(explorer.road_builder().is_none());
+        assert_eq!(explorer.explorer().unwrap().spatial_index, 3);
+
+        let analyzer = Mission::new(MissionStatus::Active, MissionData::SectorAnalyzer(ActiveRegion{
+            top_left: (0, 0),
+            bottom_right: (69, 69),
+            spatial_index: 0,
+        }));
+        assert_eq!(analyzer.kind().name(), "Sector Analyzer");
+        assert!(!analyzer.is_explorer() && !analyzer.is_road_builder());
+        assert_eq!(analyzer.region().unwrap().bottom_right, (69, 69));
+    }
+
+    #[test]
+    fn test_terminal_statuses(){
+        assert!(JerryStatus::MissionExecutionError.is_failure());
+        assert!(JerryStatus::Common(LibError::NotEnoughEnergy).is_failure());
+        assert!(JerryStatus::RoadBuilderFailed(RoadBuilderError::MapUnavailable).is_failure());
+        //the resource errors reach the scheduler through the road builder
+        let resources = RoadBuilderError::Resources(ResourceCollectorError::NoWayToDispose);
+        assert!(JerryStatus::RoadBuilderFailed(resources).is_failure());
+        assert!(!JerryStatus::LowEnergyWarning.is_failure());
+        assert!(!JerryStatus::ExpectingNiceWeather.is_failure());
+    }
+}
@@ -1,10 +1,15 @@
 use egui::Color32;
-// use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
-// use robotics_lib::world::tile::TileType;
-// use robotics_lib::world::tile::{Content, Tile};
+use robotics_lib::world::environmental_conditions::{EnvironmentalConditions, WeatherType};
+use robotics_lib::world::tile::TileType;
+use robotics_lib::world::tile::{Content, Tile};
 use serde::{Deserialize, Serialize};
-// use std::collections::HashMap;
-// use std::path::PathBuf;
+use std::collections::HashMap;
+
+use crate::logging;
+use crate::snapshot::SavedWorld;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
 
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -52,84 +57,131 @@ impl Grid {
     }
 }
 
+// world used when no file is given, the browser cannot read from a path
 const WORLD_DATA: &'static [u8] = include_bytes!("worlds/world_w_o_content.bin");
 
-pub(crate) fn load_as_grid(grid: &mut Grid, _path: PathBuf) {
-    // let msg = JsValue::from(format!("LOADER {:?}", WORLD_DATA));
-    // info!("{}", msg.as_string().unwrap());
-    let deserialized: Grid = bincode::deserialize(WORLD_DATA)
-        .expect("Failed to read file. The file is not in the correct format. The only way to create a world is with the gui, maybe you tried doing it manually or you tried to load the wrong file.");
-    *grid = deserialized;
-}
-
-
-
-pub(crate) fn load(
-    path: PathBuf,
-) -> (
+// what a Generator has to return
+pub type GeneratedWorld = (
     Vec<Vec<Tile>>,
     (usize, usize),
     EnvironmentalConditions,
     f32,
     Option<HashMap<Content, f32>>,
-) {
-    
-    let mut grid = Grid::new(1);
-    load_as_grid(&mut grid, path);
-
-    let mut out = vec![
-        vec![
-            Tile {
-                tile_type: TileType::Grass,
-                elevation: 0,
-                content: Content::None,
-            };
-            grid.size
-        ];
-        grid.size
-    ];
+);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldLoadError {
+    Io(String),
+    Format(String),
+    MissingTileType { row: usize, col: usize },
+    EmptyWeather,
+    Conditions(String),
+    // the map has to be square and not empty
+    Shape(String),
+    RobotOutOfBounds { row: usize, col: usize, size: usize },
+}
+
+impl fmt::Display for WorldLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldLoadError::Io(e) => write!(f, "Failed to read the world file: {}", e),
+            WorldLoadError::Format(e) => write!(f, "The file is not a world made with the gui: {}", e),
+            WorldLoadError::MissingTileType { row, col } => write!(f, "The tile at ({}, {}) has no tile type", row, col),
+            WorldLoadError::EmptyWeather => write!(f, "The world has no weather forecast"),
+            WorldLoadError::Conditions(e) => write!(f, "Invalid environmental conditions: {}", e),
+            WorldLoadError::Shape(e) => write!(f, "The map has the wrong shape: {}", e),
+            WorldLoadError::RobotOutOfBounds { row, col, size } => write!(f, "The robot at ({}, {}) is outside the {}x{} map", row, col, size, size),
+        }
+    }
+}
+
+impl std::error::Error for WorldLoadError {}
+
+pub(crate) fn parse_grid(bytes: &[u8]) -> Result<Grid, WorldLoadError> {
+    bincode::deserialize(bytes).map_err(|e| WorldLoadError::Format(e.to_string()))
+}
+
+pub(crate) fn load_as_grid(path: &Path) -> Result<Grid, WorldLoadError> {
+    let bytes = fs::read(path).map_err(|e| WorldLoadError::Io(format!("{}: {}", path.display(), e)))?;
+    parse_grid(&bytes)
+}
+
+//the runner indexes the map without checking, so a bad shape has to be caught here
+fn check_shape(grid: &Grid) -> Result<(), WorldLoadError> {
+    let size = grid.cells.len();
+    if size == 0 {
+        return Err(WorldLoadError::Shape(String::from("no rows")));
+    }
+    if let Some((i, row)) = grid.cells.iter().enumerate().find(|(_, row)| row.len() != size) {
+        return Err(WorldLoadError::Shape(format!("row {} has {} tiles instead of {}", i, row.len(), size)));
+    }
+    let (row, col) = grid.robot_pos;
+    if row >= size || col >= size {
+        return Err(WorldLoadError::RobotOutOfBounds { row, col, size });
+    }
+    Ok(())
+}
+
+pub(crate) fn grid_to_world(grid: Grid) -> Result<GeneratedWorld, WorldLoadError> {
+    check_shape(&grid)?;
+    let mut out = Vec::with_capacity(grid.cells.len());
     for (i, row) in grid.cells.iter().enumerate() {
+        let mut out_row = Vec::with_capacity(row.len());
         for (j, tile) in row.iter().enumerate() {
-            out[i][j] = Tile {
-                tile_type: tile.tile_type.unwrap(), // tile type is not empty, otherwise I cannot call load
+            let Some(tile_type) = tile.tile_type else {
+                return Err(WorldLoadError::MissingTileType { row: i, col: j });
+            };
+            out_row.push(Tile {
+                tile_type,
                 elevation: tile.elevation,
                 content: tile.content.clone(),
-            };
+            });
         }
+        out.push(out_row);
+    }
+    if grid.weather.is_empty() {
+        return Err(WorldLoadError::EmptyWeather);
     }
-    (
-        out,
-        grid.robot_pos,
-        EnvironmentalConditions::new(&grid.weather, 15, 12).unwrap(), // weather forecast is never empty, ensured by the gui
-        grid.max_score,
-        None,
-    )
+    let conditions = EnvironmentalConditions::new(&grid.weather, 15, 12)
+        .map_err(|e| WorldLoadError::Conditions(format!("{:?}", e)))?;
+    Ok((out, grid.robot_pos, conditions, grid.max_score, None))
+}
+
+// where the world comes from
+enum WorldSource {
+    Embedded,
+    Path(PathBuf),
+    Bytes(Vec<u8>),
 }
 
 /// This struct is the world generator, by .unwrap().unwrap().unwrap() !
 pub struct WorldgeneratorUnwrap {
     gui_start: bool,
-    path: Option<PathBuf>,
+    source: WorldSource,
     score_map: Option<HashMap<Content, f32>>,
 }
 
 impl WorldgeneratorUnwrap {
     /// This function creates an instance of our world generator
     /// # Arguments
-    /// * `gui_start` - if true, the gui will start
-    /// * `path` - if Some(path), the world will be loaded from this path, otherwise the world will
-    /// be loaded from the default path, which is "world.bin", in the parent folder of the "src"
-    /// binary directory.
+    /// * `gui_start` - the gui is not supported anymore, if true a warning is logged
+    /// * `path` - if Some(path), the world will be loaded from this path, otherwise the world
+    /// embedded in the crate will be used.
     /// # Remarks
-    /// If you choose to start the gui, the simulation will start right after you close it, loading
-    /// the path specified here (or "world.bin").
-    /// # Suggestions
-    /// Compile with `cargo run --release`. Egui really likes to be compiled with optimizations,
-    /// and it will be much faster.
+    /// The world is only read when the runner asks for it, call `try_gen` first to check it.
     pub fn init(gui_start: bool, path: Option<PathBuf>) -> Self {
         Self {
             gui_start,
-            path,
+            source: path.map_or(WorldSource::Embedded, WorldSource::Path),
+            score_map: None,
+        }
+    }
+    /// This function creates a world generator from the content of a world file,
+    /// e.g. one uploaded in the browser.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            gui_start: false,
+            source: WorldSource::Bytes(bytes),
             score_map: None,
         }
     }
@@ -142,27 +194,141 @@ impl WorldgeneratorUnwrap {
     pub fn set_score_hashmap(&mut self, score_map: HashMap<Content, f32>) {
         self.score_map = Some(score_map);
     }
+    /// Loads and validates the world, without panicking.
+    pub fn try_gen(&mut self) -> Result<GeneratedWorld, WorldLoadError> {
+        let mut loaded = grid_to_world(self.load_grid()?)?;
+        loaded.4 = self.score_map.clone();
+        Ok(loaded)
+    }
+    // the world as it is at the start, to be saved in a snapshot
+    pub(crate) fn saved_world(&self) -> Result<SavedWorld, WorldLoadError> {
+        let (tiles, _, _, max_score, _) = grid_to_world(self.load_grid()?)?;
+        Ok(SavedWorld {
+            tiles,
+            max_score,
+            score_map: self.score_map.clone(),
+        })
+    }
+    fn load_grid(&self) -> Result<Grid, WorldLoadError> {
+        match &self.source {
+            WorldSource::Embedded => parse_grid(WORLD_DATA),
+            WorldSource::Path(path) => load_as_grid(path),
+            WorldSource::Bytes(bytes) => parse_grid(bytes),
+        }
+    }
 }
 
 impl robotics_lib::world::world_generator::Generator for WorldgeneratorUnwrap {
-    fn gen(
-        &mut self,
-    ) -> (
-        Vec<Vec<Tile>>,
-        (usize, usize),
-        EnvironmentalConditions,
-        f32,
-        Option<HashMap<Content, f32>>,
-    ) {
+    fn gen(&mut self) -> GeneratedWorld {
         if self.gui_start {
-            eprintln!("Gui not supported");
-            panic!();
+            log::warn!(target: logging::RUNNER, "Gui not supported, loading the world");
         }
-        let mut loaded = match self.path.clone() {
-            Some(path) => load(path),
-            None => load(PathBuf::new().join("world3.bin")),
-        };
-        loaded.4 = self.score_map.clone();
-        loaded
+        // the trait cannot return an error, every caller checks the world with try_gen before starting the runner
+        self.try_gen().expect("the world is checked with try_gen before the runner starts")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_bytes(grid: &Grid) -> Vec<u8> {
+        bincode::serialize(grid).unwrap()
+    }
+
+    fn valid_grid() -> Grid {
+        let mut grid = Grid::new(2);
+        for row in grid.cells.iter_mut() {
+            for cell in row.iter_mut() {
+                cell.tile_type = Some(TileType::Grass);
+            }
+        }
+        grid.weather = vec![WeatherType::Sunny];
+        grid
+    }
+
+    #[test]
+    fn test_embedded_world_loads(){
+        let loaded = WorldgeneratorUnwrap::init(false, None).try_gen().unwrap();
+        assert!(!loaded.0.is_empty());
+    }
+
+    #[test]
+    fn test_world_from_bytes(){
+        let loaded = WorldgeneratorUnwrap::from_bytes(grid_bytes(&valid_grid())).try_gen().unwrap();
+        assert_eq!(loaded.0.len(), 2);
+        assert_eq!(loaded.0[1][1].tile_type, TileType::Grass);
+    }
+
+    #[test]
+    fn test_bad_bincode(){
+        let result = WorldgeneratorUnwrap::from_bytes(vec![1, 2, 3]).try_gen();
+        assert!(matches!(result, Err(WorldLoadError::Format(_))));
+    }
+
+    #[test]
+    fn test_missing_tile_type(){
+        let mut grid = valid_grid();
+        grid.cells[1][0].tile_type = None;
+        let result = WorldgeneratorUnwrap::from_bytes(grid_bytes(&grid)).try_gen();
+        assert_eq!(result.err(), Some(WorldLoadError::MissingTileType { row: 1, col: 0 }));
+    }
+
+    #[test]
+    fn test_empty_weather(){
+        let mut grid = valid_grid();
+        grid.weather.clear();
+        let result = WorldgeneratorUnwrap::from_bytes(grid_bytes(&grid)).try_gen();
+        assert_eq!(result.err(), Some(WorldLoadError::EmptyWeather));
+    }
+
+    #[test]
+    fn test_bad_shape(){
+        let mut grid = valid_grid();
+        grid.cells[1].pop();
+        let result = WorldgeneratorUnwrap::from_bytes(grid_bytes(&grid)).try_gen();
+        assert!(matches!(result, Err(WorldLoadError::Shape(_))));
+
+        let mut grid = valid_grid();
+        grid.cells.clear();
+        let result = WorldgeneratorUnwrap::from_bytes(grid_bytes(&grid)).try_gen();
+        assert!(matches!(result, Err(WorldLoadError::Shape(_))));
+
+        //three rows of two tiles
+        let mut grid = valid_grid();
+        grid.cells.push(grid.cells[0].clone());
+        let result = WorldgeneratorUnwrap::from_bytes(grid_bytes(&grid)).try_gen();
+        assert!(matches!(result, Err(WorldLoadError::Shape(_))));
+    }
+
+    #[test]
+    fn test_robot_out_of_bounds(){
+        let mut grid = valid_grid();
+        grid.robot_pos = (0, 2);
+        let result = WorldgeneratorUnwrap::from_bytes(grid_bytes(&grid)).try_gen();
+        assert_eq!(result.err(), Some(WorldLoadError::RobotOutOfBounds { row: 0, col: 2, size: 2 }));
+    }
+
+    #[test]
+    fn test_gen_loads_the_checked_world(){
+        use robotics_lib::world::world_generator::Generator;
+        let mut generator = WorldgeneratorUnwrap::from_bytes(grid_bytes(&valid_grid()));
+        let checked = generator.try_gen().unwrap();
+        let loaded = generator.gen();
+        assert_eq!(loaded.0, checked.0);
+        assert_eq!(loaded.1, checked.1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_gen_panics_on_an_unchecked_world(){
+        use robotics_lib::world::world_generator::Generator;
+        WorldgeneratorUnwrap::from_bytes(vec![1, 2, 3]).gen();
+    }
+
+    #[test]
+    fn test_missing_file(){
+        let result = WorldgeneratorUnwrap::init(false, Some(PathBuf::from("does/not/exist.bin"))).try_gen();
+        assert!(matches!(result, Err(WorldLoadError::Io(_))));
     }
 }